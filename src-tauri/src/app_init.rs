@@ -3,7 +3,9 @@ use std::sync::Arc;
 use tauri::{AppHandle, Manager, State};
 use tokio::sync::Mutex;
 
-use crate::tantivy_file_indexer::service_container::AppServiceContainer;
+use crate::{
+    shared::app_error::AppResult, tantivy_file_indexer::service_container::AppServiceContainer,
+};
 
 pub struct IsAppRunning {
     pub running: Arc<Mutex<bool>>,
@@ -11,7 +13,7 @@ pub struct IsAppRunning {
 
 /// Check to see if the backend is fully initialized and all state is managed
 #[tauri::command]
-pub async fn is_running(is_running: State<'_, IsAppRunning>) -> Result<bool, String> {
+pub async fn is_running(is_running: State<'_, IsAppRunning>) -> AppResult<bool> {
     let running = *is_running.running.lock().await;
     Ok(running)
 }
@@ -1,11 +1,14 @@
 use crate::directory_nav_service::dtos::inline_query_dto::InlineQueryDTO;
-use crate::shared::models::sys_file_model::SystemFileModel;
+use crate::shared::{
+    app_error::{AppError, AppResult},
+    models::sys_file_model::SystemFileModel,
+};
 
 #[tauri::command]
 pub async fn search_files_inline(
     _query: InlineQueryDTO,
     //files_display: State<'_, Arc<FilesDisplayState>>,
-) -> Result<Vec<SystemFileModel>, String> {
+) -> AppResult<Vec<SystemFileModel>> {
     //Ok(files_display.query(query).await)
-    Err("Inline search for backend is disabled".into())
+    Err(AppError::Internal("Inline search for backend is disabled".into()))
 }
@@ -3,6 +3,8 @@ use copypasta::{ClipboardContext, ClipboardProvider};
 use std::fs;
 use std::path::Path;
 
+use crate::shared::app_error::{AppError, AppResult};
+
 // Operations such as moving files, deleting, etc. the basics
 // TODO: provide Tauri commands
 
@@ -46,17 +48,18 @@ pub fn files_exist_in_clipboard() -> bool {
     is_format_avail(formats::FileList.into())
 }
 
-pub fn paste_files_to_directory(destination_dir: &str) -> Result<(), String> {
+pub fn paste_files_to_directory(destination_dir: &str) -> AppResult<()> {
     // Open the clipboard
-    let _clipboard = Clipboard::new_attempts(10).map_err(|e| e.to_string())?;
+    let _clipboard =
+        Clipboard::new_attempts(10).map_err(|e| AppError::Internal(e.to_string()))?;
     // Ensure the clipboard contains a file list
     if !is_format_avail(formats::FileList.into()) {
-        return Err("No files found in the clipboard".to_string());
+        return Err(AppError::NotFound("No files found in the clipboard".to_string()));
     }
 
     // Read the file paths from the clipboard
     let file_paths: Vec<String> = get_clipboard(formats::FileList)
-        .map_err(|e| e.to_string())?
+        .map_err(|e| AppError::Internal(e.to_string()))?
         .into_iter()
         .collect();
 
@@ -65,26 +68,26 @@ pub fn paste_files_to_directory(destination_dir: &str) -> Result<(), String> {
         let source_path = Path::new(&file_path);
         let file_name = source_path
             .file_name()
-            .ok_or_else(|| format!("Invalid file path: {}", file_path))?;
+            .ok_or_else(|| AppError::Internal(format!("Invalid file path: {}", file_path)))?;
         let target_path = Path::new(destination_dir).join(file_name);
 
-        fs::copy(&source_path, &target_path)
-            .map_err(|e| format!("Failed to copy file {}: {}", file_path, e))?;
+        fs::copy(source_path, &target_path)
+            .map_err(|e| AppError::from_io_with_path(e, &file_path))?;
     }
 
     println!("Files pasted into directory: {}", destination_dir);
     Ok(())
 }
 
-pub fn create_new_file(directory: &str, file_name: &str) -> Result<(), String> {
+pub fn create_new_file(directory: &str, file_name: &str) -> AppResult<()> {
     let path = Path::new(directory).join(file_name);
-    fs::File::create(path).map_err(|e| e.to_string())?;
+    fs::File::create(&path).map_err(|e| AppError::from_io_with_path(e, &path.to_string_lossy()))?;
     Ok(())
 }
 
-pub fn create_new_directory(directory: &str, directory_name: &str) -> Result<(), String> {
+pub fn create_new_directory(directory: &str, directory_name: &str) -> AppResult<()> {
     let path = Path::new(directory).join(directory_name);
-    fs::create_dir_all(path).map_err(|e| e.to_string())?;
+    fs::create_dir_all(&path).map_err(|e| AppError::from_io_with_path(e, &path.to_string_lossy()))?;
     Ok(())
 }
 
@@ -1,22 +1,25 @@
 use std::fs::{self, File};
 use std::io::{Read, Seek, SeekFrom};
 
-pub fn read_file_bytes(file_path: String, buffer_size: usize) -> Result<Vec<u8>, String> {
-    let mut file = File::open(&file_path).map_err(|err| format!("Error opening file: {}", err))?;
+use crate::shared::app_error::{AppError, AppResult};
+
+pub fn read_file_bytes(file_path: String, buffer_size: usize) -> AppResult<Vec<u8>> {
+    let mut file =
+        File::open(&file_path).map_err(|err| AppError::from_io_with_path(err, &file_path))?;
 
     let mut buffer = vec![0; buffer_size];
 
     let bytes_read = file
         .read(&mut buffer)
-        .map_err(|err| format!("Error reading file: {}", err))?;
+        .map_err(|err| AppError::from_io_with_path(err, &file_path))?;
 
     buffer.truncate(bytes_read);
 
     Ok(buffer)
 }
 
-pub fn read_file(file_path: String) -> Result<String, String> {
-    fs::read_to_string(&file_path).map_err(|err| format!("Error reading file: {}", err))
+pub fn read_file(file_path: String) -> AppResult<String> {
+    fs::read_to_string(&file_path).map_err(|err| AppError::from_io_with_path(err, &file_path))
 }
 
 pub fn read_file_range_bytes(
@@ -37,8 +40,11 @@ pub fn read_file_range(
     file_path: String,
     start: u64,
     length: usize,
-) -> Result<String, String> {
-    let buffer = read_file_range_bytes(file_path, start, length).map_err(|err| format!("Error reading file range: {}",err))?;
-    let utf = String::from_utf8(buffer).map_err(|err| format!("Error converting buffer data to utf8: {}",err))?;
+) -> AppResult<String> {
+    let buffer = read_file_range_bytes(file_path.clone(), start, length)
+        .map_err(|err| AppError::from_io_with_path(err, &file_path))?;
+    let utf = String::from_utf8(buffer).map_err(|err| {
+        AppError::Internal(format!("Error converting buffer data to utf8: {}", err))
+    })?;
     Ok(utf)
 }
@@ -1,5 +1,7 @@
 use std::path::Path;
 
+use crate::shared::app_error::{AppError, AppResult};
+
 use super::{super::super::super::util::path_ops, cmd_prompt, common, file_reader, metadata};
 
 #[tauri::command]
@@ -30,24 +32,24 @@ pub fn get_parent_directory(file_path: &str) -> String {
 
 /// Returns a Uint8Array
 #[tauri::command]
-pub fn read_file_bytes(file_path: String, buffer_size:usize)-> Result<Vec<u8>, String>{
+pub fn read_file_bytes(file_path: String, buffer_size:usize)-> AppResult<Vec<u8>>{
     file_reader::read_file_bytes(file_path, buffer_size)
 }
 
 /// Returns a String and yields the entire file contents
 #[tauri::command]
-pub fn read_file(file_path: String)-> Result<String, String>{
+pub fn read_file(file_path: String)-> AppResult<String>{
     file_reader::read_file(file_path)
 }
 
 #[tauri::command]
-pub fn read_file_range(file_path: String, start:u64, length:usize)-> Result<String, String>{
-    file_reader::read_file_range(file_path, start, length).map_err(|err| err.to_string())
+pub fn read_file_range(file_path: String, start:u64, length:usize)-> AppResult<String>{
+    file_reader::read_file_range(file_path, start, length)
 }
 
 #[tauri::command]
-pub fn read_file_range_bytes(file_path: String, start:u64, length:usize)-> Result<Vec<u8>, String>{
-    file_reader::read_file_range_bytes(file_path, start, length).map_err(|err| err.to_string())
+pub fn read_file_range_bytes(file_path: String, start:u64, length:usize)-> AppResult<Vec<u8>>{
+    file_reader::read_file_range_bytes(file_path.clone(), start, length).map_err(|err| AppError::from_io_with_path(err, &file_path))
 }
 
 /**
@@ -60,11 +62,11 @@ pub fn is_path_a_file(file_path: String) -> bool {
 }
 
 #[tauri::command]
-pub async fn open_file(file_path: String) -> Result<(), String> {
+pub async fn open_file(file_path: String) -> AppResult<()> {
     tokio::process::Command::new("cmd")
         .args(["/C", "start", "", &file_path])
         .spawn()
-        .map_err(|x| x.to_string())?;
+        .map_err(|err| AppError::from_io_with_path(err, &file_path))?;
     Ok(())
 }
 
@@ -75,28 +77,31 @@ pub fn is_directory_accessible(dir_path:String)->bool{
 
 // Common commands:
 #[tauri::command]
-pub fn move_path_into_directory(target_dir: String, source_path: String)->Result<(), String>{
-    common::move_path_into_directory(Path::new(&target_dir), Path::new(&source_path)).map_err(|err|err.to_string())
+pub fn move_path_into_directory(target_dir: String, source_path: String)->AppResult<()>{
+    common::move_path_into_directory(Path::new(&target_dir), Path::new(&source_path)).map_err(|err| AppError::from_io_with_path(err, &source_path))
 }
 
 #[tauri::command]
-pub fn delete_file(file_path:String)->Result<(), String>{
-    common::delete_path(&file_path).map_err(|err|err.to_string())
+pub fn delete_file(file_path:String)->AppResult<()>{
+    if !Path::new(&file_path).exists() {
+        return Err(AppError::NotFound(file_path));
+    }
+    Ok(common::delete_path(&file_path)?)
 }
 
 #[tauri::command]
-pub fn open_in_explorer(path: String) -> Result<(), String>{
+pub fn open_in_explorer(path: String) -> AppResult<()>{
     cmd_prompt::open_in_explorer(&path)
 }
 
 #[tauri::command]
-pub fn copy_paths_to_clipboard(paths: Vec<String>) -> Result<(), String>{
-    common::copy_paths_to_clipboard(paths).map_err(|err| err.to_string())
+pub fn copy_paths_to_clipboard(paths: Vec<String>) -> AppResult<()>{
+    common::copy_paths_to_clipboard(paths).map_err(AppError::Internal)
 }
 
 #[tauri::command]
-pub fn paste_files_to_directory(destination_dir: String) -> Result<(), String>{
-    common::paste_files_to_directory(&destination_dir).map_err(|err| err.to_string())
+pub fn paste_files_to_directory(destination_dir: String) -> AppResult<()>{
+    common::paste_files_to_directory(&destination_dir)
 }
 
 #[tauri::command]
@@ -105,12 +110,12 @@ pub fn files_exist_in_clipboard() -> bool {
 }
 
 #[tauri::command]
-pub fn create_new_file(directory: String, file_name: String) -> Result<(), String>{
-    common::create_new_file(&directory, &file_name).map_err(|err| err.to_string())
+pub fn create_new_file(directory: String, file_name: String) -> AppResult<()>{
+    common::create_new_file(&directory, &file_name)
 }
 
 #[tauri::command]
-pub fn create_new_directory(directory: String, directory_name: String) -> Result<(), String>{
-    common::create_new_directory(&directory, &directory_name).map_err(|err| err.to_string())
+pub fn create_new_directory(directory: String, directory_name: String) -> AppResult<()>{
+    common::create_new_directory(&directory, &directory_name)
 }
 
@@ -22,6 +22,12 @@ pub fn create_file_model_from_path(file_path: PathBuf) -> Option<SystemFileModel
                 size,
                 score: 0.0,
                 is_directory,
+                audio_tags: None,
+                document: None,
+                symbols: None,
+                container_path: None,
+                is_symlink: false,
+                link_target: None,
             })
         }
         Err(err) => {
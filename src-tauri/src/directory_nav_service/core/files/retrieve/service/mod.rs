@@ -8,7 +8,10 @@ use crate::{
         core::files::retrieve::{file_retriever, helper},
         dtos::get_files_dtos::GetFilesParamsDTO,
     },
-    shared::models::sys_file_model::SystemFileModel,
+    shared::{
+        app_error::{AppError, AppResult},
+        models::sys_file_model::SystemFileModel,
+    },
     tantivy_file_indexer::shared::cancel_task::CancellableTask,
 };
 
@@ -26,7 +29,12 @@ impl FileRetrieverService {
             active_dir: Arc::new(RwLock::new(None)),
         }
     }
-    pub async fn run_get_files_as_models(&self, directory: String, params: GetFilesParamsDTO) {
+    /// Fails if the directory could not be read. Being cancelled by a newer call is not treated as an error
+    pub async fn run_get_files_as_models(
+        &self,
+        directory: String,
+        params: GetFilesParamsDTO,
+    ) -> AppResult<()> {
         // First cancel any existing task
         self.get_files_task.cancel().await;
         {
@@ -37,17 +45,17 @@ impl FileRetrieverService {
         let handle = self.app_handle.clone();
         let active_dir = Arc::clone(&self.active_dir);
 
-        if let Err(e) = self
+        let result = self
             .get_files_task
             .run(tokio::spawn(async move {
-                let result = get_files_as_models(directory, params, handle, active_dir).await;
-                if let Err(err) = result {
-                    println!("Error when getting files as models: {}", err);
-                }
+                get_files_as_models(directory, params, handle, active_dir).await
             }))
             .await
-        {
-            println!("Get files task error: {}", e);
+            .map_err(AppError::from_task_error)
+            .and_then(|result| result);
+        match result {
+            Ok(_) | Err(AppError::Cancelled) => Ok(()),
+            Err(err) => Err(err),
         }
     }
 }
@@ -62,7 +70,7 @@ async fn get_files_as_models(
     params: GetFilesParamsDTO,
     app_handle: AppHandle,
     active_dir: Arc<RwLock<Option<String>>>,
-) -> Result<i32, String> {
+) -> AppResult<i32> {
     let path = Path::new(&directory);
 
     // Output files as we get to them
@@ -91,7 +99,7 @@ async fn get_files_as_models(
         })
     })
     .await
-    .map_err(|err| err.to_string())?;
+    .map_err(|err| AppError::from_io_with_path(err, &directory))?;
 
     Ok(num_files)
 }
@@ -1,5 +1,6 @@
 use crate::directory_nav_service::core::files::retrieve::service::FileRetrieverService;
 use crate::directory_nav_service::dtos::get_files_dtos::GetFilesParamsDTO;
+use crate::shared::app_error::AppResult;
 use std::sync::Arc;
 use tauri::State;
 
@@ -8,7 +9,6 @@ pub async fn get_files_as_models(
     directory: String,
     params: GetFilesParamsDTO,
     retriever: State<'_, Arc<FileRetrieverService>>,
-) -> Result<(), String> {
-    retriever.run_get_files_as_models(directory, params).await;
-    Ok(())
+) -> AppResult<()> {
+    retriever.run_get_files_as_models(directory, params).await
 }
@@ -1,4 +1,5 @@
 use super::service::DirectoryWatcherService;
+use crate::shared::app_error::AppResult;
 use std::{path::Path, sync::Arc};
 use tauri::{AppHandle, Emitter, State};
 
@@ -8,7 +9,7 @@ pub async fn watch_directory(
     path: String,
     app_handle: AppHandle,
     watcher_service: State<'_, Arc<DirectoryWatcherService>>,
-) -> Result<String,()> {
+) -> AppResult<String> {
     let path = Path::new(&path);
     let ident = "directory_watcher_event".to_string();
     let ident_clone = ident.clone();
@@ -25,7 +26,7 @@ pub async fn watch_directory(
 #[tauri::command]
 pub async fn stop_watching_directory(
     watcher_service: State<'_, Arc<DirectoryWatcherService>>,
-) -> Result<(), String> {
+) -> AppResult<()> {
     watcher_service.stop_watching().await;
     Ok(())
 }
@@ -3,6 +3,7 @@ use tantivy_file_indexer::{
     services::local_crawler::tauri_exports::*, /*services::local_db::tables::files::tauri_exports::*,*/
     services::local_db::tables::app_kv_store::tauri_exports::*,
     services::local_db::tauri_exports::*, services::search_index::tauri_exports::*,
+    services::volumes::tauri_exports::*,
 };
 use tauri::Manager;
 mod app_init;
@@ -30,6 +31,9 @@ pub fn run() {
             search_index_query,
             search_index_query_streaming,
             search_index_query_streaming_organized,
+            cancel_search,
+            get_query_cache_metrics,
+            get_volumes,
             //get_num_stored_files,
             //save_json_local, REMOVED IN FAVOR OF KV STORAGE
             //load_json_local, REMOVED IN FAVOR OF KV STORAGE
@@ -37,7 +41,12 @@ pub fn run() {
             add_dirs_to_crawler_queue,
             view_crawler_queue,
             view_crawler_priority_counts,
-            //get_crawler_analyzer_data,
+            get_index_roots,
+            add_index_root,
+            update_index_root,
+            remove_index_root,
+            crawler_status,
+            report_ui_activity,
             app_init::is_running,
             is_directory_accessible,
             get_file_from_index,
@@ -53,6 +62,10 @@ pub fn run() {
             kv_store_get,
             kv_store_subscribe_to_key,
             dispatch_crawlers,
+            pause_crawlers,
+            resume_crawlers,
+            stop_crawlers,
+            restart_crawlers,
             watch_directory,
             stop_watching_directory,
             get_file_icon,
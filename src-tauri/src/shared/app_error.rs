@@ -0,0 +1,223 @@
+use std::{fmt, io};
+
+use serde::Serialize;
+
+use crate::tantivy_file_indexer::shared::cancel_task::CANCELLED_MESSAGE;
+
+/// The error that every Tauri command returns. Gets sent to the frontend like this, so that it can react to the code
+/// instead of having to parse messages:
+///
+/// ```json
+/// { "Code": "NotFound", "Details": "C:\\Users\\me\\missing.txt" }
+/// ```
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "Code", content = "Details")]
+pub enum AppError {
+    NotFound(String),
+    PermissionDenied(String),
+    /// Something that was asked for can't be done with the given arguments, such as indexing a file as a root
+    InvalidArgument(String),
+    /// Whatever was going to be created or added is already there
+    AlreadyExists(String),
+    /// The search query could not be parsed or built
+    InvalidQuery(String),
+    /// The search index could not be read from or written to
+    IndexUnavailable(String),
+    DbError(String),
+    /// The task was stopped before it could finish, usually because a newer task replaced it
+    Cancelled,
+    /// Anything that the frontend can't do much about, other than show the details
+    Internal(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    /// The machine-readable name of the error, which is the same as the `Code` that gets sent to the frontend
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "NotFound",
+            AppError::PermissionDenied(_) => "PermissionDenied",
+            AppError::InvalidArgument(_) => "InvalidArgument",
+            AppError::AlreadyExists(_) => "AlreadyExists",
+            AppError::InvalidQuery(_) => "InvalidQuery",
+            AppError::IndexUnavailable(_) => "IndexUnavailable",
+            AppError::DbError(_) => "DbError",
+            AppError::Cancelled => "Cancelled",
+            AppError::Internal(_) => "Internal",
+        }
+    }
+
+    pub fn details(&self) -> &str {
+        match self {
+            AppError::NotFound(details)
+            | AppError::PermissionDenied(details)
+            | AppError::InvalidArgument(details)
+            | AppError::AlreadyExists(details)
+            | AppError::InvalidQuery(details)
+            | AppError::IndexUnavailable(details)
+            | AppError::DbError(details)
+            | AppError::Internal(details) => details,
+            AppError::Cancelled => CANCELLED_MESSAGE,
+        }
+    }
+
+    /// For the errors that `CancellableTask::run` returns, which are either a cancellation or a failed task
+    pub fn from_task_error(err: String) -> Self {
+        if err == CANCELLED_MESSAGE {
+            AppError::Cancelled
+        } else {
+            AppError::Internal(err)
+        }
+    }
+
+    /// Same as converting from an IO error, but with the path that the error happened on added to the details
+    pub fn from_io_with_path(err: io::Error, path: &str) -> Self {
+        let details = format!("{}: {}", path, err);
+        match AppError::from(err) {
+            AppError::NotFound(_) => AppError::NotFound(details),
+            AppError::PermissionDenied(_) => AppError::PermissionDenied(details),
+            AppError::AlreadyExists(_) => AppError::AlreadyExists(details),
+            _ => AppError::Internal(details),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.details())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => AppError::NotFound(err.to_string()),
+            io::ErrorKind::PermissionDenied => AppError::PermissionDenied(err.to_string()),
+            io::ErrorKind::AlreadyExists => AppError::AlreadyExists(err.to_string()),
+            _ => AppError::Internal(err.to_string()),
+        }
+    }
+}
+
+impl From<tantivy::TantivyError> for AppError {
+    fn from(err: tantivy::TantivyError) -> Self {
+        match err {
+            tantivy::TantivyError::InvalidArgument(_)
+            | tantivy::TantivyError::FieldNotFound(_)
+            | tantivy::TantivyError::SchemaError(_) => AppError::InvalidQuery(err.to_string()),
+            _ => AppError::IndexUnavailable(err.to_string()),
+        }
+    }
+}
+
+impl From<tantivy::query::QueryParserError> for AppError {
+    fn from(err: tantivy::query::QueryParserError) -> Self {
+        AppError::InvalidQuery(err.to_string())
+    }
+}
+
+impl From<sea_orm::DbErr> for AppError {
+    fn from(err: sea_orm::DbErr) -> Self {
+        AppError::DbError(err.to_string())
+    }
+}
+
+impl From<trash::Error> for AppError {
+    fn from(err: trash::Error) -> Self {
+        AppError::Internal(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+
+    use super::*;
+    use crate::tantivy_file_indexer::services::local_db::service::LocalDbService;
+
+    #[test]
+    fn errors_are_sent_as_a_code_and_details() {
+        let cases = [
+            (
+                AppError::NotFound("C:\\Users\\me\\missing.txt".to_string()),
+                r#"{"Code":"NotFound","Details":"C:\\Users\\me\\missing.txt"}"#,
+            ),
+            (
+                AppError::InvalidArgument("C:\\notes.txt is not a directory".to_string()),
+                r#"{"Code":"InvalidArgument","Details":"C:\\notes.txt is not a directory"}"#,
+            ),
+            (
+                AppError::AlreadyExists("C:\\docs is already an index root".to_string()),
+                r#"{"Code":"AlreadyExists","Details":"C:\\docs is already an index root"}"#,
+            ),
+            (AppError::Cancelled, r#"{"Code":"Cancelled"}"#),
+        ];
+        for (err, expected) in cases {
+            assert_eq!(serde_json::to_string(&err).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn codes_match_what_gets_sent() {
+        let errors = [
+            AppError::NotFound(String::new()),
+            AppError::PermissionDenied(String::new()),
+            AppError::InvalidArgument(String::new()),
+            AppError::AlreadyExists(String::new()),
+            AppError::InvalidQuery(String::new()),
+            AppError::IndexUnavailable(String::new()),
+            AppError::DbError(String::new()),
+            AppError::Cancelled,
+            AppError::Internal(String::new()),
+        ];
+        for err in errors {
+            let sent = serde_json::to_value(&err).unwrap();
+            assert_eq!(sent["Code"], err.code());
+        }
+    }
+
+    #[tokio::test]
+    async fn database_failures_are_sent_as_db_errors() {
+        let db_path =
+            std::env::temp_dir().join(format!("app_error_test_{}.db", uuid::Uuid::new_v4()));
+        let db_url = format!("sqlite://{}?mode=rwc", db_path.to_string_lossy());
+        let db = LocalDbService::connect(&db_url, None).await.unwrap();
+        // Dropped behind the back of the service, so that reading the roots fails the same way a broken database would
+        let connection = sea_orm::Database::connect(&db_url).await.unwrap();
+        connection
+            .execute_unprepared("DROP TABLE index_roots")
+            .await
+            .unwrap();
+
+        let err = AppError::from(db.index_roots_table().get_all().await.unwrap_err());
+        drop(connection);
+        drop(db);
+        let _ = std::fs::remove_file(db_path);
+
+        let sent = serde_json::to_value(&err).unwrap();
+        assert_eq!(sent["Code"], "DbError");
+        assert!(sent["Details"].as_str().unwrap().contains("index_roots"));
+    }
+
+    #[test]
+    fn io_errors_keep_their_kind() {
+        let err = io::Error::new(io::ErrorKind::AlreadyExists, "exists");
+        assert_eq!(
+            AppError::from_io_with_path(err, "C:\\a.txt"),
+            AppError::AlreadyExists("C:\\a.txt: exists".to_string())
+        );
+        let err = io::Error::new(io::ErrorKind::NotFound, "missing");
+        assert_eq!(
+            AppError::from(err),
+            AppError::NotFound("missing".to_string())
+        );
+        let err = io::Error::other("broken");
+        assert_eq!(
+            AppError::from(err),
+            AppError::Internal("broken".to_string())
+        );
+    }
+}
@@ -1,4 +1,7 @@
 pub mod models {
+    pub mod audio_tags_model;
+    pub mod document_metadata_model;
     pub mod sys_file_model;
 }
+pub mod app_error;
 pub mod converters;
\ No newline at end of file
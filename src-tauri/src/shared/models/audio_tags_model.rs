@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Tags read from an audio file's metadata block (ID3, Vorbis comments or MP4 atoms)
+#[derive(Clone, Deserialize, Serialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct AudioTagsModel {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub title: Option<String>,
+    pub genre: Option<String>,
+    /// The length of the track, in seconds
+    pub duration: u64,
+}
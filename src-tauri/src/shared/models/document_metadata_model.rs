@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Metadata and text read from PDF, Office and OpenDocument files
+#[derive(Clone, Deserialize, Serialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct DocumentMetadataModel {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// The number of pages. For spreadsheets this is the number of sheets, and for presentations the number of slides
+    pub page_count: u64,
+    /// The text of the document. This only gets used for indexing, so it is left out of search results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
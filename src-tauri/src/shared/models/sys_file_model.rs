@@ -6,7 +6,10 @@ use std::{
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::shared::converters::system_time_to_chrono_datetime;
+use crate::shared::{
+    converters::system_time_to_chrono_datetime,
+    models::{audio_tags_model::AudioTagsModel, document_metadata_model::DocumentMetadataModel},
+};
 
 // TODO: move this to the models folder
 #[derive(Clone, Deserialize, Serialize, Debug)]
@@ -20,6 +23,23 @@ pub struct SystemFileModel {
     pub size: u64,
     pub score: f32, // Consider making popularity more elaborate
     pub is_directory: bool,
+    /// Only present for audio files that had readable tags when they were crawled
+    #[serde(default)]
+    pub audio_tags: Option<AudioTagsModel>,
+    /// Only present for PDF, Office and OpenDocument files that could be parsed when they were crawled
+    #[serde(default)]
+    pub document: Option<DocumentMetadataModel>,
+    /// The names of the functions, structs, classes, etc. defined in a source file. Only filled in while indexing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbols: Option<Vec<String>>,
+    /// For files that live inside of an archive, this is the path of the archive. The file path will look like `backup.zip!/docs/report.pdf`
+    #[serde(default)]
+    pub container_path: Option<String>,
+    #[serde(default)]
+    pub is_symlink: bool,
+    /// Where a symlink points to, as it is written in the link. `None` for anything that isn't a symlink
+    #[serde(default)]
+    pub link_target: Option<String>,
 }
 
 impl SystemFileModel {
@@ -64,7 +84,13 @@ impl SystemFileModel {
             date_created,
             size,
             score: 1.0, // Assuming score doesn't matter here
-            is_directory: path.is_dir(),
+            is_directory: meta.is_dir(),
+            audio_tags: None,
+            document: None,
+            symbols: None,
+            container_path: None,
+            is_symlink: false,
+            link_target: None,
         };
         Ok(model)
     }
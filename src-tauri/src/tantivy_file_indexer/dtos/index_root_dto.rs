@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// What the frontend sends to add or update an index root
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct IndexRootDTO {
+    pub path: String,
+    pub enabled: bool,
+    pub max_depth: Option<u32>,
+    pub priority: u32,
+    pub refresh_interval_minutes: Option<u32>,
+    pub follow_symlinks: bool,
+    pub include_hidden: bool,
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 use tantivy::time::OffsetDateTime;
 use super::super::enums::search_query_type::SearchQueryType;
@@ -12,7 +14,19 @@ pub struct SearchParamsDTO {
     pub file_path: Option<String>,
 
     pub num_results: u32,
-    pub query_type: SearchQueryType
+    pub query_type: SearchQueryType,
+    /// How long the query may run for before whatever was found so far gets returned as partial results
+    #[serde(default)]
+    pub time_budget_ms: Option<u64>,
+}
+
+/// Used when the frontend doesn't give a time budget
+const DEFAULT_TIME_BUDGET_MS: u64 = 10_000;
+
+impl SearchParamsDTO {
+    pub fn time_budget(&self) -> Duration {
+        Duration::from_millis(self.time_budget_ms.unwrap_or(DEFAULT_TIME_BUDGET_MS))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -4,6 +4,7 @@ mod enums {
 }
 mod dtos {
     pub mod add_to_crawler_queue;
+    pub mod index_root_dto;
     pub mod search_params_dto;
     pub mod streaming_search_dto;
 }
@@ -12,13 +13,20 @@ pub mod services {
     pub mod local_crawler;
     pub mod local_db;
     pub mod search_index;
+    pub mod volumes;
 }
 mod models {
     pub mod auto_serializing_value;
+    pub mod crawler_status_model;
     pub mod emit_metadata_model;
+    pub mod index_validator_model;
+    pub mod search_done_model;
+    pub mod search_results_model;
     pub mod vector_search_params_model;
 }
 mod util {
+    pub mod file_id;
+    pub mod glob;
     pub mod path;
     pub mod string;
 }
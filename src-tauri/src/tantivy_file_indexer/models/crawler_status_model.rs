@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Returned by `crawler_status`, and emitted periodically as the `crawler_status` event while the crawlers are running
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct CrawlerStatusModel {
+    pub files_per_second: f64,
+    pub directories_per_second: f64,
+    /// The number of directories waiting in the crawler queue for each priority
+    pub queue_depth: HashMap<u32, i64>,
+    /// The errors that happened within the last minute, oldest first
+    pub recent_errors: Vec<CrawlerErrorModel>,
+    pub workers: Vec<CrawlerWorkerStatusModel>,
+    pub throttle: CrawlerThrottleModel,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct CrawlerErrorModel {
+    pub message: String,
+    pub time: DateTime<Utc>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct CrawlerWorkerStatusModel {
+    pub id: usize,
+    /// The directory that the worker is crawling, or `None` if it is between directories or waiting for work
+    pub current_directory: Option<String>,
+    pub uptime_secs: u64,
+    /// Paused workers have committed everything that they crawled, and wait to be resumed or stopped
+    pub is_paused: bool,
+}
+
+/// How much the crawlers are slowed down, and what the decision was based on
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct CrawlerThrottleModel {
+    /// Between 0, which is full speed, and 1, which is as slow as the crawlers go
+    pub level: f32,
+    /// What called for the level the most: `None`, `CPU usage`, `Disk I/O`, `On battery`, `User activity` or
+    /// `Disabled`
+    pub reason: String,
+    /// How long each crawler rests after each file
+    pub file_rest_millis: u64,
+    pub recommended_workers: u32,
+    /// As a percentage
+    pub cpu_usage: f32,
+    /// The share of time that the CPU spent waiting on the disk, as a percentage. `None` if it isn't known on this
+    /// platform
+    pub io_wait: Option<f32>,
+    /// `None` if it isn't known whether there is a battery
+    pub on_battery: Option<bool>,
+    /// Whether the user interacted with the app recently
+    pub user_active: bool,
+}
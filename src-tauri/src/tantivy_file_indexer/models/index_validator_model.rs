@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Where the index validator is at and what it found. Stored in the KV table, so that the validator picks up where it
+/// left off after a restart, and so that the frontend can subscribe to it
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct IndexValidatorModel {
+    /// The path that the current sweep got up to. Empty before a sweep starts
+    pub cursor: String,
+    /// `None` while the validator is waiting for the next sweep
+    pub sweep_started_at: Option<DateTime<Utc>>,
+    pub last_sweep_finished_at: Option<DateTime<Utc>>,
+    /// What the current sweep found so far
+    pub current: IndexValidatorFindingsModel,
+    /// What the last finished sweep found
+    pub last_sweep: Option<IndexValidatorFindingsModel>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct IndexValidatorFindingsModel {
+    pub num_checked: u64,
+    /// Files that no longer exist, which got removed from the index
+    pub num_removed: u64,
+    /// Files that changed since they were indexed, whose directories got pushed to the crawler queue
+    pub num_changed: u64,
+    /// Files on drives that aren't connected, which were left alone
+    pub num_offline: u64,
+    /// The last few paths that got removed
+    pub recently_removed: Vec<String>,
+}
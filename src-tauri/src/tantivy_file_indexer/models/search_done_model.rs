@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+use crate::shared::app_error::AppError;
+
+/// Emitted to the frontend once a search stream stops sending results
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct SearchDoneModel {
+    /// The search query, in the same way as `EmitMetadataModel::metadata`
+    pub metadata: String,
+    /// Whether the search was stopped early because another search on the same stream started or `cancel_search` was called
+    pub cancelled: bool,
+    /// Whether the search ran out of time or was cancelled before it could look at every match
+    pub partial: bool,
+    pub error: Option<AppError>,
+}
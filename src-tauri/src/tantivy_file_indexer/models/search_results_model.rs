@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+use crate::shared::models::sys_file_model::SystemFileModel;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct SearchResultsModel {
+    pub results: Vec<SystemFileModel>,
+    /// Whether the query ran out of time before it could look at every match, meaning that better results may have been missed
+    pub partial: bool,
+}
@@ -3,8 +3,9 @@ use crate::directory_nav_service;
 use super::services::{
     app_save::service::{AppSavePath, AppSaveService},
     local_crawler::service::FileCrawlerService,
-    local_db::service::LocalDbService,
+    local_db::{service::LocalDbService, tables::app_kv_store::api::AppKvStoreTable},
     search_index::service::SearchIndexService,
+    volumes::service::VolumeService,
 };
 use std::{path::PathBuf, sync::Arc};
 
@@ -27,20 +28,33 @@ impl AppServiceContainer {
         directory_nav_service::state::manage_state(handle);
 
         //let vector_db_service = Self::initialize_vector_service();
-        let search_service = Self::initialize_search_service(app_path, handle);
-
+        // The local DB comes first since the search service reads its settings from the KV store
         let local_db_service =
             Self::initialize_local_db_service(&app_save_service, handle.clone()).await;
 
+        // The search index marks what it indexes with the volume that it is on, so the volumes come before it
+        let volume_service =
+            Arc::new(VolumeService::new_async(local_db_service.volumes_table().clone()).await);
+
+        let search_service = Self::initialize_search_service(
+            app_path.clone(),
+            handle,
+            local_db_service.kv_store_table().clone(),
+            Arc::clone(&volume_service),
+        );
+
         let crawler_service = Self::initialize_crawler_service(
             Arc::clone(&local_db_service),
             Arc::clone(&search_service),
+            Arc::clone(&volume_service),
+            app_path,
         )
         .await;
 
         handle.manage(Arc::clone(&search_service));
         handle.manage(Arc::clone(&local_db_service));
         handle.manage(Arc::clone(&crawler_service));
+        handle.manage(Arc::clone(&volume_service));
         //handle.manage(Arc::clone(&crawler_analyzer_service));
         //handle.manage(Arc::clone(&vector_db_service));
 
@@ -57,8 +71,12 @@ impl AppServiceContainer {
     fn initialize_search_service(
         app_path: PathBuf,
         app_handle: &AppHandle,
+        kv_store: AppKvStoreTable,
+        volumes: Arc<VolumeService>,
     ) -> Arc<SearchIndexService> {
-        Arc::new(SearchIndexService::new(app_path, app_handle))
+        Arc::new(SearchIndexService::new(
+            app_path, app_handle, kv_store, volumes,
+        ))
     }
 
     fn initialize_app_save_service(save_dir: AppSavePath, app_name: &str) -> Arc<AppSaveService> {
@@ -75,7 +93,9 @@ impl AppServiceContainer {
     async fn initialize_crawler_service(
         db_service: Arc<LocalDbService>,
         search_service: Arc<SearchIndexService>,
+        volumes: Arc<VolumeService>,
+        app_path: PathBuf,
     ) -> Arc<FileCrawlerService> {
-        Arc::new(FileCrawlerService::new_async(db_service, search_service).await)
+        Arc::new(FileCrawlerService::new_async(db_service, search_service, volumes, app_path).await)
     }
 }
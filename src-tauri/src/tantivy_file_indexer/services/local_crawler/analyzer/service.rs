@@ -1,19 +1,37 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     sync::{
         atomic::{AtomicUsize, Ordering},
-        Arc,
+        Mutex,
     },
     time::{Duration, Instant},
 };
 
+use chrono::Utc;
 use tokio::sync::RwLock;
 
+use crate::tantivy_file_indexer::models::crawler_status_model::{
+    CrawlerErrorModel, CrawlerStatusModel, CrawlerThrottleModel, CrawlerWorkerStatusModel,
+};
+
+/// How long errors are reported for after they happen
+const ERROR_WINDOW: Duration = Duration::from_secs(60);
+/// Past this many errors in the window, the oldest ones are dropped
+const MAX_RECENT_ERRORS: usize = 100;
+
+struct Rates {
+    files_per_second: f64,
+    directories_per_second: f64,
+}
+
+/// Collects what the crawlers report, and turns it into rates and a list of recent errors for the crawler status
 pub struct FileCrawlerAnalyzerService {
     analyze_every: u64,
-    last_timestamp: Arc<RwLock<Instant>>,
-    files_processed: Arc<AtomicUsize>,
-    data_points: Arc<RwLock<HashMap<String, String>>>,
+    last_timestamp: RwLock<Instant>,
+    files_processed: AtomicUsize,
+    directories_processed: AtomicUsize,
+    rates: RwLock<Rates>,
+    recent_errors: Mutex<VecDeque<(Instant, CrawlerErrorModel)>>,
 }
 
 impl FileCrawlerAnalyzerService {
@@ -23,9 +41,14 @@ impl FileCrawlerAnalyzerService {
     pub fn new(analyze_every: u64) -> Self {
         Self {
             analyze_every,
-            last_timestamp: Arc::new(RwLock::new(Instant::now())),
-            files_processed: Arc::new(AtomicUsize::new(0)),
-            data_points: Arc::new(RwLock::new(HashMap::new())),
+            last_timestamp: RwLock::new(Instant::now()),
+            files_processed: AtomicUsize::new(0),
+            directories_processed: AtomicUsize::new(0),
+            rates: RwLock::new(Rates {
+                files_per_second: 0.0,
+                directories_per_second: 0.0,
+            }),
+            recent_errors: Mutex::new(VecDeque::new()),
         }
     }
 
@@ -33,28 +56,72 @@ impl FileCrawlerAnalyzerService {
         self.files_processed.fetch_add(val, Ordering::Relaxed);
     }
 
+    pub fn add_to_directories_processed(&self, val: usize) {
+        self.directories_processed.fetch_add(val, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self, message: String) {
+        let mut errors = self.lock_errors();
+        if errors.len() >= MAX_RECENT_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back((
+            Instant::now(),
+            CrawlerErrorModel {
+                message,
+                time: Utc::now(),
+            },
+        ));
+    }
+
+    /// Recomputes the rates if at least `analyze_every` seconds have passed since they were last computed
     pub async fn record_timestamp(&self) {
         let time = self.last_timestamp.read().await.elapsed();
-        if time > Duration::from_secs(self.analyze_every) {
-            let files_processed = self.files_processed.load(Ordering::Relaxed) as u64;
-            let mut data_points_lock = self.data_points.write().await;
-            data_points_lock.insert(
-                "Files Crawled".to_string(),
-                format!("{} files in {:?}", files_processed, time),
-            );
-            data_points_lock.insert(
-                "Files Crawled Per Second".to_string(),
-                (files_processed / time.as_secs()).to_string(),
-            );
-            // Reset the files processed counter
-            self.files_processed.store(0, Ordering::Relaxed);
-            // reset the timestamp
-            let mut timestamp_lock = self.last_timestamp.write().await;
-            *timestamp_lock = Instant::now();
+        if time < Duration::from_secs(self.analyze_every) || time.is_zero() {
+            return;
+        }
+        // Reset the counters
+        let files_processed = self.files_processed.swap(0, Ordering::Relaxed) as f64;
+        let directories_processed = self.directories_processed.swap(0, Ordering::Relaxed) as f64;
+        *self.rates.write().await = Rates {
+            files_per_second: files_processed / time.as_secs_f64(),
+            directories_per_second: directories_processed / time.as_secs_f64(),
+        };
+        // reset the timestamp
+        *self.last_timestamp.write().await = Instant::now();
+    }
+
+    pub async fn get_status(
+        &self,
+        queue_depth: HashMap<u32, i64>,
+        workers: Vec<CrawlerWorkerStatusModel>,
+        throttle: CrawlerThrottleModel,
+    ) -> CrawlerStatusModel {
+        let rates = self.rates.read().await;
+        CrawlerStatusModel {
+            files_per_second: rates.files_per_second,
+            directories_per_second: rates.directories_per_second,
+            queue_depth,
+            recent_errors: self.get_recent_errors(),
+            workers,
+            throttle,
+        }
+    }
+
+    fn get_recent_errors(&self) -> Vec<CrawlerErrorModel> {
+        let mut errors = self.lock_errors();
+        while errors
+            .front()
+            .is_some_and(|(at, _)| at.elapsed() > ERROR_WINDOW)
+        {
+            errors.pop_front();
         }
+        errors.iter().map(|(_, error)| error.clone()).collect()
     }
 
-    pub async fn get_data_points(&self) -> HashMap<String, String> {
-        self.data_points.read().await.clone()
+    fn lock_errors(&self) -> std::sync::MutexGuard<'_, VecDeque<(Instant, CrawlerErrorModel)>> {
+        self.recent_errors
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 }
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use sea_orm::DbErr;
+
+use crate::tantivy_file_indexer::services::local_db::service::LocalDbService;
+
+use super::super::indexing_crawler::plugins::IndexRootsPlugin;
+use super::super::settings::CrawlerSettings;
+use super::{
+    memory_queue::MemoryCrawlerQueue,
+    queue::{CrawlerQueue, Priority},
+};
+
+/// The crawler queue that the crawlers and the index watcher use, which is picked with `CrawlerSettings::sqlite_queue`
+/// when the app starts
+#[derive(Clone)]
+pub enum CrawlerQueueBackend {
+    Memory(MemoryCrawlerQueue),
+    Sqlite(CrawlerQueue),
+}
+
+impl CrawlerQueueBackend {
+    /// Falls back to the SQLite queue if the in-memory queue can't be restored
+    pub async fn new_async(db: Arc<LocalDbService>, index_roots: Arc<IndexRootsPlugin>) -> Self {
+        let sqlite_queue = match CrawlerSettings::get_from_db(db.kv_store_table()).await {
+            Ok(settings) => settings.sqlite_queue,
+            Err(err) => {
+                println!(
+                    "CrawlerQueueBackend: Error reading the crawler settings: {}",
+                    err
+                );
+                false
+            }
+        };
+        if !sqlite_queue {
+            match MemoryCrawlerQueue::load(&db, Arc::clone(&index_roots)).await {
+                Ok(queue) => return Self::Memory(queue),
+                Err(err) => println!(
+                    "CrawlerQueueBackend: Could not restore the crawler queue, so the SQLite queue is used instead: {}",
+                    err
+                ),
+            }
+        }
+        Self::Sqlite(CrawlerQueue::new(db, index_roots))
+    }
+
+    pub async fn push_many(&self, entries: &[(PathBuf, Priority)]) -> Result<(), DbErr> {
+        match self {
+            Self::Memory(queue) => {
+                queue.push_many(entries);
+                Ok(())
+            }
+            Self::Sqlite(queue) => queue.push_many(entries).await,
+        }
+    }
+
+    /// The number of directories in the queue for each priority
+    pub async fn get_priority_counts(&self) -> Result<HashMap<Priority, i64>, DbErr> {
+        match self {
+            Self::Memory(queue) => Ok(queue.get_priority_counts()),
+            Self::Sqlite(queue) => queue.get_priority_counts().await,
+        }
+    }
+
+    /// Writes what hasn't been written to SQLite yet. The SQLite queue is always up to date
+    pub async fn persist(&self) -> Result<(), DbErr> {
+        match self {
+            Self::Memory(queue) => queue.persist().await,
+            Self::Sqlite(_) => Ok(()),
+        }
+    }
+}
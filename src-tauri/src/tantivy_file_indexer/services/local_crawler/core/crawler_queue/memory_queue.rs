@@ -0,0 +1,490 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, Weak},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use sea_orm::DbErr;
+use tokio::sync::Notify;
+
+use crate::tantivy_file_indexer::services::local_db::{
+    service::LocalDbService,
+    tables::crawler_queue::{api::CrawlerQueueTable, entities::indexed_dir},
+};
+
+use super::super::indexing_crawler::plugins::IndexRootsPlugin;
+use super::queue::Priority;
+use super::scheduler::{self, FairScheduler};
+
+/// How often the changes to the queue are written to SQLite
+const PERSIST_INTERVAL: Duration = Duration::from_secs(2);
+/// The most paths that get deleted with one statement, to stay under the SQLite variable limit
+const DELETE_CHUNK_SIZE: usize = 500;
+/// A directory that was pushed within this many seconds doesn't get pushed again. This is what the `recently_indexed`
+/// table does for the SQLite queue
+const RECENTLY_PUSHED_SECS: i64 = 30;
+
+/// The directories in the queue, along with the orderings that the scheduler picks from
+#[derive(Default)]
+struct QueueEntries {
+    entries: HashMap<String, indexed_dir::Model>,
+    /// The entries that aren't taken, from the most urgent to the least urgent
+    by_priority: BTreeSet<(Priority, DateTime<Utc>, String)>,
+    /// The entries that aren't taken, from the longest waiting to the most recent
+    by_age: BTreeSet<(DateTime<Utc>, String)>,
+    /// When each directory was last pushed, in seconds
+    recently_pushed: HashMap<String, i64>,
+    /// The paths that changed since the queue was last written to SQLite. Paths that aren't in `entries` anymore get
+    /// deleted
+    dirty: HashSet<String>,
+}
+
+impl QueueEntries {
+    /// Returns `false` if the directory was pushed too recently to be pushed again
+    fn push(&mut self, path: String, priority: Priority, now: DateTime<Utc>) -> bool {
+        let now_secs = now.timestamp();
+        if self
+            .recently_pushed
+            .get(&path)
+            .is_some_and(|pushed_at| now_secs - pushed_at < RECENTLY_PUSHED_SECS)
+        {
+            return false;
+        }
+        self.recently_pushed.insert(path.clone(), now_secs);
+
+        // Like in the SQLite queue, a directory that is already queued keeps the more urgent priority and the time it
+        // was first added at. Pushing it again makes it available to the crawlers again, even if it was taken
+        let model = match self.remove(&path) {
+            Some(existing) => indexed_dir::Model {
+                priority: existing.priority.min(priority),
+                added_at: existing.added_at.min(now),
+                taken: false,
+                path,
+            },
+            None => indexed_dir::Model {
+                path,
+                priority,
+                taken: false,
+                added_at: now,
+            },
+        };
+        self.dirty.insert(model.path.clone());
+        self.insert(model);
+        true
+    }
+
+    /// Forgets the directories that were pushed long enough ago to be pushed again
+    fn forget_old_pushes(&mut self, now: DateTime<Utc>) {
+        let now_secs = now.timestamp();
+        self.recently_pushed
+            .retain(|_, pushed_at| now_secs - *pushed_at < RECENTLY_PUSHED_SECS);
+    }
+
+    fn insert(&mut self, model: indexed_dir::Model) {
+        if !model.taken {
+            self.by_priority
+                .insert((model.priority, model.added_at, model.path.clone()));
+            self.by_age.insert((model.added_at, model.path.clone()));
+        }
+        self.entries.insert(model.path.clone(), model);
+    }
+
+    fn remove(&mut self, path: &str) -> Option<indexed_dir::Model> {
+        let model = self.entries.remove(path)?;
+        self.by_priority
+            .remove(&(model.priority, model.added_at, model.path.clone()));
+        self.by_age.remove(&(model.added_at, model.path.clone()));
+        Some(model)
+    }
+
+    fn delete(&mut self, path: &str) {
+        if self.remove(path).is_some() {
+            self.dirty.insert(path.to_string());
+        }
+    }
+
+    /// Whether a directory is taken isn't written to SQLite, since everything is marked as not taken when the crawlers
+    /// are dispatched anyway
+    fn set_taken(&mut self, path: &str, taken: bool) {
+        if let Some(mut model) = self.remove(path) {
+            model.taken = taken;
+            self.insert(model);
+        }
+    }
+
+    fn set_taken_to_false_all(&mut self) {
+        let taken: Vec<String> = self
+            .entries
+            .values()
+            .filter(|model| model.taken)
+            .map(|model| model.path.clone())
+            .collect();
+        for path in taken {
+            self.set_taken(&path, false);
+        }
+    }
+
+    /// Up to `amount` of the most urgent and of the longest waiting entries that aren't taken
+    fn candidates(&self, amount: usize) -> Vec<indexed_dir::Model> {
+        let mut paths: Vec<&String> = self
+            .by_priority
+            .iter()
+            .take(amount)
+            .map(|(_, _, path)| path)
+            .collect();
+        let most_urgent: HashSet<&String> = paths.iter().copied().collect();
+        paths.extend(
+            self.by_age
+                .iter()
+                .take(amount)
+                .map(|(_, path)| path)
+                .filter(|path| !most_urgent.contains(path)),
+        );
+        paths
+            .into_iter()
+            .filter_map(|path| self.entries.get(path).cloned())
+            .collect()
+    }
+
+    fn priority_counts(&self) -> HashMap<Priority, i64> {
+        let mut counts = HashMap::new();
+        for model in self.entries.values() {
+            *counts.entry(model.priority).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// The entries to upsert and the paths to delete to bring SQLite up to date
+    fn take_dirty(&mut self) -> (Vec<indexed_dir::Model>, Vec<String>) {
+        let mut upserts = Vec::new();
+        let mut deletes = Vec::new();
+        for path in self.dirty.drain() {
+            match self.entries.get(&path) {
+                Some(model) => upserts.push(model.clone()),
+                None => deletes.push(path),
+            }
+        }
+        (upserts, deletes)
+    }
+}
+
+/// What the write-behind task shares with the queue. The task stops once the queue is dropped
+struct SharedEntries {
+    entries: Mutex<QueueEntries>,
+    table: CrawlerQueueTable,
+    /// Held while writing, so that an older write can't land after a newer one
+    persist_lock: tokio::sync::Mutex<()>,
+}
+
+impl SharedEntries {
+    fn lock(&self) -> MutexGuard<'_, QueueEntries> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn persist(&self) -> Result<(), DbErr> {
+        let _persist_guard = self.persist_lock.lock().await;
+        let (upserts, deletes) = self.lock().take_dirty();
+        if upserts.is_empty() && deletes.is_empty() {
+            return Ok(());
+        }
+        let result = self.write(&upserts, &deletes).await;
+        if result.is_err() {
+            // Try again next time
+            let mut entries = self.lock();
+            entries
+                .dirty
+                .extend(upserts.into_iter().map(|model| model.path));
+            entries.dirty.extend(deletes);
+        }
+        result
+    }
+
+    async fn write(&self, upserts: &[indexed_dir::Model], deletes: &[String]) -> Result<(), DbErr> {
+        for chunk in deletes.chunks(DELETE_CHUNK_SIZE) {
+            self.table.delete_paths(chunk.to_vec()).await?;
+        }
+        self.table.upsert_many(upserts).await.map_err(|err| {
+            DbErr::Custom(format!(
+                "Error upserting directories to crawler queue: {}",
+                err
+            ))
+        })
+    }
+}
+
+/// A crawler queue that is kept in memory, so that the crawlers never wait on SQLite. Changes are written to SQLite in
+/// the background every few seconds, and the queue is restored from what was last written when the app starts
+#[derive(Clone)]
+pub struct MemoryCrawlerQueue {
+    shared: Arc<SharedEntries>,
+    index_roots: Arc<IndexRootsPlugin>,
+    scheduler: Arc<FairScheduler>,
+    pub notify: Arc<Notify>,
+}
+
+impl MemoryCrawlerQueue {
+    /// Restores the queue from SQLite and starts writing changes back to it. Must be called from within a Tokio runtime
+    pub async fn load(
+        db: &LocalDbService,
+        index_roots: Arc<IndexRootsPlugin>,
+    ) -> Result<Self, DbErr> {
+        let table = db.crawler_queue_table().clone();
+        let mut entries = QueueEntries::default();
+        for mut model in table.get_all().await? {
+            // Whatever was being crawled when the app closed has to be crawled again
+            model.taken = false;
+            entries.insert(model);
+        }
+        let shared = Arc::new(SharedEntries {
+            entries: Mutex::new(entries),
+            table,
+            persist_lock: tokio::sync::Mutex::new(()),
+        });
+        spawn_write_behind(Arc::downgrade(&shared));
+        Ok(Self {
+            shared,
+            index_roots,
+            scheduler: Arc::new(FairScheduler::new()),
+            notify: Arc::new(Notify::new()),
+        })
+    }
+
+    /// The entries are picked by the `FairScheduler`, rather than strictly by their priority
+    pub async fn fetch_many(&self, amount: u64) -> Result<Vec<(PathBuf, Priority)>, DbErr> {
+        let roots: Vec<PathBuf> = self
+            .index_roots
+            .get_enabled()
+            .await?
+            .iter()
+            .map(|root| PathBuf::from(&root.path))
+            .collect();
+        let amount = amount as usize;
+
+        let mut entries = self.shared.lock();
+        let candidates = entries.candidates(scheduler::CANDIDATES.max(amount));
+        let picked = self.scheduler.pick(candidates, amount, &roots, Utc::now());
+        for model in picked.iter() {
+            entries.set_taken(&model.path, true);
+        }
+        Ok(picked
+            .into_iter()
+            .map(|model| (PathBuf::from(model.path), model.priority))
+            .collect())
+    }
+
+    /// Directories that were pushed within the last 30 seconds are left out
+    pub fn push_many(&self, entries: &[(PathBuf, Priority)]) {
+        let now = Utc::now();
+        let mut queue_entries = self.shared.lock();
+        queue_entries.forget_old_pushes(now);
+        let mut did_push = false;
+        for (path, priority) in entries.iter() {
+            did_push |= queue_entries.push(path.to_string_lossy().into_owned(), *priority, now);
+        }
+        drop(queue_entries);
+
+        if did_push {
+            self.notify.notify_waiters();
+        }
+    }
+
+    pub fn delete_many(&self, models: &[indexed_dir::Model]) {
+        let mut entries = self.shared.lock();
+        for model in models.iter() {
+            entries.delete(&model.path);
+        }
+    }
+
+    /// The number of directories in the queue for each priority
+    pub fn get_priority_counts(&self) -> HashMap<Priority, i64> {
+        self.shared.lock().priority_counts()
+    }
+
+    pub fn set_taken_to_false_all(&self) {
+        self.shared.lock().set_taken_to_false_all();
+    }
+
+    /// Writes the changes that haven't been written to SQLite yet
+    pub async fn persist(&self) -> Result<(), DbErr> {
+        self.shared.persist().await
+    }
+}
+
+fn spawn_write_behind(shared: Weak<SharedEntries>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(PERSIST_INTERVAL).await;
+            let Some(shared) = shared.upgrade() else {
+                break;
+            };
+            if let Err(err) = shared.persist().await {
+                println!(
+                    "MemoryCrawlerQueue: Error writing the crawler queue to SQLite: {}",
+                    err
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::super::queue::CrawlerQueue;
+    use super::*;
+    use crate::tantivy_file_indexer::shared::indexing_crawler::traits::crawler_queue_api::CrawlerQueueApi;
+
+    fn paths(models: &[indexed_dir::Model]) -> Vec<&str> {
+        models.iter().map(|model| model.path.as_str()).collect()
+    }
+
+    #[test]
+    fn pushing_again_keeps_the_priority_and_the_wait() {
+        let mut entries = QueueEntries::default();
+        let start = Utc::now();
+        assert!(entries.push("/a".into(), 3, start));
+        // Too soon
+        assert!(!entries.push("/a".into(), 1, start));
+
+        let later = start + chrono::Duration::seconds(RECENTLY_PUSHED_SECS);
+        entries.forget_old_pushes(later);
+        assert!(entries.push("/a".into(), 5, later));
+        let model = &entries.entries["/a"];
+        assert_eq!((model.priority, model.added_at), (3, start));
+        assert_eq!(entries.by_priority.len(), 1);
+        assert_eq!(entries.by_age.len(), 1);
+    }
+
+    #[test]
+    fn taken_entries_are_not_candidates() {
+        let mut entries = QueueEntries::default();
+        let now = Utc::now();
+        entries.push("/a".into(), 1, now);
+        entries.push("/b".into(), 2, now);
+        entries.set_taken("/a", true);
+        assert_eq!(paths(&entries.candidates(10)), vec!["/b"]);
+
+        entries.set_taken_to_false_all();
+        assert_eq!(paths(&entries.candidates(10)), vec!["/a", "/b"]);
+        // Both are still counted while taken
+        assert_eq!(entries.priority_counts().values().sum::<i64>(), 2);
+    }
+
+    #[test]
+    fn deleted_entries_are_deleted_from_sqlite() {
+        let mut entries = QueueEntries::default();
+        let now = Utc::now();
+        entries.push("/a".into(), 1, now);
+        entries.push("/b".into(), 1, now);
+        entries.take_dirty();
+
+        entries.delete("/a");
+        entries.set_taken("/b", true);
+        let (upserts, deletes) = entries.take_dirty();
+        assert!(upserts.is_empty());
+        assert_eq!(deletes, vec!["/a".to_string()]);
+    }
+
+    async fn open_db() -> (Arc<LocalDbService>, PathBuf) {
+        let db_path =
+            std::env::temp_dir().join(format!("crawler_queue_test_{}.db", uuid::Uuid::new_v4()));
+        let db_url = format!("sqlite://{}?mode=rwc", db_path.to_string_lossy());
+        let db = LocalDbService::connect(&db_url, None)
+            .await
+            .expect("The test database should open");
+        (Arc::new(db), db_path)
+    }
+
+    #[tokio::test]
+    async fn recovers_from_the_sqlite_snapshot() {
+        let (db, db_path) = open_db().await;
+        let index_roots = Arc::new(IndexRootsPlugin::new(Arc::clone(&db)));
+
+        let queue = MemoryCrawlerQueue::load(&db, Arc::clone(&index_roots))
+            .await
+            .unwrap();
+        queue.push_many(&[(PathBuf::from("/a"), 1), (PathBuf::from("/b"), 2)]);
+        let taken = queue.fetch_many(1).await.unwrap();
+        queue.delete_many(&[indexed_dir::Model {
+            path: "/b".into(),
+            priority: 2,
+            taken: false,
+            added_at: Utc::now(),
+        }]);
+        queue.persist().await.unwrap();
+        drop(queue);
+
+        let queue = MemoryCrawlerQueue::load(&db, index_roots).await.unwrap();
+        // What was taken when the queue was dropped is available again
+        assert_eq!(taken, vec![(PathBuf::from("/a"), 1)]);
+        assert_eq!(queue.fetch_many(10).await.unwrap(), taken);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    /// The directories that the crawlers would push and fetch while crawling a tree, `width` children at a time
+    fn crawl_batches(batches: usize, width: usize) -> Vec<Vec<(PathBuf, Priority)>> {
+        (0..batches)
+            .map(|batch| {
+                (0..width)
+                    .map(|child| {
+                        (
+                            PathBuf::from(format!("/bench/{}/{}", batch, child)),
+                            (batch % 10) as Priority + 1,
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Pushes every batch, fetching and deleting one directory after each push like a crawler does, then drains the
+    /// queue. Returns how long it took
+    async fn run_workload<Q: CrawlerQueueApi>(
+        queue: &Q,
+        batches: &[Vec<(PathBuf, Priority)>],
+    ) -> Duration {
+        let start = Instant::now();
+        for batch in batches.iter() {
+            let files: Vec<_> = batch.iter().cloned().map(Into::into).collect();
+            queue.push(&files).await.unwrap();
+            if let Some(file) = queue.fetch_next().await.unwrap() {
+                queue.delete_one(file).await.unwrap();
+            }
+        }
+        while let Some(file) = queue.fetch_next().await.unwrap() {
+            queue.delete_one(file).await.unwrap();
+        }
+        start.elapsed()
+    }
+
+    /// Run with `cargo test --release bench_against_sqlite_queue -- --ignored --nocapture`
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn bench_against_sqlite_queue() {
+        let batches = crawl_batches(500, 20);
+        let (db, db_path) = open_db().await;
+        let index_roots = Arc::new(IndexRootsPlugin::new(Arc::clone(&db)));
+
+        let sqlite_queue = CrawlerQueue::new(Arc::clone(&db), Arc::clone(&index_roots));
+        let sqlite_time = run_workload(&sqlite_queue, &batches).await;
+
+        let memory_queue = MemoryCrawlerQueue::load(&db, index_roots).await.unwrap();
+        let memory_time = run_workload(&memory_queue, &batches).await;
+        let persist_start = Instant::now();
+        memory_queue.persist().await.unwrap();
+        let persist_time = persist_start.elapsed();
+
+        let directories = batches.iter().map(Vec::len).sum::<usize>();
+        println!(
+            "{} directories: CrawlerQueue took {:?}, MemoryCrawlerQueue took {:?} plus {:?} to write to SQLite",
+            directories, sqlite_time, memory_time, persist_time
+        );
+        let _ = std::fs::remove_file(db_path);
+    }
+}
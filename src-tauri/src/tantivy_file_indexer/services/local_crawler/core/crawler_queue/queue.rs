@@ -1,8 +1,8 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use chrono::Utc;
 use sea_orm::DbErr;
-use tokio::sync::Notify;
+use tokio::sync::{Mutex, Notify};
 
 use crate::tantivy_file_indexer::services::local_db::{
     service::LocalDbService,
@@ -14,26 +14,51 @@ use crate::tantivy_file_indexer::services::local_db::{
     },
 };
 
+use super::super::indexing_crawler::plugins::IndexRootsPlugin;
+use super::scheduler::{self, FairScheduler};
+
 pub type Priority = u32;
 
 #[derive(Clone)]
 pub struct CrawlerQueue {
     db: Arc<LocalDbService>,
     pub notify: Arc<Notify>,
+    index_roots: Arc<IndexRootsPlugin>,
+    scheduler: Arc<FairScheduler>,
+    /// Held while fetching, so that two workers can't pick the same entries before they are marked as taken
+    fetch_lock: Arc<Mutex<()>>,
 }
 
 // Rather than locally writing to JSON, write to the database.
 
 impl CrawlerQueue {
-    pub fn new(db: Arc<LocalDbService>) -> Self {
+    pub fn new(db: Arc<LocalDbService>, index_roots: Arc<IndexRootsPlugin>) -> Self {
         Self {
             db,
             notify: Arc::new(Notify::new()),
+            index_roots,
+            scheduler: Arc::new(FairScheduler::new()),
+            fetch_lock: Arc::new(Mutex::new(())),
         }
     } //         self.mark_taken(&next_entries, true).await?;
 
+    /// The entries are picked by the `FairScheduler`, rather than strictly by their priority
     pub async fn fetch_many(&self, amount: u64) -> Result<Vec<(PathBuf, Priority)>, DbErr> {
-        let models = self.get_crawler_queue_table().get_many(amount).await?;
+        let _fetch_guard = self.fetch_lock.lock().await;
+        let candidates = self
+            .get_crawler_queue_table()
+            .get_candidates((scheduler::CANDIDATES as u64).max(amount))
+            .await?;
+        let roots: Vec<PathBuf> = self
+            .index_roots
+            .get_enabled()
+            .await?
+            .iter()
+            .map(|root| PathBuf::from(&root.path))
+            .collect();
+        let models = self
+            .scheduler
+            .pick(candidates, amount as usize, &roots, Utc::now());
         self.get_crawler_queue_table()
             .mark_taken(&models, true)
             .await?;
@@ -50,6 +75,11 @@ impl CrawlerQueue {
             .map(|_| ())
     }
 
+    /// The number of directories in the queue for each priority
+    pub async fn get_priority_counts(&self) -> Result<HashMap<Priority, i64>, DbErr> {
+        self.get_crawler_queue_table().get_priority_counts().await
+    }
+
     pub async fn set_taken_to_false_all(&self) -> Result<(), DbErr> {
         self.get_crawler_queue_table().mark_all_as_not_taken().await
     }
@@ -0,0 +1,254 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::tantivy_file_indexer::services::local_db::tables::crawler_queue::entities::indexed_dir;
+
+use super::queue::Priority;
+
+/// Directories that the user asked for. These are crawled before anything else, in the order that they were asked for
+pub const USER_PRIORITY: Priority = 0;
+/// How many of the most urgent and of the longest waiting entries the queues hand to the scheduler to pick from
+pub const CANDIDATES: usize = 256;
+/// Every time an entry waits this long, it is treated as one priority level more urgent
+const AGING_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Decides which entries of the crawler queue get crawled next.
+///
+/// - Directories that the user asked for preempt everything else
+/// - The rest are taken from each root in turn, so that a root with a lot of shallow work can't hold up the others
+/// - Within a root, entries become more urgent the longer they wait, so that deep directories can't be starved by a
+///   constant stream of shallow ones
+pub struct FairScheduler {
+    /// The root that was served last, so that the next pick starts at the root after it
+    last_root: Mutex<Option<PathBuf>>,
+}
+
+impl FairScheduler {
+    pub fn new() -> Self {
+        Self {
+            last_root: Mutex::new(None),
+        }
+    }
+
+    /// Picks up to `amount` entries out of the candidates. For the picks to be fair, the candidates should include the
+    /// most urgent and the longest waiting entries of each root.
+    ///
+    /// `roots` are the index roots, which entries are grouped by. Entries outside of every root are grouped by their
+    /// drive instead
+    pub fn pick(
+        &self,
+        candidates: Vec<indexed_dir::Model>,
+        amount: usize,
+        roots: &[PathBuf],
+        now: DateTime<Utc>,
+    ) -> Vec<indexed_dir::Model> {
+        let (mut user, background): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|entry| entry.priority == USER_PRIORITY);
+        user.sort_by_key(|entry| entry.added_at);
+
+        let mut picked: Vec<indexed_dir::Model> = user.into_iter().take(amount).collect();
+        if picked.len() >= amount {
+            return picked;
+        }
+
+        let mut by_root: BTreeMap<PathBuf, Vec<indexed_dir::Model>> = BTreeMap::new();
+        for entry in background {
+            by_root
+                .entry(root_of(Path::new(&entry.path), roots))
+                .or_default()
+                .push(entry);
+        }
+        for entries in by_root.values_mut() {
+            // Reversed, so that the most urgent entry can be popped off of the end
+            entries.sort_by_cached_key(|entry| {
+                std::cmp::Reverse((
+                    effective_priority(entry, now),
+                    entry.added_at,
+                    entry.path.clone(),
+                ))
+            });
+        }
+
+        let mut last_root = self
+            .last_root
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        while picked.len() < amount && !by_root.is_empty() {
+            // The first root after the one that was served last, wrapping around to the start
+            let next_root = last_root
+                .as_ref()
+                .and_then(|last| {
+                    by_root
+                        .range::<PathBuf, _>((
+                            std::ops::Bound::Excluded(last),
+                            std::ops::Bound::Unbounded,
+                        ))
+                        .next()
+                })
+                .or_else(|| by_root.iter().next())
+                .map(|(root, _)| root.clone());
+            let Some(root) = next_root else {
+                break;
+            };
+            let entries = by_root.get_mut(&root).expect("The root was just found");
+            if let Some(entry) = entries.pop() {
+                picked.push(entry);
+            }
+            if entries.is_empty() {
+                by_root.remove(&root);
+            }
+            *last_root = Some(root);
+        }
+        picked
+    }
+}
+
+impl Default for FairScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The priority of the entry after aging it. Background work never ages into the priority of the user's requests
+fn effective_priority(entry: &indexed_dir::Model, now: DateTime<Utc>) -> Priority {
+    let waited_secs = (now - entry.added_at).num_seconds().max(0) as u64;
+    let aged_levels =
+        (waited_secs / AGING_INTERVAL.as_secs()).min(Priority::MAX as u64) as Priority;
+    entry
+        .priority
+        .saturating_sub(aged_levels)
+        .max(USER_PRIORITY + 1)
+}
+
+/// The deepest root that the path is inside of, or the drive of the path if it isn't inside of any root
+fn root_of(path: &Path, roots: &[PathBuf]) -> PathBuf {
+    roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .cloned()
+        .unwrap_or_else(|| {
+            path.components()
+                .take_while(|comp| matches!(comp, Component::Prefix(_) | Component::RootDir))
+                .collect()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, priority: Priority, added_at: DateTime<Utc>) -> indexed_dir::Model {
+        indexed_dir::Model {
+            path: path.to_string(),
+            priority,
+            taken: false,
+            added_at,
+        }
+    }
+
+    fn paths(entries: &[indexed_dir::Model]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.path.as_str()).collect()
+    }
+
+    #[test]
+    fn user_requests_preempt_background_work() {
+        let now = Utc::now();
+        let long_ago = now - chrono::Duration::hours(1);
+        let candidates = vec![
+            entry("/a/old", 1, long_ago),
+            entry("/a/asked", USER_PRIORITY, now),
+        ];
+        let picked = FairScheduler::new().pick(candidates, 1, &[PathBuf::from("/a")], now);
+        assert_eq!(paths(&picked), vec!["/a/asked"]);
+    }
+
+    #[test]
+    fn roots_take_turns() {
+        let now = Utc::now();
+        let roots = [PathBuf::from("/a"), PathBuf::from("/b")];
+        let candidates = vec![
+            entry("/a/1", 1, now),
+            entry("/a/2", 1, now),
+            entry("/a/3", 1, now),
+            entry("/b/deep/down", 9, now),
+        ];
+        let picked = FairScheduler::new().pick(candidates, 2, &roots, now);
+        assert_eq!(paths(&picked), vec!["/a/1", "/b/deep/down"]);
+    }
+
+    #[test]
+    fn nested_roots_are_separate() {
+        let roots = [PathBuf::from("/home"), PathBuf::from("/home/me/code")];
+        assert_eq!(
+            root_of(Path::new("/home/me/code/app"), &roots),
+            PathBuf::from("/home/me/code")
+        );
+        assert_eq!(
+            root_of(Path::new("/home/me/music"), &roots),
+            PathBuf::from("/home")
+        );
+        assert_eq!(root_of(Path::new("/srv/data"), &roots), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn waiting_entries_get_more_urgent() {
+        let now = Utc::now();
+        let deep = entry("/a/b/c/d/e/f", 7, now - chrono::Duration::minutes(5));
+        let shallow = entry("/a/b", 2, now);
+        assert!(effective_priority(&deep, now) < effective_priority(&shallow, now));
+        // But never as urgent as what the user asked for
+        assert_eq!(effective_priority(&deep, now), USER_PRIORITY + 1);
+    }
+
+    /// A constant stream of shallow work in one root, and a deep directory that was queued once. The deep directory
+    /// has to be picked eventually, even though there is always something more urgent than it when it was added
+    #[test]
+    fn deep_directories_are_not_starved() {
+        let scheduler = FairScheduler::new();
+        let roots = [PathBuf::from("/a")];
+        let start = Utc::now();
+        let mut queue = vec![entry("/a/deep/down/below", 40, start)];
+
+        for tick in 0..10_000 {
+            let now = start + chrono::Duration::seconds(tick);
+            queue.push(entry(&format!("/a/shallow{}", tick), 1, now));
+            queue.push(entry(&format!("/a/shallow{}b", tick), 2, now));
+
+            let picked = scheduler.pick(queue.clone(), 1, &roots, now);
+            if picked[0].path == "/a/deep/down/below" {
+                // Once it has aged all the way, it is the longest waiting entry at the most urgent background priority
+                assert!(tick <= 40 * AGING_INTERVAL.as_secs() as i64);
+                return;
+            }
+            queue.retain(|entry| entry.path != picked[0].path);
+        }
+        panic!("The deep directory was never picked");
+    }
+
+    #[test]
+    fn busy_roots_do_not_starve_other_roots() {
+        let scheduler = FairScheduler::new();
+        let roots = [PathBuf::from("/a"), PathBuf::from("/b")];
+        let now = Utc::now();
+        let mut queue: Vec<_> = (0..100)
+            .map(|i| entry(&format!("/a/{}", i), 1, now))
+            .collect();
+        queue.push(entry("/b/x/y/z", 30, now));
+
+        let mut picked_paths = Vec::new();
+        for _ in 0..2 {
+            let picked = scheduler.pick(queue.clone(), 1, &roots, now);
+            queue.retain(|entry| entry.path != picked[0].path);
+            picked_paths.push(picked[0].path.clone());
+        }
+        assert!(picked_paths.contains(&"/b/x/y/z".to_string()));
+    }
+}
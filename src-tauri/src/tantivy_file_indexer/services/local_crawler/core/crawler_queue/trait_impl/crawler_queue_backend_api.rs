@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use crate::tantivy_file_indexer::shared::indexing_crawler::{
+    models::crawler_file::CrawlerFile, traits::crawler_queue_api::CrawlerQueueApi,
+};
+
+use super::super::backend::CrawlerQueueBackend;
+
+impl CrawlerQueueApi for CrawlerQueueBackend {
+    type Error = String;
+
+    async fn push(&self, files: &[CrawlerFile]) -> Result<(), Self::Error> {
+        match self {
+            Self::Memory(queue) => queue.push(files).await,
+            Self::Sqlite(queue) => queue.push(files).await,
+        }
+    }
+
+    async fn fetch_next(&self) -> Result<Option<CrawlerFile>, Self::Error> {
+        match self {
+            Self::Memory(queue) => queue.fetch_next().await,
+            Self::Sqlite(queue) => queue.fetch_next().await,
+        }
+    }
+
+    async fn fetch(&self, amount: u64) -> Result<Vec<CrawlerFile>, Self::Error> {
+        match self {
+            Self::Memory(queue) => queue.fetch(amount).await,
+            Self::Sqlite(queue) => queue.fetch(amount).await,
+        }
+    }
+
+    async fn delete_one(&self, file: CrawlerFile) -> Result<(), Self::Error> {
+        match self {
+            Self::Memory(queue) => queue.delete_one(file).await,
+            Self::Sqlite(queue) => queue.delete_one(file).await,
+        }
+    }
+
+    async fn set_taken_to_false_all(&self) -> Result<(), Self::Error> {
+        match self {
+            Self::Memory(queue) => CrawlerQueueApi::set_taken_to_false_all(queue).await,
+            Self::Sqlite(queue) => CrawlerQueueApi::set_taken_to_false_all(queue).await,
+        }
+    }
+
+    fn get_notifier(&self) -> Arc<tokio::sync::Notify> {
+        match self {
+            Self::Memory(queue) => queue.get_notifier(),
+            Self::Sqlite(queue) => queue.get_notifier(),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::{path::PathBuf, sync::Arc};
+
+use crate::tantivy_file_indexer::{
+    services::local_db::tables::crawler_queue::entities::indexed_dir,
+    shared::indexing_crawler::{
+        models::crawler_file::CrawlerFile, traits::crawler_queue_api::CrawlerQueueApi,
+    },
+};
+
+use super::super::memory_queue::MemoryCrawlerQueue;
+
+impl CrawlerQueueApi for MemoryCrawlerQueue {
+    type Error = String;
+
+    fn push(
+        &self,
+        files: &[CrawlerFile],
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send {
+        let entries: Vec<(PathBuf, u32)> = files.iter().map(|x| x.clone().into()).collect();
+        self.push_many(&entries);
+        async { Ok(()) }
+    }
+
+    async fn fetch_next(&self) -> Result<Option<CrawlerFile>, Self::Error> {
+        self.fetch(1).await.map(|mut entries| entries.pop())
+    }
+
+    async fn fetch(&self, amount: u64) -> Result<Vec<CrawlerFile>, Self::Error> {
+        let models = self
+            .fetch_many(amount)
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok(models.into_iter().map(|x| x.into()).collect())
+    }
+
+    fn delete_one(
+        &self,
+        file: CrawlerFile,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send {
+        let model: indexed_dir::Model = file.into();
+        self.delete_many(&[model]);
+        async { Ok(()) }
+    }
+
+    async fn set_taken_to_false_all(&self) -> Result<(), Self::Error> {
+        self.set_taken_to_false_all();
+        Ok(())
+    }
+
+    fn get_notifier(&self) -> std::sync::Arc<tokio::sync::Notify> {
+        Arc::clone(&self.notify)
+    }
+}
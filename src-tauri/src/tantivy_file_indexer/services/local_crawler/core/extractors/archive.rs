@@ -0,0 +1,243 @@
+use std::{fs::File, io::Read, path::Path};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+/// Stops a single archive from flooding the index
+const MAX_MEMBERS: usize = 50_000;
+
+#[derive(Clone, Copy)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    SevenZip,
+}
+
+/// A file that lives inside of an archive
+pub struct ArchiveMember {
+    /// The path of the file inside of the archive, using forward slashes and without a leading slash
+    pub inner_path: String,
+    /// The uncompressed size of the file, in bytes
+    pub size: u64,
+    /// Not every archive format stores this
+    pub modified: Option<DateTime<Utc>>,
+}
+
+fn kind_of(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        return Some(ArchiveKind::TarGz);
+    }
+    match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "zip" => Some(ArchiveKind::Zip),
+        "tar" => Some(ArchiveKind::Tar),
+        "7z" => Some(ArchiveKind::SevenZip),
+        _ => None,
+    }
+}
+
+pub fn is_archive_file(path: &Path) -> bool {
+    kind_of(path).is_some()
+}
+
+/// The path that an archive member gets indexed under. Example: `C:\backups\backup.zip!/docs/report.pdf`
+pub fn member_path(archive_path: &str, inner_path: &str) -> String {
+    format!("{}!/{}", archive_path, inner_path)
+}
+
+/// Lists the files inside of a zip, tar, tar.gz or 7z archive without extracting them. Directories are left out.
+///
+/// Returns an `Err` if the file could not be read as an archive
+pub fn list_archive_members(path: &Path) -> Result<Vec<ArchiveMember>, String> {
+    let kind = kind_of(path).ok_or("Not a recognised archive")?;
+    let mut members = match kind {
+        ArchiveKind::Zip => list_zip(path)?,
+        ArchiveKind::Tar => list_tar(File::open(path).map_err(|err| err.to_string())?)?,
+        ArchiveKind::TarGz => list_tar(flate2::read::GzDecoder::new(
+            File::open(path).map_err(|err| err.to_string())?,
+        ))?,
+        ArchiveKind::SevenZip => list_7z(path)?,
+    };
+    members.truncate(MAX_MEMBERS);
+    Ok(members)
+}
+
+fn list_zip(path: &Path) -> Result<Vec<ArchiveMember>, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|err| err.to_string())?;
+    let mut members = Vec::new();
+    for i in 0..archive.len().min(MAX_MEMBERS) {
+        // The raw entry is enough for listing, so nothing gets decompressed
+        let entry = archive.by_index_raw(i).map_err(|err| err.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(inner_path) = entry.name().ok().and_then(|name| normalize(&name)) else {
+            continue;
+        };
+        let modified = entry.last_modified().and_then(|time| {
+            NaiveDate::from_ymd_opt(time.year().into(), time.month().into(), time.day().into())?
+                .and_hms_opt(
+                    time.hour().into(),
+                    time.minute().into(),
+                    time.second().into(),
+                )
+                .map(|time| time.and_utc())
+        });
+        members.push(ArchiveMember {
+            inner_path,
+            size: entry.size(),
+            modified,
+        });
+    }
+    Ok(members)
+}
+
+fn list_tar<R: Read>(reader: R) -> Result<Vec<ArchiveMember>, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut members = Vec::new();
+    for entry in archive.entries().map_err(|err| err.to_string())? {
+        let entry = entry.map_err(|err| err.to_string())?;
+        let header = entry.header();
+        if !header.entry_type().is_file() {
+            continue;
+        }
+        let Some(inner_path) = entry
+            .path()
+            .ok()
+            .and_then(|path| normalize(&path.to_string_lossy()))
+        else {
+            continue;
+        };
+        let modified = header
+            .mtime()
+            .ok()
+            .and_then(|secs| Utc.timestamp_opt(secs as i64, 0).single());
+        members.push(ArchiveMember {
+            inner_path,
+            size: entry.size(),
+            modified,
+        });
+        if members.len() >= MAX_MEMBERS {
+            break;
+        }
+    }
+    Ok(members)
+}
+
+fn list_7z(path: &Path) -> Result<Vec<ArchiveMember>, String> {
+    let archive = sevenz_rust2::Archive::open(path).map_err(|err| err.to_string())?;
+    let members = archive
+        .files
+        .into_iter()
+        .filter(|entry| !entry.is_directory && !entry.is_anti_item)
+        .filter_map(|entry| {
+            let modified = entry.has_last_modified_date.then(|| {
+                DateTime::<Utc>::from(std::time::SystemTime::from(entry.last_modified_date))
+            });
+            Some(ArchiveMember {
+                inner_path: normalize(&entry.name)?,
+                size: entry.size,
+                modified,
+            })
+        })
+        .take(MAX_MEMBERS)
+        .collect();
+    Ok(members)
+}
+
+/// Archives made on Windows can use backslashes, and some tools add a leading `./`, `/` or drive letter. Members whose
+/// path would lead out of the archive with `..` are left out
+fn normalize(inner_path: &str) -> Option<String> {
+    let mut components: Vec<&str> = Vec::new();
+    for component in inner_path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            drive if components.is_empty() && drive.len() == 2 && drive.ends_with(':') => {}
+            _ => components.push(component),
+        }
+    }
+    if components.is_empty() {
+        None
+    } else {
+        Some(components.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_uses_forward_slashes_without_a_leading_slash() {
+        assert_eq!(
+            normalize("docs/report.pdf").as_deref(),
+            Some("docs/report.pdf")
+        );
+        assert_eq!(
+            normalize("docs\\report.pdf").as_deref(),
+            Some("docs/report.pdf")
+        );
+        assert_eq!(
+            normalize("./docs/report.pdf").as_deref(),
+            Some("docs/report.pdf")
+        );
+        assert_eq!(
+            normalize("/./docs//report.pdf").as_deref(),
+            Some("docs/report.pdf")
+        );
+        assert_eq!(normalize("").as_deref(), None);
+        assert_eq!(normalize("./").as_deref(), None);
+    }
+
+    #[test]
+    fn absolute_members_stay_inside_of_the_archive() {
+        assert_eq!(normalize("/etc/passwd").as_deref(), Some("etc/passwd"));
+        assert_eq!(
+            normalize("C:\\Windows\\win.ini").as_deref(),
+            Some("Windows/win.ini")
+        );
+        assert_eq!(
+            normalize("C:/Windows/win.ini").as_deref(),
+            Some("Windows/win.ini")
+        );
+    }
+
+    #[test]
+    fn members_that_lead_out_of_the_archive_are_left_out() {
+        assert_eq!(
+            normalize("docs/../report.pdf").as_deref(),
+            Some("report.pdf")
+        );
+        assert_eq!(normalize("../report.pdf").as_deref(), None);
+        assert_eq!(normalize("docs/../../report.pdf").as_deref(), None);
+        assert_eq!(normalize("..\\..\\report.pdf").as_deref(), None);
+    }
+
+    #[test]
+    fn member_paths_point_into_the_archive() {
+        assert_eq!(
+            member_path("C:\\backups\\backup.zip", "docs/report.pdf"),
+            "C:\\backups\\backup.zip!/docs/report.pdf"
+        );
+        assert_eq!(
+            member_path("/home/me/backup.tar.gz", "report.pdf"),
+            "/home/me/backup.tar.gz!/report.pdf"
+        );
+    }
+
+    #[test]
+    fn archives_are_recognised_by_extension() {
+        assert!(is_archive_file(Path::new("backup.zip")));
+        assert!(is_archive_file(Path::new("BACKUP.ZIP")));
+        assert!(is_archive_file(Path::new("backup.tar")));
+        assert!(is_archive_file(Path::new("backup.tar.gz")));
+        assert!(is_archive_file(Path::new("backup.tgz")));
+        assert!(is_archive_file(Path::new("backup.7z")));
+        assert!(!is_archive_file(Path::new("backup.gz")));
+        assert!(!is_archive_file(Path::new("zip")));
+    }
+}
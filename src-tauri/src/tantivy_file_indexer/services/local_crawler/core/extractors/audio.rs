@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use lofty::prelude::{Accessor, AudioFile, TaggedFileExt};
+
+use crate::shared::models::audio_tags_model::AudioTagsModel;
+
+/// The extensions that the tag extractor knows how to read. Lowercase and without a leading dot
+pub const AUDIO_EXTENSIONS: [&str; 4] = ["mp3", "flac", "ogg", "m4a"];
+
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Reads the ID3, Vorbis comment or MP4 tags of the file, depending on its format.
+///
+/// Returns an `Err` if the file could not be parsed as an audio file
+pub fn extract_audio_tags(path: &Path) -> Result<AudioTagsModel, String> {
+    let tagged_file = lofty::read_from_path(path).map_err(|err| err.to_string())?;
+    let duration = tagged_file.properties().duration().as_secs();
+
+    let tag = match tagged_file
+        .primary_tag()
+        .or_else(|| tagged_file.first_tag())
+    {
+        Some(tag) => tag,
+        None => {
+            // The file has no tags, but the duration is still worth indexing
+            return Ok(AudioTagsModel {
+                duration,
+                ..Default::default()
+            });
+        }
+    };
+
+    Ok(AudioTagsModel {
+        artist: tag.artist().map(|x| x.to_string()),
+        album: tag.album().map(|x| x.to_string()),
+        title: tag.title().map(|x| x.to_string()),
+        genre: tag.genre().map(|x| x.to_string()),
+        duration,
+    })
+}
@@ -0,0 +1,278 @@
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use quick_xml::{events::Event, Reader};
+use zip::ZipArchive;
+
+use crate::shared::models::document_metadata_model::DocumentMetadataModel;
+
+/// The extensions that the document extractor knows how to read. Lowercase and without a leading dot
+pub const DOCUMENT_EXTENSIONS: [&str; 5] = ["pdf", "docx", "xlsx", "pptx", "odt"];
+
+/// Indexing the entire text of huge documents would bloat the index, so the body gets cut off after this many characters
+const MAX_BODY_CHARS: usize = 100_000;
+/// The most that will be decompressed from a single zip entry or PDF page. Protects against decompression bombs
+const MAX_DECOMPRESSED_BYTES: u64 = 50_000_000;
+
+pub fn is_document_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| DOCUMENT_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Reads the title, author, page count and text of a PDF, Office or OpenDocument file.
+///
+/// For spreadsheets the page count is the number of sheets, and for presentations it is the number of slides.
+pub fn extract_document_metadata(path: &Path) -> Result<DocumentMetadataModel, String> {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let mut model = match ext.as_str() {
+        "pdf" => extract_pdf(path)?,
+        "docx" => extract_docx(&mut open_zip(path)?),
+        "xlsx" => extract_xlsx(&mut open_zip(path)?),
+        "pptx" => extract_pptx(&mut open_zip(path)?),
+        "odt" => extract_odt(&mut open_zip(path)?),
+        _ => return Err(format!("No document extractor exists for extension '{}'", ext)),
+    };
+
+    if let Some(body) = &mut model.body {
+        if let Some((cutoff, _)) = body.char_indices().nth(MAX_BODY_CHARS) {
+            body.truncate(cutoff);
+        }
+    }
+    Ok(model)
+}
+
+fn extract_pdf(path: &Path) -> Result<DocumentMetadataModel, String> {
+    let doc = lopdf::Document::load(path).map_err(|err| err.to_string())?;
+    let page_numbers: Vec<u32> = doc.get_pages().keys().copied().collect();
+
+    // Pages whose text can't be decoded (unsupported font encodings, etc.) are skipped rather than failing the whole document
+    let body: String = doc
+        .extract_text_chunks_with_limit(&page_numbers, MAX_DECOMPRESSED_BYTES as usize)
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(DocumentMetadataModel {
+        title: pdf_info_string(&doc, b"Title"),
+        author: pdf_info_string(&doc, b"Author"),
+        page_count: page_numbers.len() as u64,
+        body: non_empty(body),
+    })
+}
+
+/// Reads a value from the document information dictionary of the PDF
+fn pdf_info_string(doc: &lopdf::Document, key: &[u8]) -> Option<String> {
+    let info = match doc.trailer.get(b"Info").ok()? {
+        lopdf::Object::Reference(id) => doc.get_object(*id).ok()?,
+        obj => obj,
+    };
+    let value = info.as_dict().ok()?.get(key).ok()?;
+    lopdf::decode_text_string(value).ok().and_then(non_empty)
+}
+
+fn extract_docx<R: Read + Seek>(archive: &mut ZipArchive<R>) -> DocumentMetadataModel {
+    let (title, author) = ooxml_core_properties(archive);
+    let page_count = read_zip_entry(archive, "docProps/app.xml")
+        .and_then(|xml| xml_first_text(&xml, b"Pages"))
+        .and_then(|pages| pages.parse().ok())
+        .unwrap_or(0);
+    let body = read_zip_entry(archive, "word/document.xml")
+        .map(|xml| xml_text(&xml, &[b"t"], &[b"p"]))
+        .and_then(non_empty);
+
+    DocumentMetadataModel {
+        title,
+        author,
+        page_count,
+        body,
+    }
+}
+
+fn extract_xlsx<R: Read + Seek>(archive: &mut ZipArchive<R>) -> DocumentMetadataModel {
+    let (title, author) = ooxml_core_properties(archive);
+    let page_count = numbered_zip_entries(archive, "xl/worksheets/sheet").len() as u64;
+    // Only the shared strings are read, since numbers in cells aren't worth searching for
+    let body = read_zip_entry(archive, "xl/sharedStrings.xml")
+        .map(|xml| xml_text(&xml, &[b"t"], &[b"si"]))
+        .and_then(non_empty);
+
+    DocumentMetadataModel {
+        title,
+        author,
+        page_count,
+        body,
+    }
+}
+
+fn extract_pptx<R: Read + Seek>(archive: &mut ZipArchive<R>) -> DocumentMetadataModel {
+    let (title, author) = ooxml_core_properties(archive);
+    let slides = numbered_zip_entries(archive, "ppt/slides/slide");
+    let body: String = slides
+        .iter()
+        .filter_map(|slide| read_zip_entry(archive, slide))
+        .map(|xml| xml_text(&xml, &[b"t"], &[b"p"]))
+        .collect();
+
+    DocumentMetadataModel {
+        title,
+        author,
+        page_count: slides.len() as u64,
+        body: non_empty(body),
+    }
+}
+
+fn extract_odt<R: Read + Seek>(archive: &mut ZipArchive<R>) -> DocumentMetadataModel {
+    let meta = read_zip_entry(archive, "meta.xml").unwrap_or_default();
+    let title = xml_first_text(&meta, b"title");
+    let author =
+        xml_first_text(&meta, b"initial-creator").or_else(|| xml_first_text(&meta, b"creator"));
+    let page_count = xml_first_attribute(&meta, b"document-statistic", b"page-count")
+        .and_then(|pages| pages.parse().ok())
+        .unwrap_or(0);
+    let body = read_zip_entry(archive, "content.xml")
+        .map(|xml| xml_text(&xml, &[b"p", b"h"], &[b"p", b"h"]))
+        .and_then(non_empty);
+
+    DocumentMetadataModel {
+        title,
+        author,
+        page_count,
+        body,
+    }
+}
+
+/// Every Office Open XML package stores its title and author in the same place
+fn ooxml_core_properties<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+) -> (Option<String>, Option<String>) {
+    let core = read_zip_entry(archive, "docProps/core.xml").unwrap_or_default();
+    (
+        xml_first_text(&core, b"title"),
+        xml_first_text(&core, b"creator"),
+    )
+}
+
+fn open_zip(path: &Path) -> Result<ZipArchive<File>, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    ZipArchive::new(file).map_err(|err| err.to_string())
+}
+
+fn read_zip_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let entry = archive.by_name(name).ok()?;
+    let mut contents = String::new();
+    entry
+        .take(MAX_DECOMPRESSED_BYTES)
+        .read_to_string(&mut contents)
+        .ok()?;
+    Some(contents)
+}
+
+/// Example: the prefix `ppt/slides/slide` gives `ppt/slides/slide1.xml`, `ppt/slides/slide2.xml`, ... in numeric order
+fn numbered_zip_entries<R: Read + Seek>(archive: &ZipArchive<R>, prefix: &str) -> Vec<String> {
+    let mut entries: Vec<(u32, String)> = archive
+        .file_names()
+        .filter_map(|name| {
+            let name = name.ok()?;
+            let number = name.strip_prefix(prefix)?.strip_suffix(".xml")?.parse().ok()?;
+            Some((number, name.to_string()))
+        })
+        .collect();
+    entries.sort_by_key(|(number, _)| *number);
+    entries.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Collects the text inside of every element whose local name is in `text_elements`.
+/// A newline is added whenever one of the `line_elements` closes.
+fn xml_text(xml: &str, text_elements: &[&[u8]], line_elements: &[&[u8]]) -> String {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    // Elements such as spans can be nested inside of text elements, so track how deep the reader is
+    let mut text_depth = 0;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if text_elements.contains(&e.local_name().as_ref()) => {
+                text_depth += 1
+            }
+            Ok(Event::End(e)) => {
+                let name = e.local_name();
+                if text_elements.contains(&name.as_ref()) {
+                    text_depth -= 1;
+                }
+                if line_elements.contains(&name.as_ref()) {
+                    text.push('\n');
+                }
+            }
+            Ok(Event::Empty(e)) if text_depth > 0 => {
+                // Tabs, line breaks and OpenDocument's `<text:s/>` all stand in for whitespace
+                if matches!(e.local_name().as_ref(), b"tab" | b"br" | b"s") {
+                    text.push(' ');
+                }
+            }
+            Ok(Event::Text(e)) if text_depth > 0 => {
+                if let Ok(content) = e.unescape() {
+                    text.push_str(&content);
+                }
+            }
+            Ok(Event::Eof) => break,
+            // Keep whatever was read before the document turned out to be malformed
+            Err(_) => break,
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Returns the text of the first element with the given local name, given that it isn't blank
+fn xml_first_text(xml: &str, element: &[u8]) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut inside = false;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == element => inside = true,
+            Ok(Event::Text(e)) if inside => text.push_str(&e.unescape().ok()?),
+            Ok(Event::End(e)) if inside && e.local_name().as_ref() == element => {
+                return non_empty(text)
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+fn xml_first_attribute(xml: &str, element: &[u8], attribute: &[u8]) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.local_name().as_ref() == element => {
+                return e
+                    .attributes()
+                    .flatten()
+                    .find(|attr| attr.key.local_name().as_ref() == attribute)
+                    .and_then(|attr| attr.unescape_value().ok())
+                    .map(|value| value.to_string());
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+fn non_empty(text: String) -> Option<String> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
@@ -0,0 +1,349 @@
+use std::path::Path;
+
+/// Source files that are bigger than this are most likely generated, so they aren't scanned for symbols
+const MAX_SOURCE_FILE_SIZE: u64 = 2_000_000;
+/// Stops a single file from flooding the index
+const MAX_SYMBOLS: usize = 2_000;
+
+#[derive(Clone, Copy)]
+enum Language {
+    Rust,
+    TypeScript,
+    Python,
+    Go,
+}
+
+fn language_of(path: &Path) -> Option<Language> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    match ext.as_str() {
+        "rs" => Some(Language::Rust),
+        "ts" | "tsx" | "mts" | "cts" => Some(Language::TypeScript),
+        "py" => Some(Language::Python),
+        "go" => Some(Language::Go),
+        _ => None,
+    }
+}
+
+pub fn is_source_file(path: &Path) -> bool {
+    language_of(path).is_some()
+}
+
+/// Returns the names of the functions, structs, classes, traits, etc. that are defined in the source file.
+///
+/// This works line by line instead of parsing the file, so it is fast but will miss definitions that are split across lines
+/// in unusual ways. Duplicate names are only returned once.
+pub fn extract_symbols(path: &Path) -> Result<Vec<String>, String> {
+    let language = language_of(path).ok_or("Not a recognised source file")?;
+    let size = std::fs::metadata(path)
+        .map_err(|err| err.to_string())?
+        .len();
+    if size > MAX_SOURCE_FILE_SIZE {
+        return Err(format!("Source file is too large ({} bytes)", size));
+    }
+    let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    let mut symbols: Vec<String> = Vec::new();
+    for line in source.lines() {
+        if let Some(symbol) = symbol_in_line(language, line.trim_start()) {
+            if !symbols.iter().any(|existing| existing == symbol) {
+                symbols.push(symbol.to_string());
+            }
+            if symbols.len() >= MAX_SYMBOLS {
+                break;
+            }
+        }
+    }
+    Ok(symbols)
+}
+
+fn symbol_in_line(language: Language, line: &str) -> Option<&str> {
+    match language {
+        Language::Rust => {
+            let line = strip_modifiers(
+                strip_rust_visibility(line),
+                &["async", "const", "unsafe", "extern", "\"C\"", "default"],
+            );
+            after_keyword(
+                line,
+                &[
+                    "fn",
+                    "struct",
+                    "enum",
+                    "trait",
+                    "type",
+                    "union",
+                    "mod",
+                    "macro_rules!",
+                ],
+            )
+        }
+        Language::TypeScript => {
+            let line =
+                strip_modifiers(line, &["export", "default", "declare", "abstract", "async"]);
+            after_keyword(
+                line,
+                &[
+                    "function*",
+                    "function",
+                    "class",
+                    "interface",
+                    "type",
+                    "enum",
+                    "namespace",
+                ],
+            )
+        }
+        Language::Python => {
+            let line = strip_modifiers(line, &["async"]);
+            after_keyword(line, &["def", "class"])
+        }
+        Language::Go => {
+            match line.strip_prefix("func ") {
+                Some(rest) => {
+                    let rest = rest.trim_start();
+                    // Methods have their receiver in parentheses before the name: `func (q *Queue) Push(...)`
+                    let rest = match rest.strip_prefix('(') {
+                        Some(receiver) => &receiver[receiver.find(')')? + 1..],
+                        None => rest,
+                    };
+                    identifier(rest.trim_start())
+                }
+                None => after_keyword(line, &["type"]),
+            }
+        }
+    }
+}
+
+/// Strips `pub`, `pub(crate)`, `pub(in some::path)`, etc.
+fn strip_rust_visibility(line: &str) -> &str {
+    let Some(rest) = line.strip_prefix("pub") else {
+        return line;
+    };
+    let rest = match rest.strip_prefix('(') {
+        Some(inner) => match inner.find(')') {
+            Some(end) => &inner[end + 1..],
+            None => return line,
+        },
+        None => rest,
+    };
+    if rest.starts_with(char::is_whitespace) {
+        rest.trim_start()
+    } else {
+        line
+    }
+}
+
+fn strip_modifiers<'a>(mut line: &'a str, modifiers: &[&str]) -> &'a str {
+    while let Some(rest) = modifiers.iter().find_map(|modifier| {
+        line.strip_prefix(modifier)
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+    }) {
+        line = rest.trim_start();
+    }
+    line
+}
+
+/// Returns the identifier that comes after one of the keywords, given that the line starts with one
+fn after_keyword<'a>(line: &'a str, keywords: &[&str]) -> Option<&'a str> {
+    keywords.iter().find_map(|keyword| {
+        let rest = line.strip_prefix(keyword)?;
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        identifier(rest.trim_start())
+    })
+}
+
+fn identifier(text: &str) -> Option<&str> {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(text.len());
+    let ident = &text[..end];
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        None
+    } else {
+        Some(ident)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(language: Language, lines: &[&str]) -> Vec<Option<String>> {
+        lines
+            .iter()
+            .map(|line| symbol_in_line(language, line).map(str::to_string))
+            .collect()
+    }
+
+    fn some(names: &[&str]) -> Vec<Option<String>> {
+        names.iter().map(|name| Some(name.to_string())).collect()
+    }
+
+    #[test]
+    fn rust_definitions() {
+        let lines = [
+            "fn main() {",
+            "pub fn run(&self) -> bool {",
+            "pub(crate) struct Worker<C, P>",
+            "pub(super) enum Kind {",
+            "pub(in crate::services) trait Plugin {",
+            "pub async fn fetch() {}",
+            "pub const fn capacity() -> usize {",
+            "pub(crate) async unsafe fn read_raw() {",
+            "unsafe extern \"C\" fn callback(x: i32) {",
+            "default fn specialized(&self) {}",
+            "type Result<T> = std::result::Result<T, Error>;",
+            "pub union Bits {",
+            "mod tests {",
+            "macro_rules! hashmap {",
+        ];
+        assert_eq!(
+            symbols(Language::Rust, &lines),
+            some(&[
+                "main",
+                "run",
+                "Worker",
+                "Kind",
+                "Plugin",
+                "fetch",
+                "capacity",
+                "read_raw",
+                "callback",
+                "specialized",
+                "Result",
+                "Bits",
+                "tests",
+                "hashmap",
+            ])
+        );
+    }
+
+    #[test]
+    fn rust_lookalikes() {
+        let lines = [
+            "fn_call(x);",
+            "let struct_name = \"a\";",
+            "type_of(value)",
+            "public_api();",
+            "pub const MAX_SIZE: usize = 10;",
+            "pub static NAME: &str = \"a\";",
+            "impl Plugin for Worker {",
+            "// fn commented_out() {}",
+            "pub(crate fn broken() {}",
+            "fn 2d() {}",
+        ];
+        assert_eq!(symbols(Language::Rust, &lines), vec![None; lines.len()]);
+    }
+
+    #[test]
+    fn rust_visibility_is_stripped() {
+        assert_eq!(strip_rust_visibility("pub fn a()"), "fn a()");
+        assert_eq!(strip_rust_visibility("pub(crate) fn a()"), "fn a()");
+        assert_eq!(
+            strip_rust_visibility("pub(in crate::a::b)  fn a()"),
+            "fn a()"
+        );
+        assert_eq!(strip_rust_visibility("fn a()"), "fn a()");
+        assert_eq!(strip_rust_visibility("public()"), "public()");
+        assert_eq!(strip_rust_visibility("pub(crate"), "pub(crate");
+    }
+
+    #[test]
+    fn typescript_definitions() {
+        let lines = [
+            "function search(query: string) {",
+            "export function parse() {",
+            "export default class SearchPage {",
+            "export async function fetchAll() {",
+            "async function load() {",
+            "function* walk(node) {",
+            "export declare namespace Config {",
+            "export abstract class Repository<T> {",
+            "interface Props {",
+            "export type FileModel = {",
+            "export enum Status {",
+            "function $init() {",
+        ];
+        assert_eq!(
+            symbols(Language::TypeScript, &lines),
+            some(&[
+                "search",
+                "parse",
+                "SearchPage",
+                "fetchAll",
+                "load",
+                "walk",
+                "Config",
+                "Repository",
+                "Props",
+                "FileModel",
+                "Status",
+                "$init",
+            ])
+        );
+    }
+
+    #[test]
+    fn typescript_lookalikes() {
+        let lines = [
+            "functionName();",
+            "typeof value === 'string'",
+            "classList.add('active');",
+            "export default function () {",
+            "export const search = () => {",
+            "exported = true;",
+            "// class Commented {}",
+        ];
+        assert_eq!(
+            symbols(Language::TypeScript, &lines),
+            vec![None; lines.len()]
+        );
+    }
+
+    #[test]
+    fn python_definitions_and_lookalikes() {
+        let lines = [
+            "def run(self):",
+            "async def fetch(url):",
+            "class Model(Base):",
+            "define = 1",
+            "classes = []",
+            "# def commented():",
+            "async_result = call()",
+        ];
+        let mut expected = some(&["run", "fetch", "Model"]);
+        expected.extend([None, None, None, None]);
+        assert_eq!(symbols(Language::Python, &lines), expected);
+    }
+
+    #[test]
+    fn go_definitions_and_lookalikes() {
+        let lines = [
+            "func main() {",
+            "func (q *Queue) Push(item string) {",
+            "func (Queue) Len() int {",
+            "type Queue struct {",
+            "type Handler interface {",
+            "funcs := []func(){}",
+            "typeName := \"a\"",
+            "go func() {",
+            "// func commented() {}",
+        ];
+        let mut expected = some(&["main", "Push", "Len", "Queue", "Handler"]);
+        expected.extend([None, None, None, None]);
+        assert_eq!(symbols(Language::Go, &lines), expected);
+    }
+
+    #[test]
+    fn source_files_are_recognised_by_extension() {
+        assert!(is_source_file(Path::new("main.rs")));
+        assert!(is_source_file(Path::new("App.TSX")));
+        assert!(is_source_file(Path::new("config.mts")));
+        assert!(is_source_file(Path::new("script.py")));
+        assert!(is_source_file(Path::new("server.go")));
+        assert!(!is_source_file(Path::new("main.js")));
+        assert!(!is_source_file(Path::new("Makefile")));
+    }
+}
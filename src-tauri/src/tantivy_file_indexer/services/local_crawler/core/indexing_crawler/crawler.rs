@@ -5,12 +5,25 @@ use tokio::fs::ReadDir;
 
 use crate::{
     shared::models::sys_file_model::SystemFileModel,
-    tantivy_file_indexer::{services::local_crawler::core::indexing_crawler::plugins::FiltererPlugin, shared::indexing_crawler::{
-        models::crawler_file::CrawlerFile, traits::crawler_queue_api::CrawlerQueueApi,
-    }},
+    tantivy_file_indexer::{
+        services::{
+            local_crawler::core::indexing_crawler::plugins::{
+                change_detector::DirectoryChange, ChangeDetectorPlugin, FiltererPlugin,
+            },
+            local_db::tables::index_roots::entities::index_root,
+        },
+        shared::indexing_crawler::{
+            models::crawler_file::CrawlerFile, traits::crawler_queue_api::CrawlerQueueApi,
+        },
+        util::path::is_hidden,
+    },
 };
 
-use super::plugins::{filterer::ShouldIndexResult};
+use super::super::settings::CrawlerSettings;
+use super::{
+    plugins::filterer::ShouldIndexResult,
+    symlinks::{ResolvedEntry, SymlinkPolicy, VisitedLinks},
+};
 
 pub enum CrawlerError {
     ReadDir(String),
@@ -18,32 +31,78 @@ pub enum CrawlerError {
     NotDir(String),
 }
 
+#[derive(Default)]
+pub struct CrawledDirectory {
+    pub files: Vec<SystemFileModel>,
+    /// `None` if there is no change detector, or it couldn't tell whether the directory changed
+    pub change: Option<DirectoryChange>,
+}
+
+impl CrawledDirectory {
+    /// Unchanged directories don't need their files to be indexed again
+    pub fn is_unchanged(&self) -> bool {
+        self.change.as_ref().is_some_and(|change| change.unchanged)
+    }
+}
+
 /// Where `file` should ideally be a directory. If its not, it will get ignored. Note that this is not a recursive crawl.
 /// Returns an `Error` if the found directories failed to get pushed to the crawler queue or there was an error reading the directory.
+///
+/// `root` is the index root that the directory is inside of, if there is one, which decides how deep the crawl goes and what gets skipped.
+/// `visited_links` keeps track of the symlinks that were followed under it
 pub async fn crawl<C>(
     file: &CrawlerFile,
     queue: Arc<C>,
 
     filterer: Option<Arc<FiltererPlugin>>,
-) -> Result<Vec<SystemFileModel>, CrawlerError>
+    root: Option<&index_root::Model>,
+    visited_links: Option<&VisitedLinks>,
+    change_detector: Option<&ChangeDetectorPlugin>,
+    settings: &CrawlerSettings,
+) -> Result<CrawledDirectory, CrawlerError>
 where
     C: CrawlerQueueApi,
 {
-    if !file.path.is_dir() {
-        return Err(CrawlerError::NotDir(format!(
-            "The path {} is not a directory",
-            file.path.to_string_lossy()
-        )));
+    let dir_metadata = match tokio::fs::metadata(&file.path).await {
+        Ok(metadata) if metadata.is_dir() => metadata,
+        _ => {
+            return Err(CrawlerError::NotDir(format!(
+                "The path {} is not a directory",
+                file.path.to_string_lossy()
+            )))
+        }
+    };
+    let mut check = match change_detector {
+        Some(detector) => detector.check(&file.path, &dir_metadata, settings).await,
+        None => None,
+    };
+    if let Some(check) = check.take_if(|check| check.can_skip_reading()) {
+        return Ok(CrawledDirectory {
+            files: Vec::new(),
+            change: Some(check.finish()),
+        });
     }
 
     let mut dtos = Vec::new();
     let mut dir_paths_found: Vec<CrawlerFile> = Vec::new();
 
+    let can_descend = root.is_none_or(|root| root.can_descend(&file.path));
+    let include_hidden = root.is_none_or(|root| root.include_hidden);
+    let symlink_policy = match &filterer {
+        Some(filterer) => filterer.symlink_policy().await,
+        None => SymlinkPolicy::default(),
+    }
+    .for_root(root);
+
     let mut dir = read_dir(&file.path).await?;
 
     while let Ok(Some(entry)) = dir.next_entry().await {
         let entry_path = entry.path();
-        if let Ok(metadata) = entry_path.metadata() {
+        if let Some(resolved) = ResolvedEntry::read(&entry_path, symlink_policy) {
+            let metadata = &resolved.metadata;
+            if !include_hidden && is_hidden(&entry_path, metadata) {
+                continue;
+            }
             // First, see if the path can be filtered
             if let Some(filterer) = &filterer {
                 if let ShouldIndexResult::ShouldNotIndex(_reason) =
@@ -58,13 +117,24 @@ where
                     // DONT BREAK HERE!!!!!!!!!!!!!!!!!!!!!!!!!
                     continue;
                 }
+                if filterer
+                    .is_excluded_by_ignore_files(&entry_path, metadata.is_dir())
+                    .await
+                {
+                    continue;
+                }
             }
 
-            match SystemFileModel::try_new_from_meta(entry_path.clone(), &metadata) {
+            if let Some(check) = &mut check {
+                check.add_entry(&entry_path, metadata);
+            }
+            // Checked before the entry gets turned into a model, since that consumes it
+            let should_descend = can_descend && resolved.can_descend(&entry_path, visited_links);
+            match resolved.into_model(entry_path.clone()) {
                 Ok(dto) => {
                     dtos.push(dto);
                     // If it is a directory, push it to the queue so that it can get processed
-                    if metadata.is_dir() {
+                    if should_descend {
                         dir_paths_found.push(CrawlerFile {
                             path: entry_path,
                             priority: file.priority + 1,
@@ -86,14 +156,19 @@ where
             }
         }
     }
-    if !dir_paths_found.is_empty() {
+    let change = check.map(|check| check.finish());
+    let should_descend = change.as_ref().is_none_or(|change| change.should_descend);
+    if should_descend && !dir_paths_found.is_empty() {
         queue
             .push(&dir_paths_found)
             .await
             .map_err(|err| CrawlerError::PushToQueue(err.to_string()))?;
     }
 
-    Ok(dtos)
+    Ok(CrawledDirectory {
+        files: dtos,
+        change,
+    })
 }
 
 async fn read_dir(dir_path: &PathBuf) -> Result<ReadDir, CrawlerError> {
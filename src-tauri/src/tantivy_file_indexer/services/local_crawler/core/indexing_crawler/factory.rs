@@ -1,13 +1,23 @@
-use super::{worker, worker_task_handle::CrawlerWorkerTaskHandle};
-use std::sync::Arc;
+use super::{
+    worker, worker_status::CrawlerWorkerStatus, worker_task_handle::CrawlerWorkerTaskHandle,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 use tokio::sync::mpsc;
 
+use super::super::settings::SharedCrawlerSettings;
+
 use crate::{
     shared::models::sys_file_model::SystemFileModel,
     tantivy_file_indexer::{
+        services::local_crawler::analyzer::service::FileCrawlerAnalyzerService,
         services::local_crawler::core::indexing_crawler::plugins::{
-            FiltererPlugin, GarbageCollectorPlugin, ThrottleAmount, ThrottlePlugin,
+            ArchiveIndexerPlugin, ChangeDetectorPlugin, FiltererPlugin, GarbageCollectorPlugin,
+            IndexRootsPlugin, MetadataExtractorPlugin, ThrottlePlugin,
         },
+        services::volumes::service::VolumeService,
         shared::indexing_crawler::traits::{
             commit_pipeline::CrawlerCommitPipeline, crawler_queue_api::CrawlerQueueApi,
         },
@@ -24,7 +34,16 @@ where
     worker_batch_size: usize,
     garbage_collector: Option<Arc<GarbageCollectorPlugin>>,
     filterer: Option<Arc<FiltererPlugin>>,
-    throttle: ThrottlePlugin,
+    metadata_extractor: Option<Arc<MetadataExtractorPlugin>>,
+    archive_indexer: Option<Arc<ArchiveIndexerPlugin>>,
+    index_roots: Option<Arc<IndexRootsPlugin>>,
+    analyzer: Option<Arc<FileCrawlerAnalyzerService>>,
+    change_detector: Option<Arc<ChangeDetectorPlugin>>,
+    throttle: Option<Arc<ThrottlePlugin>>,
+    volumes: Option<Arc<VolumeService>>,
+    settings: Option<SharedCrawlerSettings>,
+    /// Every worker that gets built has a different id, so that it can be told apart in the crawler status
+    next_worker_id: AtomicUsize,
 }
 
 impl<C, P> IndexingCrawlersFactory<C, P>
@@ -39,7 +58,15 @@ where
             worker_batch_size: 512,
             garbage_collector: None,
             filterer: None,
-            throttle: ThrottlePlugin::new(),
+            metadata_extractor: None,
+            archive_indexer: None,
+            index_roots: None,
+            analyzer: None,
+            change_detector: None,
+            throttle: None,
+            volumes: None,
+            settings: None,
+            next_worker_id: AtomicUsize::new(0),
         }
     }
     pub fn set_batch_size(mut self, size: usize) -> Self {
@@ -54,8 +81,36 @@ where
         self.filterer = Some(f);
         self
     }
-    pub fn set_throttle(&mut self, t: ThrottleAmount) -> &Self {
-        self.throttle.set(t);
+    pub fn set_metadata_extractor(mut self, e: Arc<MetadataExtractorPlugin>) -> Self {
+        self.metadata_extractor = Some(e);
+        self
+    }
+    pub fn set_archive_indexer(mut self, a: Arc<ArchiveIndexerPlugin>) -> Self {
+        self.archive_indexer = Some(a);
+        self
+    }
+    pub fn set_index_roots(mut self, r: Arc<IndexRootsPlugin>) -> Self {
+        self.index_roots = Some(r);
+        self
+    }
+    pub fn set_analyzer(mut self, a: Arc<FileCrawlerAnalyzerService>) -> Self {
+        self.analyzer = Some(a);
+        self
+    }
+    pub fn set_change_detector(mut self, d: Arc<ChangeDetectorPlugin>) -> Self {
+        self.change_detector = Some(d);
+        self
+    }
+    pub fn set_throttle(mut self, t: Arc<ThrottlePlugin>) -> Self {
+        self.throttle = Some(t);
+        self
+    }
+    pub fn set_volumes(mut self, v: Arc<VolumeService>) -> Self {
+        self.volumes = Some(v);
+        self
+    }
+    pub fn set_settings(mut self, s: SharedCrawlerSettings) -> Self {
+        self.settings = Some(s);
         self
     }
     /// Returns a handle to the crawler tasks
@@ -70,12 +125,15 @@ where
         let mut handles = Vec::new();
         for _ in 0..num_workers {
             let (sender, receiver) = mpsc::channel(10);
+            let id = self.next_worker_id.fetch_add(1, Ordering::Relaxed);
+            let status = Arc::new(CrawlerWorkerStatus::new(id));
 
             let mut worker = worker::IndexingCrawlerWorker::new(
                 Arc::clone(&self.crawler_queue),
                 Arc::clone(&self.pipeline),
                 self.worker_batch_size,
                 receiver,
+                Arc::clone(&status),
             );
 
             // Inject a garbage collector if there is one
@@ -90,13 +148,57 @@ where
                 worker.inject_filterer(filterer);
             }
 
-            // Inject a throttle
-            worker.set_throttle(self.throttle.clone());
+            // Inject a metadata extractor if there is one
+            if let Some(e) = &self.metadata_extractor {
+                let extractor = Arc::clone(e);
+                worker.inject_metadata_extractor(extractor);
+            }
+
+            // Inject an archive indexer if there is one
+            if let Some(a) = &self.archive_indexer {
+                let indexer = Arc::clone(a);
+                worker.inject_archive_indexer(indexer);
+            }
+
+            // Inject the index roots if there are any
+            if let Some(r) = &self.index_roots {
+                let roots = Arc::clone(r);
+                worker.inject_index_roots(roots);
+            }
+
+            // Inject an analyzer if there is one
+            if let Some(a) = &self.analyzer {
+                let analyzer = Arc::clone(a);
+                worker.inject_analyzer(analyzer);
+            }
+
+            // Inject a change detector if there is one
+            if let Some(d) = &self.change_detector {
+                let change_detector = Arc::clone(d);
+                worker.inject_change_detector(change_detector);
+            }
+
+            // Inject a throttle if there is one
+            if let Some(t) = &self.throttle {
+                let throttle = Arc::clone(t);
+                worker.inject_throttle(throttle);
+            }
+
+            // Inject the volumes if there are any
+            if let Some(v) = &self.volumes {
+                let volumes = Arc::clone(v);
+                worker.inject_volumes(volumes);
+            }
+
+            // Inject the crawler settings if the task manager shares them
+            if let Some(s) = &self.settings {
+                worker.inject_settings(s.clone());
+            }
 
             let task = tokio::spawn(async move {
                 worker.worker_task().await;
             });
-            handles.push(CrawlerWorkerTaskHandle::new(sender, task));
+            handles.push(CrawlerWorkerTaskHandle::new(sender, task, status));
         }
         handles
     }
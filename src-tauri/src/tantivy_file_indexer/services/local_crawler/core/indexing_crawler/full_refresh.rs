@@ -0,0 +1,84 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use tokio::task::JoinHandle;
+
+use crate::tantivy_file_indexer::{
+    services::local_db::tables::app_kv_store::api::AppKvStoreTable,
+    shared::indexing_crawler::{
+        models::crawler_file::CrawlerFile, traits::crawler_queue_api::CrawlerQueueApi,
+    },
+};
+
+use super::super::settings::CrawlerSettings;
+use super::plugins::IndexRootsPlugin;
+
+/// How often the index roots are checked for whether they are due for another crawl
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Pushes the index roots to the crawler queue whenever they are due for another crawl, which picks up the changes that
+/// the index watcher missed. The first check happens right away, so roots that have never been crawled get crawled as
+/// soon as the crawlers are dispatched
+///
+/// Runs until the returned task is aborted
+pub fn spawn<C>(
+    queue: Arc<C>,
+    index_roots: Arc<IndexRootsPlugin>,
+    kv_store: AppKvStoreTable,
+) -> JoinHandle<()>
+where
+    C: CrawlerQueueApi,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            // Read every time, so that a new interval applies without restarting the crawlers
+            let refresh_interval_minutes = match CrawlerSettings::get_from_db(&kv_store).await {
+                Ok(settings) => settings.full_refresh_interval_minutes(),
+                Err(err) => {
+                    println!("Full refresh: Error reading the crawler settings: {}", err);
+                    continue;
+                }
+            };
+            if let Err(err) =
+                push_due_roots(queue.as_ref(), &index_roots, refresh_interval_minutes).await
+            {
+                println!("Full refresh: Error pushing the index roots: {}", err);
+            }
+        }
+    })
+}
+
+/// Roots without their own refresh interval use `default_interval_minutes`
+async fn push_due_roots<C>(
+    queue: &C,
+    index_roots: &IndexRootsPlugin,
+    default_interval_minutes: u32,
+) -> Result<(), String>
+where
+    C: CrawlerQueueApi,
+{
+    let roots = index_roots
+        .get_due(default_interval_minutes)
+        .await
+        .map_err(|err| err.to_string())?;
+    if roots.is_empty() {
+        return Ok(());
+    }
+
+    let files: Vec<CrawlerFile> = roots
+        .iter()
+        .map(|root| CrawlerFile {
+            path: root.path.clone().into(),
+            priority: root.priority,
+            taken: false,
+            added_at: Utc::now(),
+        })
+        .collect();
+    queue.push(&files).await.map_err(|err| err.to_string())?;
+    index_roots
+        .mark_crawled(&roots)
+        .await
+        .map_err(|err| err.to_string())
+}
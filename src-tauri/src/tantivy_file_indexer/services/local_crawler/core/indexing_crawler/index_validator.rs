@@ -0,0 +1,462 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::task::JoinHandle;
+
+use crate::{
+    get_parent_directory,
+    shared::{converters::system_time_to_chrono_datetime, models::sys_file_model::SystemFileModel},
+    tantivy_file_indexer::{
+        models::index_validator_model::IndexValidatorModel,
+        services::{
+            local_db::{
+                service::LocalDbService,
+                tables::{
+                    app_kv_store::api::AppKvStoreTable, directory_states::api::DirectoryStatesTable,
+                },
+            },
+            search_index::pipelines::tantivy_pipeline::TantivyPipeline,
+            volumes::service::VolumeService,
+        },
+        shared::indexing_crawler::{
+            models::crawler_file::CrawlerFile,
+            traits::{commit_pipeline::CrawlerCommitPipeline, crawler_queue_api::CrawlerQueueApi},
+        },
+    },
+};
+
+use super::super::{crawler_queue::queue::Priority, settings::CrawlerSettings};
+use super::plugins::{IndexRootsPlugin, ThrottlePlugin};
+
+const KV_STORE_NAME: &str = "indexValidatorStatus";
+/// How often the validator checks whether the next sweep is due, while it is waiting for it
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How long the validator rests between chunks, on top of the throttle's rest after each file
+const CHUNK_REST: Duration = Duration::from_secs(1);
+/// After every index root, so that the validator never gets in the way of a crawl
+const REQUEUE_PRIORITY: Priority = 9;
+const MAX_RECENTLY_REMOVED: usize = 20;
+
+/// What the validator found out about an indexed file
+#[derive(Debug, PartialEq)]
+enum Finding {
+    Unchanged,
+    /// The file was modified since it was indexed
+    Changed,
+    /// The file doesn't exist anymore
+    Missing,
+    /// The file is on a drive that isn't connected, so there is no telling
+    Offline,
+}
+
+/// Goes through the whole index a chunk at a time, removing the files that no longer exist and pushing the
+/// directories of the files that changed to the crawler queue. This catches what both the index watcher and the full
+/// refresh missed, such as files that were deleted while the app was closed from directories that aren't crawled
+/// anymore.
+///
+/// Where the validator is at and what it found is saved to the KV table after every chunk, so that a sweep picks up
+/// where it left off after a restart. Once a sweep finishes, the next one starts after the interval in the crawler
+/// settings.
+///
+/// Runs until the returned task is aborted
+pub fn spawn<C>(
+    pipeline: Arc<TantivyPipeline>,
+    queue: Arc<C>,
+    index_roots: Arc<IndexRootsPlugin>,
+    volumes: Arc<VolumeService>,
+    throttle: Arc<ThrottlePlugin>,
+    db_service: Arc<LocalDbService>,
+) -> JoinHandle<()>
+where
+    C: CrawlerQueueApi,
+{
+    tokio::spawn(async move {
+        let kv_store = db_service.kv_store_table();
+        loop {
+            // Read every time, so that new settings apply without restarting the crawlers
+            let settings = match CrawlerSettings::get_from_db(kv_store).await {
+                Ok(settings) => settings,
+                Err(err) => {
+                    println!(
+                        "Index validator: Error reading the crawler settings: {}",
+                        err
+                    );
+                    tokio::time::sleep(CHECK_INTERVAL).await;
+                    continue;
+                }
+            };
+            let mut state = match kv_store
+                .get_or_create_default::<IndexValidatorModel>(KV_STORE_NAME)
+                .await
+            {
+                Ok(state) => state,
+                Err(err) => {
+                    println!("Index validator: Error reading where it left off: {}", err);
+                    tokio::time::sleep(CHECK_INTERVAL).await;
+                    continue;
+                }
+            };
+            if settings.disable_index_validator
+                || !is_due(&state, settings.index_validator_interval(), Utc::now())
+            {
+                tokio::time::sleep(CHECK_INTERVAL).await;
+                continue;
+            }
+
+            let validator = ChunkValidator {
+                pipeline: &pipeline,
+                queue: queue.as_ref(),
+                index_roots: &index_roots,
+                volumes: &volumes,
+                throttle: &throttle,
+                directory_states: db_service.directory_states_table(),
+            };
+            if let Err(err) = validator
+                .validate_next_chunk(&mut state, settings.index_validator_chunk_size())
+                .await
+            {
+                println!("Index validator: Error validating a chunk: {}", err);
+                tokio::time::sleep(CHECK_INTERVAL).await;
+                continue;
+            }
+            if let Err(err) = kv_store.set(KV_STORE_NAME.to_string(), &state).await {
+                println!("Index validator: Error saving where it is at: {}", err);
+            }
+            tokio::time::sleep(CHUNK_REST).await;
+        }
+    })
+}
+
+/// Forgets where the validator is at and what it found, such as after the index got wiped. The next sweep starts from
+/// the beginning right away
+pub async fn reset(kv_store: &AppKvStoreTable) -> Result<(), String> {
+    kv_store
+        .set(KV_STORE_NAME.to_string(), IndexValidatorModel::default())
+        .await
+}
+
+struct ChunkValidator<'a, C> {
+    pipeline: &'a TantivyPipeline,
+    queue: &'a C,
+    index_roots: &'a IndexRootsPlugin,
+    volumes: &'a VolumeService,
+    throttle: &'a ThrottlePlugin,
+    directory_states: &'a DirectoryStatesTable,
+}
+
+impl<C> ChunkValidator<'_, C>
+where
+    C: CrawlerQueueApi,
+{
+    /// Checks the files that come after the cursor, and moves the cursor past them. Starts a new sweep if there isn't
+    /// one going, and finishes it once the end of the index is reached
+    async fn validate_next_chunk(
+        &self,
+        state: &mut IndexValidatorModel,
+        chunk_size: usize,
+    ) -> Result<(), String> {
+        if state.sweep_started_at.is_none() {
+            state.sweep_started_at = Some(Utc::now());
+            state.cursor.clear();
+            state.current = Default::default();
+        }
+        let (models, next_cursor) = self
+            .pipeline
+            .get_chunk_after(&state.cursor, chunk_size)
+            .await?;
+
+        // So that a drive that was unplugged a moment ago doesn't get its files removed
+        self.volumes.refresh().await;
+        let mut missing = Vec::new();
+        let mut changed_dirs = HashSet::new();
+        for model in models {
+            match check(&model, self.volumes).await {
+                Finding::Unchanged => {}
+                Finding::Changed => {
+                    state.current.num_changed += 1;
+                    changed_dirs.insert(dir_to_recrawl(&model));
+                }
+                Finding::Missing => missing.push(model.file_path),
+                Finding::Offline => state.current.num_offline += 1,
+            }
+            state.current.num_checked += 1;
+            self.throttle.rest_short().await;
+        }
+
+        state.current.num_removed += missing.len() as u64;
+        state
+            .current
+            .recently_removed
+            .extend(missing.iter().cloned());
+        let num_extra = state
+            .current
+            .recently_removed
+            .len()
+            .saturating_sub(MAX_RECENTLY_REMOVED);
+        state.current.recently_removed.drain(..num_extra);
+        self.pipeline.remove_many(missing).await?;
+        requeue(
+            self.queue,
+            self.index_roots,
+            self.directory_states,
+            changed_dirs,
+        )
+        .await?;
+
+        match next_cursor {
+            Some(cursor) => state.cursor = cursor,
+            None => {
+                state.cursor.clear();
+                state.sweep_started_at = None;
+                state.last_sweep_finished_at = Some(Utc::now());
+                state.last_sweep = Some(std::mem::take(&mut state.current));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a changed file gets picked up by a crawl. A changed directory is crawled itself
+fn dir_to_recrawl(model: &SystemFileModel) -> String {
+    if model.is_directory {
+        model.file_path.clone()
+    } else {
+        get_parent_directory(&model.file_path)
+    }
+}
+
+/// Pushes the directories to the crawler queue after forgetting their states. Editing a file in place doesn't change
+/// the modified time of its directory, so the change detector would skip the directory otherwise.
+///
+/// Directories outside of the index roots are left alone, since crawling them would index them
+async fn requeue<C>(
+    queue: &C,
+    index_roots: &IndexRootsPlugin,
+    directory_states: &DirectoryStatesTable,
+    dirs: HashSet<String>,
+) -> Result<(), String>
+where
+    C: CrawlerQueueApi,
+{
+    let mut paths = Vec::new();
+    for dir in dirs {
+        if index_roots.root_for(Path::new(&dir)).await.is_some() {
+            paths.push(dir);
+        }
+    }
+    if paths.is_empty() {
+        return Ok(());
+    }
+    directory_states
+        .delete_many(&paths)
+        .await
+        .map_err(|err| err.to_string())?;
+    let files: Vec<CrawlerFile> = paths
+        .into_iter()
+        .map(|path| CrawlerFile {
+            path: PathBuf::from(path),
+            priority: REQUEUE_PRIORITY,
+            taken: false,
+            added_at: Utc::now(),
+        })
+        .collect();
+    queue.push(&files).await.map_err(|err| err.to_string())
+}
+
+/// A sweep that was started keeps going, and a new one starts once `interval` has passed since the last one finished
+fn is_due(state: &IndexValidatorModel, interval: chrono::Duration, now: DateTime<Utc>) -> bool {
+    if state.sweep_started_at.is_some() {
+        return true;
+    }
+    state
+        .last_sweep_finished_at
+        .is_none_or(|finished_at| now - finished_at >= interval)
+}
+
+/// Files inside of archives are left to their archive, since whatever happens to the archive happens to them as well
+async fn check(model: &SystemFileModel, volumes: &VolumeService) -> Finding {
+    if model.container_path.is_some() {
+        return Finding::Unchanged;
+    }
+    let path = Path::new(&model.file_path);
+    // A symlink is indexed as itself, even if it is broken
+    let metadata = match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return if volumes.is_offline(path) {
+                Finding::Offline
+            } else {
+                Finding::Missing
+            };
+        }
+        // Files that can't be read right now aren't necessarily gone
+        Err(_) => return Finding::Unchanged,
+    };
+    if model.is_symlink {
+        return Finding::Unchanged;
+    }
+    match metadata.modified().map(system_time_to_chrono_datetime) {
+        Ok(Ok(modified)) => compare_modified(model.date_modified, modified),
+        _ => Finding::Unchanged,
+    }
+}
+
+/// The index only keeps the modified time to the second
+fn compare_modified(indexed: DateTime<Utc>, current: DateTime<Utc>) -> Finding {
+    if indexed.timestamp() == current.timestamp() {
+        Finding::Unchanged
+    } else {
+        Finding::Changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tantivy_file_indexer::{
+        dtos::index_root_dto::IndexRootDTO,
+        services::local_crawler::core::crawler_queue::memory_queue::MemoryCrawlerQueue,
+    };
+
+    use super::super::plugins::ChangeDetectorPlugin;
+    use super::*;
+
+    fn model(file_path: &str, is_directory: bool) -> SystemFileModel {
+        SystemFileModel {
+            name: String::new(),
+            file_path: file_path.to_string(),
+            date_modified: Utc::now(),
+            date_created: Utc::now(),
+            size: 0,
+            score: 0.0,
+            is_directory,
+            audio_tags: None,
+            document: None,
+            symbols: None,
+            container_path: None,
+            is_symlink: false,
+            link_target: None,
+        }
+    }
+
+    #[test]
+    fn changed_directories_are_crawled_themselves() {
+        let dir = Path::new("docs").join("reports");
+        let file = dir.join("q1.pdf");
+        assert_eq!(
+            dir_to_recrawl(&model(&file.to_string_lossy(), false)),
+            dir.to_string_lossy()
+        );
+        assert_eq!(
+            dir_to_recrawl(&model(&dir.to_string_lossy(), true)),
+            dir.to_string_lossy()
+        );
+    }
+
+    #[tokio::test]
+    async fn files_changed_in_place_get_indexed_again_when_only_modified_times_are_compared() {
+        let root =
+            std::env::temp_dir().join(format!("index_validator_test_{}", uuid::Uuid::new_v4()));
+        let dir = root.join("docs");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.txt"), "first").unwrap();
+        let db_path = root.with_extension("db");
+        let db_url = format!("sqlite://{}?mode=rwc", db_path.to_string_lossy());
+        let db = Arc::new(LocalDbService::connect(&db_url, None).await.unwrap());
+        db.index_roots_table()
+            .insert(IndexRootDTO {
+                path: root.to_string_lossy().to_string(),
+                enabled: true,
+                max_depth: None,
+                priority: 1,
+                refresh_interval_minutes: None,
+                follow_symlinks: false,
+                include_hidden: false,
+            })
+            .await
+            .unwrap();
+        let index_roots = Arc::new(IndexRootsPlugin::new(Arc::clone(&db)));
+        let queue = MemoryCrawlerQueue::load(&db, Arc::clone(&index_roots))
+            .await
+            .unwrap();
+        let detector = ChangeDetectorPlugin::new(Arc::clone(&db));
+        let settings = CrawlerSettings {
+            compare_modified_times_only: true,
+            ..Default::default()
+        };
+        let is_unchanged = || async {
+            let metadata = std::fs::metadata(&dir).unwrap();
+            let check = detector.check(&dir, &metadata, &settings).await.unwrap();
+            check.finish().unchanged
+        };
+
+        let metadata = std::fs::metadata(&dir).unwrap();
+        let check = detector.check(&dir, &metadata, &settings).await.unwrap();
+        detector.record(&[check.finish().state]).await;
+        // Writing to a file doesn't touch the modified time of its directory
+        std::fs::write(dir.join("notes.txt"), "second").unwrap();
+        assert!(is_unchanged().await);
+
+        let notes = dir.join("notes.txt").to_string_lossy().to_string();
+        let changed_dirs = HashSet::from([dir_to_recrawl(&model(&notes, false))]);
+        requeue(
+            &queue,
+            &index_roots,
+            db.directory_states_table(),
+            changed_dirs,
+        )
+        .await
+        .unwrap();
+
+        assert!(!is_unchanged().await);
+        assert_eq!(
+            queue.fetch_many(10).await.unwrap(),
+            vec![(dir.clone(), REQUEUE_PRIORITY)]
+        );
+
+        drop(queue);
+        drop(db);
+        let _ = std::fs::remove_dir_all(&root);
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn sweeps_start_after_the_interval_and_keep_going() {
+        let now = Utc::now();
+        let interval = chrono::Duration::hours(1);
+        assert!(is_due(&IndexValidatorModel::default(), interval, now));
+
+        let finished = IndexValidatorModel {
+            last_sweep_finished_at: Some(now - chrono::Duration::minutes(30)),
+            ..Default::default()
+        };
+        assert!(!is_due(&finished, interval, now));
+        assert!(is_due(
+            &finished,
+            interval,
+            now + chrono::Duration::minutes(30)
+        ));
+
+        let in_progress = IndexValidatorModel {
+            sweep_started_at: Some(now),
+            ..finished
+        };
+        assert!(is_due(&in_progress, interval, now));
+    }
+
+    #[test]
+    fn only_whole_seconds_count_as_changes() {
+        let indexed = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let same_second = DateTime::from_timestamp(1_700_000_000, 999_000_000).unwrap();
+        assert_eq!(compare_modified(indexed, same_second), Finding::Unchanged);
+        assert_eq!(
+            compare_modified(indexed, indexed + chrono::Duration::seconds(1)),
+            Finding::Changed
+        );
+    }
+}
@@ -1,7 +1,11 @@
 pub mod plugins;
 mod crawler;
 pub mod factory;
-mod idle;
+pub mod full_refresh;
+pub mod index_validator;
+pub mod symlinks;
 pub mod task_manager;
+pub mod volume_monitor;
 mod worker;
-mod worker_task_handle;
\ No newline at end of file
+pub mod worker_status;
+pub mod worker_task_handle;
\ No newline at end of file
@@ -0,0 +1,183 @@
+use std::{path::Path, sync::Arc};
+
+use chrono::Utc;
+
+use crate::{
+    shared::models::sys_file_model::SystemFileModel,
+    tantivy_file_indexer::{
+        services::{
+            local_crawler::core::{extractors::archive, settings::CrawlerSettings},
+            local_db::{
+                service::LocalDbService, tables::directory_states::entities::directory_state,
+            },
+        },
+        shared::indexing_crawler::traits::commit_pipeline::CrawlerCommitPipeline,
+    },
+};
+
+/// Indexes the files inside of archives as virtual entries whose parent is the archive itself.
+///
+/// This only happens if `IndexArchiveMembers` is turned on in the crawler settings. Archives that have been read get a
+/// directory state under their own path, so that they are only read again once they have been modified
+pub struct ArchiveIndexerPlugin {
+    db_service: Arc<LocalDbService>,
+}
+
+impl ArchiveIndexerPlugin {
+    pub fn new(db_service: Arc<LocalDbService>) -> Self {
+        Self { db_service }
+    }
+
+    /// The archives in `files` whose members could get indexed. These should be passed to `index_members` once
+    /// `files` have been upserted
+    pub fn archives_in(
+        &self,
+        files: &[SystemFileModel],
+        settings: &CrawlerSettings,
+    ) -> Vec<SystemFileModel> {
+        if !settings.index_archive_members {
+            return Vec::new();
+        }
+        let max_file_size = settings.max_extract_file_size();
+        files
+            .iter()
+            .filter(|file| {
+                !file.is_directory
+                    && file.size <= max_file_size
+                    && archive::is_archive_file(Path::new(&file.file_path))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Indexes the members of every archive that is new or has been modified since it was last read.
+    /// Members that are no longer in a modified archive get removed
+    pub async fn index_members<P>(&self, pipeline: &P, archives: Vec<SystemFileModel>)
+    where
+        P: CrawlerCommitPipeline<InputModel = SystemFileModel>,
+    {
+        let mut states = Vec::new();
+        for archive_file in archives {
+            let modified_at = archive_file.date_modified.timestamp_millis();
+            if self.read_at(&archive_file.file_path).await == Some(modified_at) {
+                continue;
+            }
+            if self.index_archive(pipeline, &archive_file).await {
+                states.push(directory_state::Model {
+                    path: archive_file.file_path,
+                    modified_at,
+                    entries_hash: None,
+                    indexed_at: Utc::now().timestamp(),
+                });
+            }
+        }
+        if states.is_empty() {
+            return;
+        }
+        if let Err(err) = self
+            .db_service
+            .directory_states_table()
+            .upsert_many(&states)
+            .await
+        {
+            println!(
+                "ArchiveIndexerPlugin: could not save the states of {} archives: {}",
+                states.len(),
+                err
+            );
+        }
+    }
+
+    /// The modified time that the archive had when it was last read, if it has been read
+    async fn read_at(&self, archive_path: &str) -> Option<i64> {
+        match self
+            .db_service
+            .directory_states_table()
+            .get(archive_path)
+            .await
+        {
+            Ok(state) => state.map(|state| state.modified_at),
+            Err(err) => {
+                println!(
+                    "ArchiveIndexerPlugin: could not read the state of {}: {}",
+                    archive_path, err
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns `true` if the archive could be read and its members got indexed, even if it turned out to be empty
+    async fn index_archive<P>(&self, pipeline: &P, archive_file: &SystemFileModel) -> bool
+    where
+        P: CrawlerCommitPipeline<InputModel = SystemFileModel>,
+    {
+        let archive_path = archive_file.file_path.clone();
+        let path_clone = archive_path.clone();
+        // Reading archives is blocking IO, so keep it off of the async workers
+        let members = match tokio::task::spawn_blocking(move || {
+            archive::list_archive_members(Path::new(&path_clone))
+        })
+        .await
+        {
+            Ok(Ok(members)) => members,
+            Ok(Err(err)) => {
+                println!(
+                    "ArchiveIndexerPlugin: could not read archive {}: {}",
+                    archive_path, err
+                );
+                return false;
+            }
+            Err(err) => {
+                println!("ArchiveIndexerPlugin: listing task failed: {}", err);
+                return false;
+            }
+        };
+
+        let models: Vec<SystemFileModel> = members
+            .into_iter()
+            .map(|member| Self::member_to_model(archive_file, member))
+            .collect();
+
+        // Upserting under the archive removes the members that are no longer in it
+        match pipeline.upsert_many(models, archive_path.clone()).await {
+            Ok(_) => true,
+            Err(err) => {
+                println!(
+                    "ArchiveIndexerPlugin: could not index the members of {}: {}",
+                    archive_path, err
+                );
+                false
+            }
+        }
+    }
+
+    fn member_to_model(
+        archive_file: &SystemFileModel,
+        member: archive::ArchiveMember,
+    ) -> SystemFileModel {
+        let name = member
+            .inner_path
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        // Fall back to the archive's date for formats that don't store one per member
+        let date_modified = member.modified.unwrap_or(archive_file.date_modified);
+        SystemFileModel {
+            name,
+            file_path: archive::member_path(&archive_file.file_path, &member.inner_path),
+            date_modified,
+            date_created: date_modified,
+            size: member.size,
+            score: 1.0,
+            is_directory: false,
+            audio_tags: None,
+            document: None,
+            symbols: None,
+            container_path: Some(archive_file.file_path.clone()),
+            is_symlink: false,
+            link_target: None,
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::{fs::Metadata, path::Path, sync::Arc, time::UNIX_EPOCH};
+
+use chrono::Utc;
+
+use crate::tantivy_file_indexer::services::{
+    local_crawler::core::settings::CrawlerSettings,
+    local_db::{service::LocalDbService, tables::directory_states::entities::directory_state},
+};
+
+/// Finds out whether a directory changed since its files were last indexed, so that unchanged directories don't have to
+/// be indexed again. A directory counts as changed if its modified time changed, or unless turned off, if the names,
+/// sizes or modified times of its entries changed
+pub struct ChangeDetectorPlugin {
+    db_service: Arc<LocalDbService>,
+}
+
+impl ChangeDetectorPlugin {
+    pub fn new(db_service: Arc<LocalDbService>) -> Self {
+        Self { db_service }
+    }
+
+    /// Starts checking a directory, whose entries then need to be added to the check as they get read.
+    /// Returns `None` if unchanged directories are indexed again anyway, or if the modified time can't be read
+    pub async fn check(
+        &self,
+        dir: &Path,
+        metadata: &Metadata,
+        settings: &CrawlerSettings,
+    ) -> Option<DirectoryCheck> {
+        if settings.reindex_unchanged_directories {
+            return None;
+        }
+        let modified_at = modified_millis(metadata)?;
+        let path = dir.to_string_lossy().to_string();
+        let stored = match self.db_service.directory_states_table().get(&path).await {
+            Ok(stored) => stored,
+            Err(err) => {
+                println!(
+                    "ChangeDetectorPlugin: could not read the state of {}: {}",
+                    path, err
+                );
+                None
+            }
+        };
+        Some(DirectoryCheck {
+            path,
+            modified_at,
+            stored,
+            entries: (!settings.compare_modified_times_only).then_some(EntriesHash::default()),
+            stop_at_unchanged: settings.stop_at_unchanged_directories,
+        })
+    }
+
+    /// Should be called once the files of the directories have been indexed, so that they can be skipped next time
+    pub async fn record(&self, states: &[directory_state::Model]) {
+        if states.is_empty() {
+            return;
+        }
+        if let Err(err) = self
+            .db_service
+            .directory_states_table()
+            .upsert_many(states)
+            .await
+        {
+            println!(
+                "ChangeDetectorPlugin: could not save the states of {} directories: {}",
+                states.len(),
+                err
+            );
+        }
+    }
+}
+
+/// A hash of every entry that doesn't depend on the order that the entries were read in
+#[derive(Default)]
+struct EntriesHash {
+    sum: u64,
+    count: u64,
+}
+
+/// Whether one directory changed, which is known once all of its entries have been added
+pub struct DirectoryCheck {
+    path: String,
+    modified_at: i64,
+    stored: Option<directory_state::Model>,
+    /// `None` if only the modified time of the directory is compared
+    entries: Option<EntriesHash>,
+    stop_at_unchanged: bool,
+}
+
+impl DirectoryCheck {
+    /// Returns `true` if the entries don't have to be read at all, since they aren't compared and the unchanged
+    /// directory won't be descended into
+    pub fn can_skip_reading(&self) -> bool {
+        self.entries.is_none()
+            && self.stop_at_unchanged
+            && self
+                .stored
+                .as_ref()
+                .is_some_and(|stored| stored.modified_at == self.modified_at)
+    }
+
+    pub fn add_entry(&mut self, path: &Path, metadata: &Metadata) {
+        if let Some(entries) = &mut self.entries {
+            let mut bytes = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+                .into_bytes();
+            bytes.extend(metadata.len().to_le_bytes());
+            bytes.extend(modified_millis(metadata).unwrap_or_default().to_le_bytes());
+            entries.sum = entries.sum.wrapping_add(fnv1a(&bytes));
+            entries.count += 1;
+        }
+    }
+
+    pub fn finish(self) -> DirectoryChange {
+        let entries_hash = self.entries.map(|entries| {
+            let mut bytes = entries.sum.to_le_bytes().to_vec();
+            bytes.extend(entries.count.to_le_bytes());
+            fnv1a(&bytes) as i64
+        });
+        let unchanged = self.stored.is_some_and(|stored| {
+            stored.modified_at == self.modified_at && stored.entries_hash == entries_hash
+        });
+        DirectoryChange {
+            unchanged,
+            should_descend: !(unchanged && self.stop_at_unchanged),
+            state: directory_state::Model {
+                path: self.path,
+                modified_at: self.modified_at,
+                entries_hash,
+                indexed_at: Utc::now().timestamp(),
+            },
+        }
+    }
+}
+
+pub struct DirectoryChange {
+    pub unchanged: bool,
+    pub should_descend: bool,
+    /// To be recorded once the files of the directory have been indexed
+    pub state: directory_state::Model,
+}
+
+fn modified_millis(metadata: &Metadata) -> Option<i64> {
+    let modified = metadata.modified().ok()?;
+    let millis = match modified.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis() as i64,
+        Err(err) => -(err.duration().as_millis() as i64),
+    };
+    Some(millis)
+}
+
+/// FNV-1a, since the hashes are stored and need to stay the same between versions of the app
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
+}
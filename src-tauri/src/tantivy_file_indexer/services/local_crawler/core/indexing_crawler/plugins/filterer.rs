@@ -1,6 +1,7 @@
-use std::path::Path;
+use std::{path::Path, sync::Arc};
 
 use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::RwLock;
 
 use crate::tantivy_file_indexer::{
     models::auto_serializing_value::AutoSerializingValue,
@@ -8,6 +9,14 @@ use crate::tantivy_file_indexer::{
     util::{path::get_path_components, string},
 };
 
+use super::super::symlinks::SymlinkPolicy;
+use super::{
+    ignore_files::IgnoreFileCache,
+    path_rules::{PathRuleAction, PathRuleMatcher, PathRuleModel},
+};
+
+const PATH_RULES_KEY: &str = "crawlerPathRules";
+
 pub enum ShouldIndexResult {
     ShouldIndex,
     /// The `String` is the reason why it isn't getting indexed
@@ -27,6 +36,13 @@ pub struct FiltererPlugin {
     /// All directory names should be in lowercase. If the filterer finds an exact match, then the directory will be excluded
     dir_names_exclude: JsonVal<Vec<String>>,
     exclude_dirs_starting_with_period: JsonVal<bool>,
+    /// Whether `.gitignore`, `.ignore` and `.supersearcherignore` files get respected
+    respect_ignore_files: JsonVal<bool>,
+    ignore_files: IgnoreFileCache,
+    path_rules: JsonVal<Vec<PathRuleModel>>,
+    /// Compiled from `path_rules` the first time that they are needed and whenever they change after that
+    path_rule_matcher: RwLock<Option<Arc<PathRuleMatcher>>>,
+    symlink_policy: JsonVal<SymlinkPolicy>,
 }
 
 impl FiltererPlugin {
@@ -37,10 +53,23 @@ impl FiltererPlugin {
             blacklisted_extensions: JsonVal::new(Vec::new()),
             dir_names_exclude: JsonVal::new(Vec::new()),
             exclude_dirs_starting_with_period: JsonVal::new(false),
+            respect_ignore_files: JsonVal::new(true),
+            ignore_files: IgnoreFileCache::new(),
+            path_rules: JsonVal::new(Vec::new()),
+            path_rule_matcher: RwLock::new(None),
+            symlink_policy: JsonVal::new(SymlinkPolicy::default()),
         }
     }
 
     pub async fn should_crawl_directory(&self, dir_path: &Path) -> bool {
+        match self.check_path_rules(dir_path).await {
+            Some(PathRuleAction::Exclude) => return false,
+            // An explicit include takes precedence over the directory names and the period check
+            Some(PathRuleAction::Include) => {
+                return !self.is_excluded_by_ignore_files(dir_path, true).await
+            }
+            None => {}
+        }
         self.refresh_json("crawlerDirectoryNamesExclude", &self.dir_names_exclude)
             .await;
         self.refresh_json(
@@ -70,14 +99,44 @@ impl FiltererPlugin {
         let dir_names_exclude = self.dir_names_exclude.get_data().await;
 
         // ! Note that we need to call to_lowercase() to ensure case insensitivity
-        !path_components.iter().any(|comp| {
+        let is_name_excluded = path_components.iter().any(|comp| {
+            let comp = comp.to_lowercase();
             dir_names_exclude
                 .iter()
-                .any(|exclude| comp.to_lowercase().contains(&exclude.to_lowercase()))
-        })
+                .any(|exclude| comp == exclude.to_lowercase())
+        });
+        if is_name_excluded {
+            return false;
+        }
+        !self.is_excluded_by_ignore_files(dir_path, true).await
+    }
+
+    /// Returns `true` if a `.gitignore`, `.ignore` or `.supersearcherignore` file in one of the parent directories
+    /// excludes the path. The files get read once per directory and are cached after that
+    pub async fn is_excluded_by_ignore_files(&self, path: &Path, is_dir: bool) -> bool {
+        self.refresh_json("crawlerRespectIgnoreFiles", &self.respect_ignore_files)
+            .await;
+        if !self.respect_ignore_files.get_data().await {
+            return false;
+        }
+        self.ignore_files.is_ignored(path, is_dir).await
+    }
+
+    pub async fn symlink_policy(&self) -> SymlinkPolicy {
+        self.refresh_json("crawlerSymlinkPolicy", &self.symlink_policy)
+            .await;
+        self.symlink_policy.get_data().await
     }
 
     pub async fn should_index(&self, path: &Path) -> ShouldIndexResult {
+        match self.check_path_rules(path).await {
+            Some(PathRuleAction::Exclude) => {
+                return ShouldIndexResult::ShouldNotIndex("Excluded by a path rule".into())
+            }
+            // An explicit include takes precedence over the extension lists and the noise ratio
+            Some(PathRuleAction::Include) => return ShouldIndexResult::ShouldIndex,
+            None => {}
+        }
         self.refresh_json("crawlerWhitelistedExtensions", &self.whitelisted_extensions)
             .await;
         self.refresh_json("crawlerBlacklistedExtensions", &self.blacklisted_extensions)
@@ -117,7 +176,39 @@ impl FiltererPlugin {
         false
     }
 
-    async fn refresh_json<T>(&self, key: &str, json: &JsonVal<T>)
+    /// Returns the action of the last path rule that matches the path, if any
+    async fn check_path_rules(&self, path: &Path) -> Option<PathRuleAction> {
+        let did_update = self.refresh_json(PATH_RULES_KEY, &self.path_rules).await;
+        let matcher = self.path_rule_matcher.read().await.clone();
+        let matcher = match matcher {
+            Some(matcher) if !did_update => matcher,
+            _ => self.compile_path_rules(matcher.is_none()).await,
+        };
+        matcher.check(path)
+    }
+
+    /// Where `load` is `true` if the rules haven't been read from the KV store yet
+    async fn compile_path_rules(&self, load: bool) -> Arc<PathRuleMatcher> {
+        if load {
+            match self
+                .kv_store
+                .get_or_create(PATH_RULES_KEY, Vec::new())
+                .await
+            {
+                Ok(rules) => self.path_rules.set(rules).await,
+                Err(err) => println!(
+                    "Crawler Filterer: Error reading {}: {}",
+                    PATH_RULES_KEY, err
+                ),
+            }
+        }
+        let matcher = Arc::new(PathRuleMatcher::new(&self.path_rules.get_data().await));
+        *self.path_rule_matcher.write().await = Some(Arc::clone(&matcher));
+        matcher
+    }
+
+    /// Returns `true` if the value was updated
+    async fn refresh_json<T>(&self, key: &str, json: &JsonVal<T>) -> bool
     where
         T: Serialize + Clone + DeserializeOwned,
     {
@@ -129,9 +220,11 @@ impl FiltererPlugin {
                         key
                     );
                 }
+                did_update
             }
             Err(err) => {
                 println!("Crawler Filterer: Error updating {}: {}", key, err);
+                false
             }
         }
     }
@@ -0,0 +1,229 @@
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use lru::LruCache;
+use regex::{Regex, RegexBuilder};
+
+use crate::tantivy_file_indexer::util::glob::glob_to_regex;
+
+/// The files that ignore rules are read from, from the highest precedence to the lowest
+const IGNORE_FILE_NAMES: [&str; 3] = [".supersearcherignore", ".ignore", GITIGNORE_FILE_NAME];
+const GITIGNORE_FILE_NAME: &str = ".gitignore";
+/// The number of directories whose ignore files are kept in memory
+const CACHE_CAPACITY: usize = 4096;
+
+struct IgnoreRule {
+    /// Matches the path relative to the directory that the ignore file is in, or any of its parent directories
+    regex: Regex,
+    /// Starts with `!`, so it includes what an earlier rule excluded
+    negated: bool,
+    /// Ends with `/`, so it only matches directories
+    dir_only: bool,
+}
+
+/// The rules of one `.gitignore`, `.ignore` or `.supersearcherignore` file, which use the `.gitignore` syntax
+struct IgnoreFile {
+    rules: Vec<IgnoreRule>,
+    /// `.gitignore` files only apply inside of a git repository
+    is_gitignore: bool,
+}
+
+impl IgnoreFile {
+    fn parse(contents: &str, is_gitignore: bool) -> Self {
+        let rules = contents.lines().filter_map(parse_rule).collect();
+        Self {
+            rules,
+            is_gitignore,
+        }
+    }
+
+    /// Returns `Some(true)` if the path is ignored, `Some(false)` if it is explicitly included and `None` if no rule is
+    /// about it. `relative_path` is relative to the directory of the ignore file and separated with `/`
+    fn check(&self, relative_path: &str, is_dir: bool) -> Option<bool> {
+        // The last rule that matches wins
+        self.rules.iter().rev().find_map(|rule| {
+            let captures = rule.regex.captures(relative_path)?;
+            // A match on a parent directory means that the path is inside of a directory, which can be excluded by anything
+            let matched_parent = captures.get(1).is_some();
+            if rule.dir_only && !is_dir && !matched_parent {
+                return None;
+            }
+            Some(!rule.negated)
+        })
+    }
+}
+
+fn parse_rule(line: &str) -> Option<IgnoreRule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (pattern, negated) = match line.strip_prefix('!') {
+        Some(pattern) => (pattern, true),
+        None => (line, false),
+    };
+    let (pattern, dir_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    if pattern.is_empty() {
+        return None;
+    }
+    // A pattern with a slash before the end is relative to the directory of the ignore file, otherwise it matches
+    // names at any depth
+    let body = if pattern.contains('/') {
+        glob_to_regex(pattern.trim_start_matches('/'))
+    } else {
+        format!("(?:.*/)?{}", glob_to_regex(pattern))
+    };
+    let regex = RegexBuilder::new(&format!("^(?:{})(/.*)?$", body))
+        .case_insensitive(cfg!(windows))
+        .build()
+        .ok()?;
+    Some(IgnoreRule {
+        regex,
+        negated,
+        dir_only,
+    })
+}
+
+/// The ignore files that are in one directory
+struct DirIgnoreFiles {
+    /// From the highest precedence to the lowest
+    files: Vec<IgnoreFile>,
+    /// Has a `.git` directory, so `.gitignore` files above it don't apply
+    is_repo_root: bool,
+}
+
+impl DirIgnoreFiles {
+    async fn load(dir: &Path) -> Self {
+        let mut files = Vec::new();
+        for name in IGNORE_FILE_NAMES {
+            if let Ok(contents) = tokio::fs::read_to_string(dir.join(name)).await {
+                files.push(IgnoreFile::parse(&contents, name == GITIGNORE_FILE_NAME));
+            }
+        }
+        let is_repo_root = tokio::fs::try_exists(dir.join(".git"))
+            .await
+            .unwrap_or(false);
+        Self {
+            files,
+            is_repo_root,
+        }
+    }
+}
+
+/// Reads the `.gitignore`, `.ignore` and `.supersearcherignore` files above a path to decide whether it is ignored.
+///
+/// Files in deeper directories take precedence over files in the directories above them, and within one directory,
+/// `.supersearcherignore` takes precedence over `.ignore`, which takes precedence over `.gitignore`
+pub struct IgnoreFileCache {
+    dirs: Mutex<LruCache<PathBuf, Arc<DirIgnoreFiles>>>,
+}
+
+impl IgnoreFileCache {
+    pub fn new() -> Self {
+        Self {
+            dirs: Mutex::new(LruCache::new(NonZeroUsize::new(CACHE_CAPACITY).unwrap())),
+        }
+    }
+
+    pub async fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ancestors = Vec::new();
+        for dir in path.ancestors().skip(1) {
+            ancestors.push((dir, self.get_dir(dir).await));
+        }
+        // Without a repository, there is nothing for a `.gitignore` to apply to
+        let repo_root_index = ancestors.iter().position(|(_, dir)| dir.is_repo_root);
+
+        for (i, (dir, dir_files)) in ancestors.iter().enumerate() {
+            let Some(relative_path) = relative_path(path, dir) else {
+                continue;
+            };
+            for file in dir_files.files.iter() {
+                if file.is_gitignore && repo_root_index.is_none_or(|root| i > root) {
+                    continue;
+                }
+                if let Some(ignored) = file.check(&relative_path, is_dir) {
+                    return ignored;
+                }
+            }
+        }
+        false
+    }
+
+    async fn get_dir(&self, dir: &Path) -> Arc<DirIgnoreFiles> {
+        if let Some(dir_files) = self.lock().get(dir) {
+            return Arc::clone(dir_files);
+        }
+        let dir_files = Arc::new(DirIgnoreFiles::load(dir).await);
+        self.lock().put(dir.to_path_buf(), Arc::clone(&dir_files));
+        dir_files
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<PathBuf, Arc<DirIgnoreFiles>>> {
+        self.dirs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for IgnoreFileCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn relative_path(path: &Path, dir: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?;
+    let components: Vec<String> = relative
+        .components()
+        .map(|comp| comp.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(components.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(contents: &str, path: &str, is_dir: bool) -> Option<bool> {
+        IgnoreFile::parse(contents, false).check(path, is_dir)
+    }
+
+    #[test]
+    fn unanchored_patterns_match_at_any_depth() {
+        assert_eq!(check("target", "target", true), Some(true));
+        assert_eq!(check("target", "crates/app/target", true), Some(true));
+        assert_eq!(check("*.o", "src/main.o", false), Some(true));
+        assert_eq!(check("*.o", "src/main.c", false), None);
+    }
+
+    #[test]
+    fn patterns_with_a_slash_are_anchored() {
+        assert_eq!(check("/build", "build", true), Some(true));
+        assert_eq!(check("/build", "src/build", true), None);
+        assert_eq!(check("docs/out", "docs/out", true), Some(true));
+        assert_eq!(check("docs/out", "a/docs/out", true), None);
+    }
+
+    #[test]
+    fn contents_of_ignored_directories_are_ignored() {
+        assert_eq!(
+            check("node_modules/", "node_modules/react/index.js", false),
+            Some(true)
+        );
+        assert_eq!(check("node_modules/", "node_modules", false), None);
+    }
+
+    #[test]
+    fn later_rules_win() {
+        let contents = "*.log\n!keep.log\n# comment\n\n";
+        assert_eq!(check(contents, "debug.log", false), Some(true));
+        assert_eq!(check(contents, "keep.log", false), Some(false));
+        assert_eq!(check("!keep.log\n*.log", "keep.log", false), Some(true));
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use chrono::Utc;
+use tokio::sync::RwLock;
+
+use crate::tantivy_file_indexer::services::local_db::{
+    service::LocalDbService, tables::index_roots::entities::index_root,
+};
+
+use super::super::symlinks::VisitedLinks;
+
+/// Tells the crawlers where to start and how far to go, based on the index roots that the user has set
+pub struct IndexRootsPlugin {
+    db_service: Arc<LocalDbService>,
+    /// The enabled roots, with the deepest paths first so that nested roots take precedence over the roots that they
+    /// are inside of. `None` if they need to be read from the database again
+    roots: RwLock<Option<Arc<Vec<index_root::Model>>>>,
+    /// What was reached through symlinks under each root since it was last crawled from the top, by the id of the root
+    visited_links: Mutex<HashMap<i32, Arc<VisitedLinks>>>,
+}
+
+impl IndexRootsPlugin {
+    pub fn new(db_service: Arc<LocalDbService>) -> Self {
+        Self {
+            db_service,
+            roots: RwLock::new(None),
+            visited_links: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Should be called whenever the roots are changed, so that they get read again
+    pub async fn invalidate(&self) {
+        *self.roots.write().await = None;
+    }
+
+    pub async fn get_enabled(&self) -> Result<Arc<Vec<index_root::Model>>, sea_orm::DbErr> {
+        if let Some(roots) = self.roots.read().await.as_ref() {
+            return Ok(Arc::clone(roots));
+        }
+        let mut roots = self.db_service.index_roots_table().get_enabled().await?;
+        roots.sort_by_key(|root| std::cmp::Reverse(Path::new(&root.path).components().count()));
+        let roots = Arc::new(roots);
+        *self.roots.write().await = Some(Arc::clone(&roots));
+        Ok(roots)
+    }
+
+    /// Returns the deepest enabled root that the path is inside of
+    pub async fn root_for(&self, path: &Path) -> Option<index_root::Model> {
+        match self.get_enabled().await {
+            Ok(roots) => roots
+                .iter()
+                .find(|root| root.depth_of(path).is_some())
+                .cloned(),
+            Err(err) => {
+                println!("IndexRootsPlugin: Error reading the index roots: {}", err);
+                None
+            }
+        }
+    }
+
+    /// What was reached through symlinks under the root. Crawling the root itself starts over, so that symlinks which
+    /// were changed to point somewhere else get followed again
+    pub fn visited_links(&self, root: &index_root::Model, crawled_dir: &Path) -> Arc<VisitedLinks> {
+        let mut visited_links = self
+            .visited_links
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if crawled_dir == Path::new(&root.path) {
+            visited_links.remove(&root.id);
+        }
+        Arc::clone(visited_links.entry(root.id).or_default())
+    }
+
+    /// The enabled roots that haven't been crawled within their refresh interval, where roots without their own interval
+    /// use `default_interval_minutes`
+    pub async fn get_due(
+        &self,
+        default_interval_minutes: u32,
+    ) -> Result<Vec<index_root::Model>, sea_orm::DbErr> {
+        let now = Utc::now();
+        Ok(self
+            .get_enabled()
+            .await?
+            .iter()
+            .filter(|root| root.is_due(now, default_interval_minutes))
+            .cloned()
+            .collect())
+    }
+
+    pub async fn mark_crawled(&self, roots: &[index_root::Model]) -> Result<(), sea_orm::DbErr> {
+        let ids = roots.iter().map(|root| root.id).collect();
+        self.db_service
+            .index_roots_table()
+            .set_last_crawled_at(ids, Utc::now())
+            .await?;
+        self.invalidate().await;
+        Ok(())
+    }
+}
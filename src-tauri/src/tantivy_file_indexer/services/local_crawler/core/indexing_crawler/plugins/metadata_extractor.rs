@@ -0,0 +1,156 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+use crate::{
+    shared::models::sys_file_model::SystemFileModel,
+    tantivy_file_indexer::services::local_crawler::core::{
+        extractors::{audio, document, symbols},
+        settings::CrawlerSettings,
+    },
+};
+
+/// Reads the metadata stored inside of files, such as audio tags, the text of documents or the symbols in source code, so that it can be indexed alongside the file
+#[derive(Default)]
+pub struct MetadataExtractorPlugin;
+
+impl MetadataExtractorPlugin {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Fills in the metadata of every file that an extractor exists for. The other files are returned as they were.
+    ///
+    /// A file that fails to be parsed, or whose parser panics, is logged and still gets indexed, just without its
+    /// metadata. The rest of the batch isn't affected
+    pub async fn extract(
+        &self,
+        files: Vec<SystemFileModel>,
+        settings: &CrawlerSettings,
+    ) -> Vec<SystemFileModel> {
+        let max_file_size = settings.max_extract_file_size();
+        let fallback = files.clone();
+        // Parsing files is blocking IO, so keep it off of the async workers
+        match tokio::task::spawn_blocking(move || {
+            files
+                .into_iter()
+                .map(|file| Self::extract_one(file, max_file_size))
+                .collect::<Vec<_>>()
+        })
+        .await
+        {
+            Ok(files) => files,
+            Err(err) => {
+                println!("MetadataExtractorPlugin: extraction task failed: {}", err);
+                fallback
+            }
+        }
+    }
+
+    fn extract_one(mut file: SystemFileModel, max_file_size: u64) -> SystemFileModel {
+        if file.is_directory || file.size > max_file_size {
+            return file;
+        }
+        let path = Path::new(&file.file_path);
+        if audio::is_audio_file(path) {
+            file.audio_tags = Self::parse(&file.file_path, "audio tags", || {
+                audio::extract_audio_tags(path)
+            });
+        } else if document::is_document_file(path) {
+            file.document = Self::parse(&file.file_path, "document", || {
+                document::extract_document_metadata(path)
+            });
+        } else if symbols::is_source_file(path) {
+            file.symbols = Self::parse(&file.file_path, "symbols", || {
+                symbols::extract_symbols(path)
+            })
+            .filter(|symbols| !symbols.is_empty());
+        }
+        file
+    }
+
+    /// Runs one parser on one file, logging it and returning `None` if the parser fails or panics. The parsers can
+    /// panic on malformed files, which shouldn't take the rest of the batch down with them
+    fn parse<T>(
+        file_path: &str,
+        what: &str,
+        parser: impl FnOnce() -> Result<T, String>,
+    ) -> Option<T> {
+        let result = panic::catch_unwind(AssertUnwindSafe(parser))
+            .unwrap_or_else(|_| Err("the parser panicked".to_string()));
+        match result {
+            Ok(parsed) => Some(parsed),
+            Err(err) => {
+                println!(
+                    "MetadataExtractorPlugin: could not read the {} of {}: {}",
+                    what, file_path, err
+                );
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn file(path: &Path, contents: &[u8]) -> SystemFileModel {
+        std::fs::write(path, contents).unwrap();
+        SystemFileModel {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            file_path: path.to_string_lossy().to_string(),
+            date_modified: Utc::now(),
+            date_created: Utc::now(),
+            size: contents.len() as u64,
+            score: 0.0,
+            is_directory: false,
+            audio_tags: None,
+            document: None,
+            symbols: None,
+            container_path: None,
+            is_symlink: false,
+            link_target: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn a_failing_file_leaves_the_rest_of_the_batch_alone() {
+        let dir =
+            std::env::temp_dir().join(format!("metadata_extractor_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let files = vec![
+            file(&dir.join("first.rs"), b"pub fn first() {}"),
+            file(&dir.join("broken.pdf"), b"%PDF-1.7\n\0\0 not really a pdf"),
+            file(&dir.join("broken.mp3"), b"ID3\x04 not really an mp3"),
+            file(&dir.join("last.py"), b"def last():\n    pass\n"),
+        ];
+
+        let extracted = MetadataExtractorPlugin::new()
+            .extract(files, &CrawlerSettings::default())
+            .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<&str> = extracted.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(names, ["first.rs", "broken.pdf", "broken.mp3", "last.py"]);
+        assert_eq!(extracted[0].symbols, Some(vec!["first".to_string()]));
+        assert!(extracted[1].document.is_none());
+        assert!(extracted[2].audio_tags.is_none());
+        assert_eq!(extracted[3].symbols, Some(vec!["last".to_string()]));
+    }
+
+    #[test]
+    fn a_panicking_parser_only_loses_its_own_file() {
+        let parsed: Option<()> = MetadataExtractorPlugin::parse("broken.pdf", "document", || {
+            panic!("malformed cross-reference table")
+        });
+        assert!(parsed.is_none());
+        assert_eq!(
+            MetadataExtractorPlugin::parse("good.rs", "symbols", || Ok(1)),
+            Some(1)
+        );
+    }
+}
@@ -1,7 +1,17 @@
+pub mod archive_indexer;
+pub mod change_detector;
 pub mod filterer;
 pub mod garbage_collector;
+pub mod ignore_files;
+pub mod index_roots;
+pub mod metadata_extractor;
+pub mod path_rules;
 pub mod throttle;
 
+pub use archive_indexer::ArchiveIndexerPlugin;
+pub use change_detector::ChangeDetectorPlugin;
 pub use filterer::FiltererPlugin;
 pub use throttle::*;
 pub use garbage_collector::GarbageCollectorPlugin;
+pub use index_roots::IndexRootsPlugin;
+pub use metadata_extractor::MetadataExtractorPlugin;
@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::tantivy_file_indexer::util::glob::glob_to_regex;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum PathRuleAction {
+    Include,
+    Exclude,
+}
+
+/// A glob pattern that includes or excludes paths, such as `**/node_modules`, `/home/*/.cache/**` or `*.tmp`.
+/// Patterns that start with `/` or a drive letter are matched against the full path, while any other pattern can match
+/// at any depth. A pattern that matches a directory also matches everything inside of it
+///
+/// This is to be stored in the KV table as a list, where later rules take precedence over earlier ones
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct PathRuleModel {
+    pub pattern: String,
+    pub action: PathRuleAction,
+}
+
+struct CompiledPathRule {
+    regex: Regex,
+    action: PathRuleAction,
+}
+
+/// The path rules compiled into regexes, so that they only have to be parsed when they change
+pub struct PathRuleMatcher {
+    rules: Vec<CompiledPathRule>,
+}
+
+impl PathRuleMatcher {
+    /// Rules with patterns that can't be compiled are left out
+    pub fn new(rules: &[PathRuleModel]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| match compile_pattern(&rule.pattern) {
+                Ok(regex) => Some(CompiledPathRule {
+                    regex,
+                    action: rule.action,
+                }),
+                Err(err) => {
+                    println!(
+                        "Crawler Filterer: Invalid path rule {}: {}",
+                        rule.pattern, err
+                    );
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Returns the action of the last rule that matches the path, or `None` if no rule matches it
+    pub fn check(&self, path: &Path) -> Option<PathRuleAction> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let path = normalize(&path.to_string_lossy());
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.regex.is_match(&path))
+            .map(|rule| rule.action)
+    }
+}
+
+fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    let pattern = normalize(pattern.trim());
+    let pattern = pattern.trim_end_matches('/');
+    let is_absolute = pattern.starts_with('/') || has_drive_letter(pattern);
+    let body = if is_absolute {
+        glob_to_regex(pattern)
+    } else {
+        format!("(?:.*/)?{}", glob_to_regex(pattern))
+    };
+    RegexBuilder::new(&format!("^(?:{})(?:/.*)?$", body))
+        .case_insensitive(cfg!(windows))
+        .build()
+}
+
+/// Windows paths are matched with forward slashes, so that the same patterns work everywhere. On other platforms, a
+/// backslash escapes the next character in a pattern instead
+fn normalize(s: &str) -> String {
+    if cfg!(windows) {
+        s.replace('\\', "/")
+    } else {
+        s.to_string()
+    }
+}
+
+fn has_drive_letter(pattern: &str) -> bool {
+    let bytes = pattern.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, action: PathRuleAction) -> PathRuleModel {
+        PathRuleModel {
+            pattern: pattern.to_string(),
+            action,
+        }
+    }
+
+    fn check(rules: &[PathRuleModel], path: &str) -> Option<PathRuleAction> {
+        PathRuleMatcher::new(rules).check(Path::new(path))
+    }
+
+    #[test]
+    fn names_match_whole_components() {
+        let rules = [rule("bin", PathRuleAction::Exclude)];
+        assert_eq!(check(&rules, "/src/bin"), Some(PathRuleAction::Exclude));
+        assert_eq!(
+            check(&rules, "/src/bin/main.rs"),
+            Some(PathRuleAction::Exclude)
+        );
+        assert_eq!(check(&rules, "/home/cabinet"), None);
+        assert_eq!(check(&rules, "/binary-assets"), None);
+    }
+
+    #[test]
+    fn absolute_and_relative_patterns() {
+        let rules = [
+            rule("**/node_modules", PathRuleAction::Exclude),
+            rule("/home/*/.cache/**", PathRuleAction::Exclude),
+            rule("*.tmp", PathRuleAction::Exclude),
+        ];
+        assert!(check(&rules, "/code/app/node_modules/react/index.js").is_some());
+        assert!(check(&rules, "/home/me/.cache/thumbnails/a.png").is_some());
+        assert!(check(&rules, "/srv/home/me/.cache/a.png").is_none());
+        assert!(check(&rules, "/home/me/notes.tmp").is_some());
+        assert!(check(&rules, "/home/me/notes.txt").is_none());
+    }
+
+    #[test]
+    fn later_rules_take_precedence() {
+        let rules = [
+            rule("/data/**", PathRuleAction::Exclude),
+            rule("/data/photos", PathRuleAction::Include),
+        ];
+        assert_eq!(
+            check(&rules, "/data/music/a.mp3"),
+            Some(PathRuleAction::Exclude)
+        );
+        assert_eq!(
+            check(&rules, "/data/photos/a.png"),
+            Some(PathRuleAction::Include)
+        );
+    }
+}
@@ -1,58 +1,277 @@
-use std::time::Duration;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
 
-#[derive(Clone)]
-pub enum ThrottleAmount {
-    None,
-    Low,
-    Medium,
-    High,
+use crate::tantivy_file_indexer::{
+    models::crawler_status_model::CrawlerThrottleModel,
+    services::local_crawler::core::settings::CrawlerSettings,
+};
+
+/// The lowest level that the throttle goes down to while running on battery
+const ON_BATTERY_LEVEL: f32 = 0.5;
+/// The lowest level that the throttle goes down to while the user is interacting with the app
+const USER_ACTIVE_LEVEL: f32 = 0.75;
+/// How many percentage points above the threshold the CPU usage or I/O wait has to be for the throttle to be all the
+/// way up
+const PRESSURE_SPAN: f32 = 30.0;
+/// How much of the way down to a lower level the throttle goes on each update. The throttle goes up right away, but
+/// comes back down slowly, so that a short break in the load doesn't bring every crawler back at once
+const EASE_DOWN: f32 = 0.3;
+
+/// What the throttle is based on
+#[derive(Clone, Default)]
+pub struct SystemLoad {
+    /// As a percentage of all of the cores
+    pub cpu_usage: f32,
+    /// As a percentage of CPU time. `None` if it isn't known on this platform
+    pub io_wait: Option<f32>,
+    /// `None` if it isn't known whether there is a battery
+    pub on_battery: Option<bool>,
+    /// Whether the user interacted with the app recently
+    pub user_active: bool,
 }
-#[derive(Clone)]
+
+impl SystemLoad {
+    async fn sample(user_active: bool) -> Self {
+        let (cpu_usage, io_wait) = tokio::join!(
+            system_info::cpu::get_global_cpu_usage(),
+            system_info::io::get_io_wait_percent()
+        );
+        Self {
+            cpu_usage,
+            io_wait,
+            on_battery: system_info::power::is_on_battery(),
+            user_active,
+        }
+    }
+}
+
+/// Slows the crawlers down while the computer is busy, on battery or being used, so that indexing stays out of the
+/// user's way. Shared by every crawler and the task manager.
+///
+/// The throttle has a level between 0, which is full speed, and 1, which is as slow as the crawlers go. The level
+/// decides both how many crawlers should be running and how long each crawler rests after each file
 pub struct ThrottlePlugin {
-    duration: Duration,
-    amount: ThrottleAmount,
+    /// How long each crawler rests after each file, in microseconds
+    rest_micros: AtomicU64,
+    last_ui_activity: Mutex<Option<Instant>>,
+    status: Mutex<CrawlerThrottleModel>,
 }
 
 impl ThrottlePlugin {
-    /// Initialize a new throttler with the throttle set to `None`
+    /// Initialize a new throttle that doesn't slow anything down until it is first updated
     pub fn new() -> Self {
-        let amount = ThrottleAmount::None;
-        let duration = Self::duration_for_throttle(&amount);
-        Self { duration, amount }
-    }
-    pub fn set<I>(&mut self, amount: I)
-    where
-        I: Into<ThrottleAmount>,
-    {
-        self.amount = amount.into();
-    }
-    pub fn upgrade(&mut self) {
-        match self.amount {
-            ThrottleAmount::None => self.amount = ThrottleAmount::Low,
-            ThrottleAmount::Low => self.amount = ThrottleAmount::Medium,
-            ThrottleAmount::Medium => self.amount = ThrottleAmount::High,
-            ThrottleAmount::High => {}
+        Self {
+            rest_micros: AtomicU64::new(0),
+            last_ui_activity: Mutex::new(None),
+            status: Mutex::new(CrawlerThrottleModel::default()),
         }
     }
-    /// If the throttle amount is set to `None`, then no rest happens
+
+    /// Marks the user as active, which keeps the throttle up until they have been idle for a while
+    pub fn record_ui_activity(&self) {
+        *self
+            .last_ui_activity
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Instant::now());
+    }
+
+    /// Samples the load on the system and moves the throttle towards the level that it calls for. Returns the number
+    /// of crawlers that should be running
+    pub async fn update(&self, settings: &CrawlerSettings) -> u32 {
+        let max_workers = settings.max_num_crawlers.max(1);
+        if settings.disable_adaptive_throttle {
+            self.rest_micros.store(0, Ordering::Relaxed);
+            *self.lock_status() = CrawlerThrottleModel {
+                recommended_workers: max_workers,
+                reason: "Disabled".to_string(),
+                ..Default::default()
+            };
+            return max_workers;
+        }
+
+        let user_active =
+            self.is_user_active(Duration::from_secs(settings.user_idle_secs().into()));
+        let load = SystemLoad::sample(user_active).await;
+        let (target, reason) = target_level(&load, settings);
+        let level = smooth(self.lock_status().level, target);
+
+        let rest = settings.max_file_rest().mul_f32(level);
+        let recommended_workers = recommended_workers(level, max_workers);
+        self.rest_micros
+            .store(rest.as_micros() as u64, Ordering::Relaxed);
+        *self.lock_status() = CrawlerThrottleModel {
+            level,
+            reason: reason.to_string(),
+            file_rest_millis: rest.as_millis() as u64,
+            recommended_workers,
+            cpu_usage: load.cpu_usage,
+            io_wait: load.io_wait,
+            on_battery: load.on_battery,
+            user_active: load.user_active,
+        };
+        recommended_workers
+    }
+
+    /// Rests for as long as the throttle says to after each file. If the throttle is all the way down, then no rest
+    /// happens
     pub async fn rest_short(&self) {
-        if let ThrottleAmount::None = self.amount {
+        let micros = self.rest_micros.load(Ordering::Relaxed);
+        if micros == 0 {
             return;
         }
-        tokio::time::sleep(self.duration).await
-    }
-    fn duration_for_throttle(amount: &ThrottleAmount) -> Duration {
-        match amount {
-            ThrottleAmount::None => Duration::from_millis(0),
-            ThrottleAmount::Low => Duration::from_millis(50),
-            ThrottleAmount::Medium => Duration::from_millis(100),
-            ThrottleAmount::High => Duration::from_millis(200),
-        }
+        tokio::time::sleep(Duration::from_micros(micros)).await
+    }
+
+    /// What the throttle is set to, and why
+    pub fn status(&self) -> CrawlerThrottleModel {
+        self.lock_status().clone()
+    }
+
+    fn is_user_active(&self, idle_after: Duration) -> bool {
+        self.last_ui_activity
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .is_some_and(|last| last.elapsed() < idle_after)
+    }
+
+    fn lock_status(&self) -> MutexGuard<'_, CrawlerThrottleModel> {
+        self.status
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for ThrottlePlugin {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl From<ThrottlePlugin> for ThrottleAmount {
-    fn from(value: ThrottlePlugin) -> Self {
-        value.amount
+/// The level that the load calls for, along with what called for it the most
+fn target_level(load: &SystemLoad, settings: &CrawlerSettings) -> (f32, &'static str) {
+    let pressure =
+        |value: f32, threshold: u32| ((value - threshold as f32) / PRESSURE_SPAN).clamp(0.0, 1.0);
+
+    let mut levels = vec![
+        (
+            pressure(load.cpu_usage, settings.throttle_cpu_threshold_percent()),
+            "CPU usage",
+        ),
+        (
+            load.io_wait.map_or(0.0, |io_wait| {
+                pressure(io_wait, settings.throttle_io_wait_threshold_percent())
+            }),
+            "Disk I/O",
+        ),
+    ];
+    if load.on_battery == Some(true) && !settings.full_speed_on_battery {
+        levels.push((ON_BATTERY_LEVEL, "On battery"));
+    }
+    if load.user_active {
+        levels.push((USER_ACTIVE_LEVEL, "User activity"));
+    }
+
+    levels
+        .into_iter()
+        .filter(|(level, _)| *level > 0.0)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .unwrap_or((0.0, "None"))
+}
+
+/// Goes up to a higher target right away, but only part of the way down to a lower one
+fn smooth(previous: f32, target: f32) -> f32 {
+    if target >= previous {
+        target
+    } else {
+        previous + (target - previous) * EASE_DOWN
+    }
+}
+
+/// All of the crawlers at level 0, down to one crawler at level 1
+fn recommended_workers(level: f32, max_workers: u32) -> u32 {
+    let max_workers = max_workers.max(1);
+    let extra = ((max_workers - 1) as f32 * (1.0 - level.clamp(0.0, 1.0))).round() as u32;
+    1 + extra
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> CrawlerSettings {
+        CrawlerSettings {
+            max_num_crawlers: 8,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn idle_system_runs_at_full_speed() {
+        let load = SystemLoad {
+            cpu_usage: 0.0,
+            io_wait: Some(0.0),
+            ..Default::default()
+        };
+        let (level, reason) = target_level(&load, &settings());
+        assert_eq!(level, 0.0);
+        assert_eq!(reason, "None");
+        assert_eq!(recommended_workers(level, 8), 8);
+    }
+
+    #[test]
+    fn busiest_resource_sets_the_level() {
+        let load = SystemLoad {
+            cpu_usage: 65.0,
+            io_wait: Some(40.0),
+            on_battery: Some(true),
+            user_active: false,
+        };
+        let (level, reason) = target_level(&load, &settings());
+        assert_eq!(level, 1.0);
+        assert_eq!(reason, "Disk I/O");
+        assert_eq!(recommended_workers(level, 8), 1);
+
+        let calm = SystemLoad {
+            on_battery: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            target_level(&calm, &settings()),
+            (ON_BATTERY_LEVEL, "On battery")
+        );
+        let plugged_in = CrawlerSettings {
+            full_speed_on_battery: true,
+            ..settings()
+        };
+        assert_eq!(target_level(&calm, &plugged_in).0, 0.0);
+    }
+
+    #[test]
+    fn throttle_rises_fast_and_falls_slowly() {
+        assert_eq!(smooth(0.0, 0.8), 0.8);
+        let eased = smooth(0.8, 0.0);
+        assert!(eased > 0.5 && eased < 0.8);
+
+        let mut level = 1.0;
+        let mut workers = Vec::new();
+        for _ in 0..10 {
+            level = smooth(level, 0.0);
+            workers.push(recommended_workers(level, 8));
+        }
+        // One step at a time, and all the way back up eventually
+        assert!(workers.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(*workers.last().unwrap(), 8);
+    }
+
+    #[test]
+    fn there_is_always_a_worker() {
+        assert_eq!(recommended_workers(1.0, 0), 1);
+        assert_eq!(recommended_workers(0.0, 0), 1);
+        assert_eq!(recommended_workers(2.0, 4), 1);
     }
 }
@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    fs::Metadata,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    shared::models::sys_file_model::SystemFileModel,
+    tantivy_file_indexer::{
+        services::local_db::tables::index_roots::entities::index_root,
+        util::file_id::{file_id, FileId},
+    },
+};
+
+/// What the crawlers and the index watcher do with symlinks. This is to be stored in the KV table
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum SymlinkPolicy {
+    /// Symlinks are left out of the index
+    Ignore,
+    /// The symlink itself gets indexed, but what it points to doesn't get crawled
+    IndexLinkOnly,
+    /// The symlink gets indexed with the metadata of what it points to, and directories get crawled unless that leads
+    /// to a cycle
+    #[default]
+    Follow,
+}
+
+impl SymlinkPolicy {
+    /// A root that doesn't follow symlinks still gets them indexed, unless they are ignored altogether
+    pub fn for_root(self, root: Option<&index_root::Model>) -> Self {
+        match self {
+            SymlinkPolicy::Follow if root.is_some_and(|root| !root.follow_symlinks) => {
+                SymlinkPolicy::IndexLinkOnly
+            }
+            policy => policy,
+        }
+    }
+}
+
+/// The directories that symlinks were followed into while crawling an index root, along with the symlink that each of
+/// them was reached through. Without it, two symlinks to the same directory would get it crawled twice
+#[derive(Default)]
+pub struct VisitedLinks {
+    targets: Mutex<HashMap<FileId, PathBuf>>,
+}
+
+impl VisitedLinks {
+    /// Returns the symlink that the directory was already reached through, if it was a different one. Following the
+    /// same symlink again is fine, since that is what happens when its parent gets crawled again
+    fn visit(&self, id: FileId, link: &Path) -> Option<PathBuf> {
+        let mut targets = self
+            .targets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let first_link = targets.entry(id).or_insert_with(|| link.to_path_buf());
+        (first_link != link).then(|| first_link.clone())
+    }
+}
+
+/// An entry of a directory, with symlinks resolved according to the symlink policy
+pub struct ResolvedEntry {
+    pub metadata: Metadata,
+    pub is_symlink: bool,
+    /// `None` for anything that isn't a symlink, or if the symlink couldn't be read
+    pub link_target: Option<PathBuf>,
+}
+
+impl ResolvedEntry {
+    /// Returns `None` if the entry should be left out, or if its metadata can't be read
+    pub fn read(path: &Path, policy: SymlinkPolicy) -> Option<Self> {
+        let link_metadata = path.symlink_metadata().ok()?;
+        if !link_metadata.is_symlink() {
+            return Some(Self {
+                metadata: link_metadata,
+                is_symlink: false,
+                link_target: None,
+            });
+        }
+        let metadata = match policy {
+            SymlinkPolicy::Ignore => return None,
+            SymlinkPolicy::IndexLinkOnly => link_metadata,
+            // A broken symlink still gets indexed, just as the link itself
+            SymlinkPolicy::Follow => path.metadata().unwrap_or(link_metadata),
+        };
+        Some(Self {
+            metadata,
+            is_symlink: true,
+            link_target: std::fs::read_link(path).ok(),
+        })
+    }
+
+    /// Returns `true` if the entry is a directory that can be crawled. A symlink to one of the directories above it
+    /// can't, since it would get crawled forever, and neither can a symlink to a directory that `visited` already
+    /// reached through another symlink
+    pub fn can_descend(&self, path: &Path, visited: Option<&VisitedLinks>) -> bool {
+        if !self.metadata.is_dir() {
+            return false;
+        }
+        if !self.is_symlink {
+            return true;
+        }
+        let Some(id) = file_id(path) else {
+            return false;
+        };
+        // The ancestors get resolved as well, so a cycle through multiple symlinks is found once it comes back around
+        let is_cycle = path
+            .ancestors()
+            .skip(1)
+            .any(|ancestor| file_id(ancestor) == Some(id));
+        if is_cycle {
+            println!(
+                "Crawler: Not following the symlink {}, since it leads to a directory above it",
+                path.display()
+            );
+            return false;
+        }
+        if let Some(first_link) = visited.and_then(|visited| visited.visit(id, path)) {
+            println!(
+                "Crawler: Not following the symlink {}, since the directory it leads to was already crawled through {}",
+                path.display(),
+                first_link.display()
+            );
+            return false;
+        }
+        true
+    }
+
+    pub fn into_model(self, path: PathBuf) -> Result<SystemFileModel, String> {
+        let mut model = SystemFileModel::try_new_from_meta(path, &self.metadata)?;
+        model.is_symlink = self.is_symlink;
+        model.link_target = self
+            .link_target
+            .map(|target| target.to_string_lossy().to_string());
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link_dir(target: &Path, link: &Path) -> std::io::Result<()> {
+        #[cfg(windows)]
+        return std::os::windows::fs::symlink_dir(target, link);
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(target, link);
+    }
+
+    #[test]
+    fn sibling_links_to_the_same_directory_are_followed_once() {
+        let dir = std::env::temp_dir().join(format!("symlinks_test_{}", uuid::Uuid::new_v4()));
+        let target = dir.join("target");
+        std::fs::create_dir_all(&target).unwrap();
+        let first = dir.join("first");
+        let second = dir.join("second");
+        // Creating symlinks on Windows needs developer mode or elevated privileges
+        if let Err(err) = link_dir(&target, &first).and_then(|_| link_dir(&target, &second)) {
+            println!("Skipping, since the symlinks could not be created: {}", err);
+            std::fs::remove_dir_all(&dir).unwrap();
+            return;
+        }
+
+        let visited = VisitedLinks::default();
+        let can_descend = |path: &Path| {
+            ResolvedEntry::read(path, SymlinkPolicy::Follow)
+                .unwrap()
+                .can_descend(path, Some(&visited))
+        };
+        let descended = [
+            can_descend(&target),
+            can_descend(&first),
+            can_descend(&second),
+            // The parent of the first link getting crawled again follows it again
+            can_descend(&first),
+        ];
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(descended, [true, true, false, true]);
+    }
+}
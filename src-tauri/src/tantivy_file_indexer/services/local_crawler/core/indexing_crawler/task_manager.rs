@@ -1,11 +1,21 @@
-use std::{cmp::Ordering, sync::Arc, time::Duration};
+use std::{
+    cmp::Ordering,
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc,
+    },
+    time::Duration,
+};
 
 use print_err::print_err;
-use tokio::sync::{mpsc, RwLock};
+use tokio::{
+    sync::{mpsc, watch, RwLock},
+    task::JoinHandle,
+};
 
 use crate::tantivy_file_indexer::services::{
     local_crawler::core::{
-        crawler_queue::queue::CrawlerQueue, indexing_crawler::plugins::throttle::ThrottleAmount,
+        crawler_queue::backend::CrawlerQueueBackend, indexing_crawler::plugins::ThrottlePlugin,
         settings::CrawlerSettings,
     },
     local_db::service::LocalDbService,
@@ -15,54 +25,139 @@ use crate::tantivy_file_indexer::services::{
 use super::{factory, worker_task_handle::CrawlerWorkerTaskHandle};
 
 /// The max number of crawlers that can be active at once
-type CrawlerFactory = factory::IndexingCrawlersFactory<CrawlerQueue, TantivyPipeline>;
+type CrawlerFactory = factory::IndexingCrawlersFactory<CrawlerQueueBackend, TantivyPipeline>;
 /// A message from the crawler task manager
 pub enum CrawlerMessage {
+    /// Commit what has been crawled so far and stop
     Kill,
-    Throttle,
+    /// Commit what has been crawled so far and wait for `Resume` or `Kill`
+    Pause,
+    Resume,
 }
 pub type CrawlerManagerMessageReceiver = mpsc::Receiver<CrawlerMessage>;
 pub type CrawlerManagerMessageSender = mpsc::Sender<CrawlerMessage>;
+/// The handles of the crawlers that are running, shared so that the status of each crawler can be read
+pub type CrawlerWorkerHandles = Arc<RwLock<Vec<CrawlerWorkerTaskHandle>>>;
+
+/// Controls the crawlers that the task manager is managing
+pub struct CrawlerTaskManagerHandle {
+    handles: CrawlerWorkerHandles,
+    /// The task manager doesn't add or remove crawlers while they are paused
+    paused: Arc<AtomicBool>,
+    stop_sender: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
 
+impl CrawlerTaskManagerHandle {
+    pub async fn pause(&self) {
+        let handles = self.handles.read().await;
+        self.paused.store(true, atomic::Ordering::Relaxed);
+        send_to_all(&handles, || CrawlerMessage::Pause).await;
+    }
+
+    pub async fn resume(&self) {
+        let handles = self.handles.read().await;
+        self.paused.store(false, atomic::Ordering::Relaxed);
+        send_to_all(&handles, || CrawlerMessage::Resume).await;
+    }
+
+    /// Stops the task manager, then kills every crawler and waits for them to commit what they have crawled
+    pub async fn stop(self) {
+        let _ = self.stop_sender.send(true);
+        if let Err(err) = self.task.await {
+            println!("Crawler Task Manager did not stop cleanly: {}", err);
+        }
+        let crawlers: Vec<CrawlerWorkerTaskHandle> = self.handles.write().await.drain(..).collect();
+        send_to_all(&crawlers, || CrawlerMessage::Kill).await;
+        for crawler in crawlers {
+            crawler.join().await;
+        }
+        println!("Crawler Task Manager: All crawlers have been stopped");
+    }
+}
+
+/// `settings` gets the crawler settings every time that the task manager reads them, which the crawlers get a receiver
+/// of
 pub async fn build_managed(
     mut factory: CrawlerFactory,
     local_db: Arc<LocalDbService>,
-) -> Result<(), String> {
-    let settings = CrawlerSettings::get_from_db(local_db.kv_store_table()).await?;
+    handles: CrawlerWorkerHandles,
+    throttle: Arc<ThrottlePlugin>,
+    settings: watch::Sender<Arc<CrawlerSettings>>,
+) -> Result<CrawlerTaskManagerHandle, String> {
+    let current_settings = CrawlerSettings::get_from_db(local_db.kv_store_table()).await?;
 
-    let num_workers = settings.max_num_crawlers;
-    factory = factory.set_batch_size(512);
-    let tasks = factory.build(num_workers).await;
+    // Start out with as many crawlers as the load allows, instead of starting them all and killing them off again
+    let num_workers = throttle.update(&current_settings).await;
+    settings.send_replace(Arc::new(current_settings));
+    factory = factory
+        .set_batch_size(512)
+        .set_settings(settings.subscribe());
+    handles
+        .write()
+        .await
+        .extend(factory.build(num_workers).await);
 
     println!(
         "Crawler task manager has spawned {} file crawlers",
         num_workers
     );
     let factory = Arc::new(RwLock::new(factory));
-    {
-        let mut factory_lock = factory.write().await;
-        factory_lock.set_throttle(ThrottleAmount::High);
-    }
 
-    manage_crawl_tasks(tasks, factory, Arc::clone(&local_db));
-    Ok(())
+    let paused = Arc::new(AtomicBool::new(false));
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let task = manage_crawl_tasks(
+        Arc::clone(&handles),
+        factory,
+        Arc::clone(&local_db),
+        throttle,
+        settings,
+        Arc::clone(&paused),
+        stop_receiver,
+    );
+    Ok(CrawlerTaskManagerHandle {
+        handles,
+        paused,
+        stop_sender,
+        task,
+    })
 }
 fn manage_crawl_tasks(
-    mut crawl_task_handles: Vec<CrawlerWorkerTaskHandle>,
+    handles: CrawlerWorkerHandles,
     factory: Arc<RwLock<CrawlerFactory>>,
     db: Arc<LocalDbService>,
-) {
-    let check_frequency = Duration::from_secs(30);
+    throttle: Arc<ThrottlePlugin>,
+    settings: watch::Sender<Arc<CrawlerSettings>>,
+    paused: Arc<AtomicBool>,
+    mut stop_receiver: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    // Often enough that the crawlers get out of the way quickly once the user or another program needs the computer
+    let check_frequency = Duration::from_secs(5);
     tokio::spawn(async move {
         loop {
-            tokio::time::sleep(check_frequency).await;
-            crawl_task_handles = remove_dead_crawlers(crawl_task_handles);
+            tokio::select! {
+                _ = tokio::time::sleep(check_frequency) => {}
+                _ = stop_receiver.changed() => break,
+            }
+            let Some(recommended_crawlers) =
+                compute_recommended_num_crawlers(&db, &throttle, &settings).await
+            else {
+                continue;
+            };
+
+            let mut crawl_task_handles = handles.write().await;
+            // Checked while holding the lock, so that crawlers can't be added after the others have been paused
+            if paused.load(atomic::Ordering::Relaxed) {
+                continue;
+            }
+            remove_dead_crawlers(&mut crawl_task_handles);
             let num_active_crawlers = crawl_task_handles.len() as u32;
-            let recommended_crawlers = compute_recommended_num_crawlers(&db).await;
-            println!(
-                "Crawler Task Manager: There are {} active crawlers and {} are recommended",
-                num_active_crawlers, recommended_crawlers
-            );
+            if num_active_crawlers != recommended_crawlers {
+                println!(
+                    "Crawler Task Manager: There are {} active crawlers and {} are recommended",
+                    num_active_crawlers, recommended_crawlers
+                );
+            }
             match num_active_crawlers.cmp(&recommended_crawlers) {
                 Ordering::Less => {
                     // Add more crawlers
@@ -80,7 +175,17 @@ fn manage_crawl_tasks(
                 }
             }
         }
-    });
+        println!("Crawler Task Manager has stopped");
+    })
+}
+
+async fn send_to_all<F>(crawlers: &[CrawlerWorkerTaskHandle], message: F)
+where
+    F: Fn() -> CrawlerMessage,
+{
+    for crawler in crawlers {
+        print_err("CrawlerTaskManager", crawler.sender.send(message()).await);
+    }
 }
 
 /// Sends a message to some of the crawlers to gracefully terminate them
@@ -97,29 +202,30 @@ async fn kill_crawlers(crawlers: &[CrawlerWorkerTaskHandle], amt: u32) {
     }
 }
 
-/// Returns all of the crawlers that are still running
-fn remove_dead_crawlers(crawlers: Vec<CrawlerWorkerTaskHandle>) -> Vec<CrawlerWorkerTaskHandle> {
-    let mut alive_crawlers = Vec::new();
-    for crawler in crawlers.into_iter() {
-        if !crawler.is_finished() {
-            alive_crawlers.push(crawler);
-        }
-    }
-    alive_crawlers
+/// Keeps only the crawlers that are still running
+fn remove_dead_crawlers(crawlers: &mut Vec<CrawlerWorkerTaskHandle>) {
+    crawlers.retain(|crawler| !crawler.is_finished());
 }
 
-/// Determine the recommended number of crawlers that should be active based on current CPU usage
-async fn compute_recommended_num_crawlers(db: &Arc<LocalDbService>) -> u32 {
-    let settings = CrawlerSettings::get_from_db(db.kv_store_table())
-        .await
-        .expect("Crawler settings should be present in database");
-
-    let cpu_usage = system_info::cpu::get_global_cpu_usage().await;
-    let num = ((settings.max_num_crawlers * 8) as f32) / cpu_usage;
-    let num = (num as u32).clamp(1, settings.max_num_crawlers);
-    println!(
-        "Crawler Task Manager: {} crawlers are recommended due to {}% CPU usage",
-        num, cpu_usage
-    );
-    num
+/// Updates the throttle and returns the number of crawlers that it recommends, sharing the crawler settings that it read
+/// along the way. Returns `None` if the crawler settings can't be read, in which case the crawlers are left as they are
+async fn compute_recommended_num_crawlers(
+    db: &Arc<LocalDbService>,
+    throttle: &ThrottlePlugin,
+    shared_settings: &watch::Sender<Arc<CrawlerSettings>>,
+) -> Option<u32> {
+    match CrawlerSettings::get_from_db(db.kv_store_table()).await {
+        Ok(settings) => {
+            let recommended = throttle.update(&settings).await;
+            shared_settings.send_replace(Arc::new(settings));
+            Some(recommended)
+        }
+        Err(err) => {
+            println!(
+                "Crawler Task Manager: Could not read the crawler settings: {}",
+                err
+            );
+            None
+        }
+    }
 }
@@ -0,0 +1,219 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::Utc;
+use tauri::{AppHandle, Emitter};
+use tokio::{sync::RwLock, task::JoinHandle};
+
+use crate::tantivy_file_indexer::{
+    services::{
+        local_crawler::watcher::service::IndexWatcherService,
+        local_db::{service::LocalDbService, tables::volumes::entities::volume},
+        search_index::pipelines::tantivy_pipeline::TantivyPipeline,
+        volumes::service::VolumeService,
+    },
+    shared::indexing_crawler::{
+        models::crawler_file::CrawlerFile, traits::crawler_queue_api::CrawlerQueueApi,
+    },
+};
+
+use super::super::crawler_queue::queue::Priority;
+use super::plugins::IndexRootsPlugin;
+
+/// How often the drives are checked for whether any got plugged in, unplugged or mounted somewhere else
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// After every index root, like the directories that the index validator finds to have changed
+const REINDEX_PRIORITY: Priority = 9;
+
+/// The index watcher is only there while the crawlers are dispatched
+pub type SharedIndexWatcher = Arc<RwLock<Option<Arc<IndexWatcherService>>>>;
+
+/// Keeps the index in line with the drives that are connected:
+/// - A drive that got mounted somewhere else has its indexed files, directory states and index roots moved along with
+///   it, so that it doesn't have to be crawled again
+/// - A drive that came back has its index roots crawled, to pick up whatever changed while it was gone, and watched
+///   again
+///
+/// The `volumes_changed` event is emitted with every volume whenever something changed.
+///
+/// Runs until the returned task is aborted
+pub fn spawn<C>(
+    volumes: Arc<VolumeService>,
+    pipeline: Arc<TantivyPipeline>,
+    local_db: Arc<LocalDbService>,
+    queue: Arc<C>,
+    index_roots: Arc<IndexRootsPlugin>,
+    index_watcher: SharedIndexWatcher,
+    app_handle: AppHandle,
+) -> JoinHandle<()>
+where
+    C: CrawlerQueueApi,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            volumes.refresh().await;
+            let changes = volumes.take_changes();
+            if changes.is_empty() {
+                continue;
+            }
+
+            for (old_mount_path, volume) in changes.moved.iter() {
+                if let Err(err) = move_volume(
+                    queue.as_ref(),
+                    &volumes,
+                    &pipeline,
+                    &local_db,
+                    old_mount_path,
+                    volume,
+                )
+                .await
+                {
+                    println!(
+                        "Volume monitor: Could not move {} from {} to {}: {}",
+                        volume.id, old_mount_path, volume.mount_path, err
+                    );
+                }
+            }
+            if !changes.moved.is_empty() || !changes.came_online.is_empty() {
+                index_roots.invalidate().await;
+                if let Err(err) =
+                    push_roots_on(queue.as_ref(), &index_roots, &changes.came_online).await
+                {
+                    println!("Volume monitor: Error pushing the index roots: {}", err);
+                }
+                rewatch(&index_roots, &index_watcher).await;
+            }
+
+            if let Err(err) = app_handle.emit("volumes_changed", volumes.get_all()) {
+                println!("Volume monitor: Could not emit the volumes: {}", err);
+            }
+        }
+    })
+}
+
+async fn move_volume<C>(
+    queue: &C,
+    volumes: &VolumeService,
+    pipeline: &TantivyPipeline,
+    local_db: &LocalDbService,
+    old_mount_path: &str,
+    volume: &volume::Model,
+) -> Result<(), String>
+where
+    C: CrawlerQueueApi,
+{
+    let moved = pipeline
+        .move_volume(&volume.id, old_mount_path, &volume.mount_path)
+        .await?;
+    println!(
+        "Volume monitor: {} moved from {} to {}, along with {} indexed files",
+        volume.id, old_mount_path, volume.mount_path, moved.num_moved
+    );
+
+    // The directory states and index roots are only known by their paths, so they are left alone if another drive got
+    // mounted where this one used to be
+    let replaced = volumes.get_all().iter().any(|other| {
+        other.id != volume.id && other.is_online && other.mount_path == old_mount_path
+    });
+    if replaced {
+        return Ok(());
+    }
+    local_db
+        .directory_states_table()
+        .move_tree(old_mount_path, &volume.mount_path)
+        .await
+        .map_err(|err| err.to_string())?;
+    local_db
+        .index_roots_table()
+        .move_tree(old_mount_path, &volume.mount_path)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    // The bodies of the documents didn't move with them, so their directories get indexed again
+    local_db
+        .directory_states_table()
+        .delete_many(&moved.document_dirs)
+        .await
+        .map_err(|err| err.to_string())?;
+    let dirs: Vec<CrawlerFile> = moved
+        .document_dirs
+        .into_iter()
+        .map(|dir| CrawlerFile {
+            path: dir.into(),
+            priority: REINDEX_PRIORITY,
+            taken: false,
+            added_at: Utc::now(),
+        })
+        .collect();
+    if dirs.is_empty() {
+        return Ok(());
+    }
+    queue.push(&dirs).await.map_err(|err| err.to_string())
+}
+
+/// Pushes the parts of the index roots that are on the volumes, which is either the whole root or the volume's mount
+/// path if the volume is mounted inside of the root
+async fn push_roots_on<C>(
+    queue: &C,
+    index_roots: &IndexRootsPlugin,
+    volumes: &[volume::Model],
+) -> Result<(), String>
+where
+    C: CrawlerQueueApi,
+{
+    let roots = index_roots
+        .get_enabled()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let mut files = Vec::new();
+    for root in roots.iter() {
+        let root_path = Path::new(&root.path);
+        for volume in volumes {
+            let path = if volume.contains(root_path) {
+                &root.path
+            } else if Path::new(&volume.mount_path).starts_with(root_path) {
+                &volume.mount_path
+            } else {
+                continue;
+            };
+            files.push(CrawlerFile {
+                path: path.clone().into(),
+                priority: root.priority,
+                taken: false,
+                added_at: Utc::now(),
+            });
+        }
+    }
+    if files.is_empty() {
+        return Ok(());
+    }
+    queue.push(&files).await.map_err(|err| err.to_string())
+}
+
+/// The roots that were on a drive that went away stopped being watched, and the roots that moved have to be watched
+/// where they are now
+async fn rewatch(index_roots: &IndexRootsPlugin, index_watcher: &SharedIndexWatcher) {
+    let Some(index_watcher) = index_watcher.read().await.clone() else {
+        return;
+    };
+    let roots: Vec<PathBuf> = match index_roots.get_enabled().await {
+        Ok(roots) => roots.iter().map(|root| PathBuf::from(&root.path)).collect(),
+        Err(err) => {
+            println!("Volume monitor: Could not read the index roots: {}", err);
+            return;
+        }
+    };
+    if let Err(err) = index_watcher.watch(roots).await {
+        println!(
+            "Volume monitor: Could not restart the index watcher: {}",
+            err
+        );
+    }
+}
@@ -1,14 +1,16 @@
 use rand::{Rng, SeedableRng};
-use std::{sync::Arc, time::Duration};
-use tokio::sync::mpsc::error::TryRecvError;
+use std::{pin::Pin, sync::Arc, time::Duration};
+use tokio::sync::{futures::Notified, mpsc::error::TryRecvError};
 
 use crate::{
     shared::models::sys_file_model::SystemFileModel,
     tantivy_file_indexer::{
-        services::local_crawler::core::indexing_crawler::{
-            idle,
-            plugins::{FiltererPlugin, GarbageCollectorPlugin, ThrottleAmount, ThrottlePlugin},
+        services::local_crawler::analyzer::service::FileCrawlerAnalyzerService,
+        services::local_crawler::core::indexing_crawler::plugins::{
+            ArchiveIndexerPlugin, ChangeDetectorPlugin, FiltererPlugin, GarbageCollectorPlugin,
+            IndexRootsPlugin, MetadataExtractorPlugin, ThrottlePlugin,
         },
+        services::volumes::service::VolumeService,
         shared::{
             async_retry,
             indexing_crawler::{
@@ -21,9 +23,11 @@ use crate::{
     },
 };
 
+use super::super::settings::{CrawlerSettings, SharedCrawlerSettings};
 use super::{
-    crawler::{self, CrawlerError},
+    crawler::{self, CrawledDirectory, CrawlerError},
     task_manager::{CrawlerManagerMessageReceiver, CrawlerMessage},
+    worker_status::CrawlerWorkerStatus,
 };
 
 pub struct IndexingCrawlerWorker<C, P>
@@ -37,10 +41,19 @@ where
     /// Message channel to receive orders from the task manager
     receiver: CrawlerManagerMessageReceiver,
     channel_closed: bool,
+    /// Shared with the task manager, which reports it in the crawler status
+    status: Arc<CrawlerWorkerStatus>,
 
     garbage_collector: Option<Arc<GarbageCollectorPlugin>>,
     filterer: Option<Arc<FiltererPlugin>>,
-    throttle: ThrottlePlugin,
+    metadata_extractor: Option<Arc<MetadataExtractorPlugin>>,
+    archive_indexer: Option<Arc<ArchiveIndexerPlugin>>,
+    index_roots: Option<Arc<IndexRootsPlugin>>,
+    analyzer: Option<Arc<FileCrawlerAnalyzerService>>,
+    change_detector: Option<Arc<ChangeDetectorPlugin>>,
+    throttle: Option<Arc<ThrottlePlugin>>,
+    volumes: Option<Arc<VolumeService>>,
+    settings: Option<SharedCrawlerSettings>,
 }
 
 impl<C, P> IndexingCrawlerWorker<C, P>
@@ -56,6 +69,7 @@ where
         pipeline: Arc<P>,
         batch_size: usize,
         receiver: CrawlerManagerMessageReceiver,
+        status: Arc<CrawlerWorkerStatus>,
     ) -> Self {
         Self {
             crawler_queue,
@@ -63,10 +77,18 @@ where
             batch_size,
             receiver,
             channel_closed: false,
+            status,
 
             garbage_collector: None,
             filterer: None,
-            throttle: ThrottlePlugin::new(),
+            metadata_extractor: None,
+            archive_indexer: None,
+            index_roots: None,
+            analyzer: None,
+            change_detector: None,
+            throttle: None,
+            volumes: None,
+            settings: None,
         }
     }
 
@@ -78,23 +100,91 @@ where
         self.filterer = Some(f);
     }
 
-    pub fn set_throttle<T>(&mut self, t: T)
-    where
-        T: Into<ThrottleAmount>,
-    {
-        self.throttle.set(t);
+    pub fn inject_metadata_extractor(&mut self, e: Arc<MetadataExtractorPlugin>) {
+        self.metadata_extractor = Some(e);
+    }
+
+    pub fn inject_archive_indexer(&mut self, a: Arc<ArchiveIndexerPlugin>) {
+        self.archive_indexer = Some(a);
+    }
+
+    pub fn inject_index_roots(&mut self, r: Arc<IndexRootsPlugin>) {
+        self.index_roots = Some(r);
+    }
+
+    pub fn inject_analyzer(&mut self, a: Arc<FileCrawlerAnalyzerService>) {
+        self.analyzer = Some(a);
+    }
+
+    pub fn inject_change_detector(&mut self, d: Arc<ChangeDetectorPlugin>) {
+        self.change_detector = Some(d);
+    }
+
+    pub fn inject_throttle(&mut self, t: Arc<ThrottlePlugin>) {
+        self.throttle = Some(t);
+    }
+
+    pub fn inject_volumes(&mut self, v: Arc<VolumeService>) {
+        self.volumes = Some(v);
+    }
+
+    pub fn inject_settings(&mut self, s: SharedCrawlerSettings) {
+        self.settings = Some(s);
     }
 
     pub async fn worker_task(&mut self) {
-        let mut files_bank: Vec<(CrawlerFile, Vec<SystemFileModel>)> = Vec::new();
+        let mut files_bank: Vec<(CrawlerFile, CrawledDirectory)> = Vec::new();
         let mut num_files_processed = 0;
+        let notifier = self.crawler_queue.get_notifier();
 
         self.random_wait().await;
         loop {
+            // Handle messages from the task manager first, so that directories that get skipped don't hold them up
+            let mut kill = false;
+            // Handle all incoming messages
+            while let Some(message) = self.get_next_receiver_msg() {
+                match message {
+                    CrawlerMessage::Kill => {
+                        kill = true;
+                        break;
+                    }
+                    CrawlerMessage::Pause => {
+                        // Nothing that was crawled should be left uncommitted while paused
+                        num_files_processed = 0;
+                        files_bank = self.commit_files_bank(files_bank).await;
+                        if !self.wait_until_resumed().await {
+                            kill = true;
+                            break;
+                        }
+                    }
+                    CrawlerMessage::Resume => { /* Not paused, so there is nothing to resume */ }
+                }
+            }
+            // Break the outer loop
+            if kill {
+                self.commit_files_bank(files_bank).await;
+                break;
+            }
+
+            // Armed before fetching, so that something that gets pushed right after the queue is found to be empty
+            // still wakes the worker up
+            let notified = notifier.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
             // Since not every directory will have a lot of files, save up a bunch of files and then commit all of them
             match self.staggered_fetch_next().await {
                 Ok(file_option) => match file_option {
                     Some(file) => {
+                        if let Some(volumes) = &self.volumes {
+                            if volumes.is_offline(&file.path) {
+                                // The drive isn't connected, so there is nothing to crawl. Its roots get pushed
+                                // again once it comes back
+                                self.remove_from_crawler_queue(&file).await;
+                                continue;
+                            }
+                        }
+
                         if let Some(filterer) = &self.filterer {
                             if !filterer.should_crawl_directory(&file.path).await {
                                 // ! Removed the log as it can be annoying sometimes
@@ -107,9 +197,15 @@ where
                             }
                         }
 
-                        let inner_files = self.handle_crawl(&file).await;
-                        let len = inner_files.len();
+                        self.status.set_current_directory(Some(&file.path));
+                        let crawled = self.handle_crawl(&file).await;
+                        self.status.set_current_directory(None);
+                        let len = crawled.files.len();
                         num_files_processed += len;
+                        if let Some(analyzer) = &self.analyzer {
+                            analyzer.add_to_files_processed(len);
+                            analyzer.add_to_directories_processed(1);
+                        }
                         //println!("Crawler processed {} files", len);
                         // Register this number of files to the garbage collector, if there is one
                         if let Some(collector) = &self.garbage_collector {
@@ -118,7 +214,12 @@ where
                             }
                         }
 
-                        files_bank.push((file, inner_files));
+                        if crawled.is_unchanged() {
+                            // Nothing to index, but the directory is done with
+                            self.remove_from_crawler_queue(&file).await;
+                            continue;
+                        }
+                        files_bank.push((file, crawled));
 
                         if num_files_processed >= self.batch_size {
                             // Commit all and drain the bank of files
@@ -132,11 +233,10 @@ where
                         files_bank = self.commit_files_bank(files_bank).await;
                     }
                     None => {
-                        // stagger the update since multiple crawlers may finish at the same time
-                        self.random_wait().await;
-                        let queue_clone = Arc::clone(&self.crawler_queue);
-                        if let Err(err) = idle::create_busy_work(queue_clone).await {
-                            println!("Crawler Worker - Error creating busy work: {}", err);
+                        // Nothing to do until something gets pushed to the queue. The index roots get pushed again by
+                        // the full refresh job
+                        if !self.wait_for_work(notified).await {
+                            break;
                         }
                     }
                 },
@@ -145,28 +245,60 @@ where
                     "File crawler task encountered an error trying to fetch item from queue: {}. Retrying.",
                     err
                 );
+                    self.record_error(format!("Could not fetch from the crawler queue: {}", err));
                     self.random_wait().await;
                 }
             }
-            // Handle messages from the task manager:
-            let mut kill = false;
-            // Handle all incoming messages
-            while let Some(message) = self.get_next_receiver_msg() {
-                match message {
-                    CrawlerMessage::Kill => {
-                        kill = true;
-                        break;
+        }
+        println!("Crawler worker has been killed due to task manager saying so");
+    }
+
+    /// Parks the worker until something gets pushed to the crawler queue, while still handling the messages from the
+    /// task manager. Returns `false` if the worker should be killed
+    async fn wait_for_work(&mut self, mut notified: Pin<&mut Notified<'_>>) -> bool {
+        loop {
+            if self.channel_closed {
+                notified.as_mut().await;
+                return true;
+            }
+            tokio::select! {
+                _ = notified.as_mut() => return true,
+                message = self.receiver.recv() => match message {
+                    Some(CrawlerMessage::Kill) => return false,
+                    // Whatever was pushed while paused gets fetched once resumed
+                    Some(CrawlerMessage::Pause) => return self.wait_until_resumed().await,
+                    Some(CrawlerMessage::Resume) => { /* Not paused, so there is nothing to resume */ }
+                    None => {
+                        println!(
+                            "WARNING: Crawler task manager communication channel closed while the crawler was waiting for work"
+                        );
+                        self.channel_closed = true;
                     }
-                    CrawlerMessage::Throttle => self.throttle.upgrade(),
                 }
             }
-            // Break the outer loop
-            if kill {
-                self.commit_files_bank(files_bank).await;
-                break;
-            }
         }
-        println!("Crawler worker has been killed due to task manager saying so");
+    }
+
+    /// Returns `false` if the worker should be killed instead of resuming
+    async fn wait_until_resumed(&mut self) -> bool {
+        self.status.set_paused(true);
+        let resume = loop {
+            match self.receiver.recv().await {
+                Some(CrawlerMessage::Resume) => break true,
+                Some(CrawlerMessage::Kill) => break false,
+                Some(CrawlerMessage::Pause) => { /* Already paused */ }
+                None => {
+                    // Nothing can resume the worker anymore
+                    println!(
+                        "WARNING: Crawler task manager communication channel closed while the crawler was paused"
+                    );
+                    self.channel_closed = true;
+                    break false;
+                }
+            }
+        };
+        self.status.set_paused(false);
+        resume
     }
 
     fn get_next_receiver_msg(&mut self) -> Option<CrawlerMessage> {
@@ -189,46 +321,95 @@ where
         None
     }
 
-    /// Indexes the items, but applies a throttle while creating the batches
-    async fn handle_index_batched(&self, dir: &CrawlerFile, files: Vec<SystemFileModel>) {
-        let batch_size = 128;
-        let mut batch = Vec::new();
-        for (i, file) in files.into_iter().enumerate() {
-            batch.push(file);
-            self.throttle.rest_short().await;
-            if i % batch_size == 0 {
-                // Commit the batch
-                self.handle_index(dir, std::mem::take(&mut batch)).await;
+    /// Indexes the files, resting for as long as the throttle says to for each of them first. The whole directory goes
+    /// to `upsert_many` at once, since whatever was indexed in the directory before but isn't passed along gets removed
+    /// as stale. Returns `true` if the files got indexed
+    async fn handle_index_throttled(
+        &self,
+        dir: &CrawlerFile,
+        files: Vec<SystemFileModel>,
+        settings: &CrawlerSettings,
+    ) -> bool {
+        if let Some(throttle) = &self.throttle {
+            for _ in 0..files.len() {
+                throttle.rest_short().await;
             }
         }
-        if !batch.is_empty() {
-            self.handle_index(dir, batch).await;
-        }
+        self.handle_index(dir, files, settings).await
     }
 
-    async fn handle_index(&self, dir: &CrawlerFile, files: Vec<SystemFileModel>) {
+    /// Returns `true` if the files got indexed
+    async fn handle_index(
+        &self,
+        dir: &CrawlerFile,
+        files: Vec<SystemFileModel>,
+        settings: &CrawlerSettings,
+    ) -> bool {
         let parent_path = dir.path.to_string_lossy().to_string();
 
+        let files = match &self.metadata_extractor {
+            Some(extractor) => extractor.extract(files, settings).await,
+            None => files,
+        };
+
+        let archives = match &self.archive_indexer {
+            Some(indexer) => indexer.archives_in(&files, settings),
+            None => Vec::new(),
+        };
+
         match self.pipeline.upsert_many(files, parent_path).await {
             Ok(_) => {
+                // Only once the archives themselves are in the index, so that their members never end up without them
+                if let Some(indexer) = &self.archive_indexer {
+                    indexer
+                        .index_members(self.pipeline.as_ref(), archives)
+                        .await;
+                }
                 // If all goes well, then the directory can be removed from the crawler queue
                 self.remove_from_crawler_queue(dir).await;
+                true
             }
             Err(err) => {
                 println!("Error indexing files: {}", err);
+                self.record_error(format!(
+                    "Could not index the files in {}: {}",
+                    dir.path.to_string_lossy(),
+                    err
+                ));
+                false
             }
         }
     }
 
     /// Returns all of the files that were found in the given directory
-    async fn handle_crawl(&self, directory: &CrawlerFile) -> Vec<SystemFileModel>
+    async fn handle_crawl(&self, directory: &CrawlerFile) -> CrawledDirectory
     where
         C: CrawlerQueueApi,
     {
         let filterer_clone = self.filterer.clone();
-        match crawler::crawl(directory, Arc::clone(&self.crawler_queue), filterer_clone).await {
-            Ok(dtos) => {
-                return dtos;
+        let root = match &self.index_roots {
+            Some(index_roots) => index_roots.root_for(&directory.path).await,
+            None => None,
+        };
+        let visited_links = match (&self.index_roots, &root) {
+            (Some(index_roots), Some(root)) => {
+                Some(index_roots.visited_links(root, &directory.path))
+            }
+            _ => None,
+        };
+        match crawler::crawl(
+            directory,
+            Arc::clone(&self.crawler_queue),
+            filterer_clone,
+            root.as_ref(),
+            visited_links.as_deref(),
+            self.change_detector.as_deref(),
+            &self.current_settings(),
+        )
+        .await
+        {
+            Ok(crawled) => {
+                return crawled;
             }
             Err(err) => match err {
                 CrawlerError::PushToQueue(err) => {
@@ -237,35 +418,70 @@ where
             The original directory will stay in the queue for re-indexing",
                         err
                     );
+                    self.record_error(format!(
+                        "Could not push the directories in {} to the crawler queue: {}",
+                        directory.path.to_string_lossy(),
+                        err
+                    ));
                 }
                 CrawlerError::ReadDir(err) | CrawlerError::NotDir(err) => {
                     println!(
                         "Crawler could not read directory: {}. Removing it from the queue",
                         err
                     );
+                    self.record_error(format!(
+                        "Could not read {}: {}",
+                        directory.path.to_string_lossy(),
+                        err
+                    ));
                     // Something must be wrong with the directory, so go ahead and remove it from the queue early
                     self.remove_from_crawler_queue(directory).await
                 }
             },
         }
-        Vec::new()
+        CrawledDirectory::default()
     }
 
     async fn commit_files_bank(
         &self,
-        mut files: Vec<(CrawlerFile, Vec<SystemFileModel>)>,
-    ) -> Vec<(CrawlerFile, Vec<SystemFileModel>)> {
+        mut files: Vec<(CrawlerFile, CrawledDirectory)>,
+    ) -> Vec<(CrawlerFile, CrawledDirectory)> {
         //println!("Crawler is committing files bank");
         if files.is_empty() {
             return files;
         }
-        for (dir, inner_files) in files.drain(..) {
+        let settings = self.current_settings();
+        let mut indexed_states = Vec::new();
+        for (dir, crawled) in files.drain(..) {
             //println!("Draining {}", dir.path.to_string_lossy());
-            self.handle_index_batched(&dir, inner_files).await;
+            let indexed = self
+                .handle_index_throttled(&dir, crawled.files, &settings)
+                .await;
+            if let (true, Some(change)) = (indexed, crawled.change) {
+                indexed_states.push(change.state);
+            }
+        }
+        // Only directories that were fully indexed can be skipped next time
+        if let Some(detector) = &self.change_detector {
+            detector.record(&indexed_states).await;
         }
         files
     }
 
+    /// The defaults are used if no settings were injected
+    fn current_settings(&self) -> Arc<CrawlerSettings> {
+        match &self.settings {
+            Some(settings) => Arc::clone(&settings.borrow()),
+            None => Arc::new(CrawlerSettings::default()),
+        }
+    }
+
+    fn record_error(&self, message: String) {
+        if let Some(analyzer) = &self.analyzer {
+            analyzer.record_error(message);
+        }
+    }
+
     async fn remove_from_crawler_queue(&self, directory: &CrawlerFile) {
         if let Err(err) = async_retry::retry_with_backoff(
             |_| self.crawler_queue.delete_one(directory.clone()),
@@ -298,3 +514,140 @@ where
         tokio::time::sleep(Duration::from_millis(rng.gen_range(100..=2000))).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+
+    use chrono::Utc;
+    use tokio::sync::{mpsc, Notify};
+
+    use super::*;
+    use crate::tantivy_file_indexer::services::search_index::models::file::TantivyFileModel;
+
+    #[derive(Clone, Default)]
+    struct FakeQueue {
+        notify: Arc<Notify>,
+    }
+
+    impl CrawlerQueueApi for FakeQueue {
+        type Error = String;
+
+        async fn push(&self, _files: &[CrawlerFile]) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn fetch_next(&self) -> Result<Option<CrawlerFile>, Self::Error> {
+            Ok(None)
+        }
+
+        async fn fetch(&self, _amount: u64) -> Result<Vec<CrawlerFile>, Self::Error> {
+            Ok(Vec::new())
+        }
+
+        async fn delete_one(&self, _file: CrawlerFile) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn set_taken_to_false_all(&self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn get_notifier(&self) -> Arc<Notify> {
+            Arc::clone(&self.notify)
+        }
+    }
+
+    /// Removes whatever was indexed in the directory but isn't being upserted, like the real pipeline does
+    #[derive(Default)]
+    struct FakePipeline {
+        children: Mutex<HashMap<String, Vec<SystemFileModel>>>,
+    }
+
+    impl CrawlerCommitPipeline for FakePipeline {
+        type IndexedModel = TantivyFileModel;
+        type InputModel = SystemFileModel;
+        type Error = String;
+
+        async fn get_children(&self, parent_key: String) -> Result<Vec<SystemFileModel>, String> {
+            Ok(self
+                .children
+                .lock()
+                .unwrap()
+                .get(&parent_key)
+                .cloned()
+                .unwrap_or_default())
+        }
+
+        async fn upsert_many(
+            &self,
+            models: Vec<SystemFileModel>,
+            parent_key: String,
+        ) -> Result<(), String> {
+            self.children.lock().unwrap().insert(parent_key, models);
+            Ok(())
+        }
+
+        async fn upsert_one(&self, _model: SystemFileModel) -> Result<(), String> {
+            Ok(())
+        }
+
+        async fn get_one(&self, _model: SystemFileModel) -> Option<TantivyFileModel> {
+            None
+        }
+
+        async fn remove_many(&self, _keys: Vec<String>) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    fn file(dir: &str, i: usize) -> SystemFileModel {
+        SystemFileModel {
+            name: format!("{}.txt", i),
+            file_path: format!("{}/{}.txt", dir, i),
+            date_modified: Utc::now(),
+            date_created: Utc::now(),
+            size: 0,
+            score: 0.0,
+            is_directory: false,
+            audio_tags: None,
+            document: None,
+            symbols: None,
+            container_path: None,
+            is_symlink: false,
+            link_target: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn large_directories_are_indexed_whole() {
+        let pipeline = Arc::new(FakePipeline::default());
+        let (_sender, receiver) = mpsc::channel(1);
+        let worker = IndexingCrawlerWorker::new(
+            Arc::new(FakeQueue::default()),
+            Arc::clone(&pipeline),
+            512,
+            receiver,
+            Arc::new(CrawlerWorkerStatus::new(0)),
+        );
+        let dir = CrawlerFile {
+            path: PathBuf::from("/big"),
+            priority: 0,
+            taken: true,
+            added_at: Utc::now(),
+        };
+
+        let files: Vec<SystemFileModel> = (0..300).map(|i| file("/big", i)).collect();
+        assert!(
+            worker
+                .handle_index_throttled(&dir, files, &CrawlerSettings::default())
+                .await
+        );
+        let children = pipeline.get_children("/big".to_string()).await.unwrap();
+        assert_eq!(children.len(), 300);
+    }
+}
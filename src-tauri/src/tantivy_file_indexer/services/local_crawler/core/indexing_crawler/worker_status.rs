@@ -0,0 +1,56 @@
+use std::{
+    path::Path,
+    sync::{Mutex, MutexGuard},
+    time::Instant,
+};
+
+use crate::tantivy_file_indexer::models::crawler_status_model::CrawlerWorkerStatusModel;
+
+struct Inner {
+    current_directory: Option<String>,
+    is_paused: bool,
+}
+
+/// What a worker is doing right now. The worker updates it and the task manager's handle to the worker reads it
+pub struct CrawlerWorkerStatus {
+    id: usize,
+    started_at: Instant,
+    inner: Mutex<Inner>,
+}
+
+impl CrawlerWorkerStatus {
+    pub fn new(id: usize) -> Self {
+        Self {
+            id,
+            started_at: Instant::now(),
+            inner: Mutex::new(Inner {
+                current_directory: None,
+                is_paused: false,
+            }),
+        }
+    }
+
+    pub fn set_current_directory(&self, dir: Option<&Path>) {
+        self.lock().current_directory = dir.map(|dir| dir.to_string_lossy().to_string());
+    }
+
+    pub fn set_paused(&self, is_paused: bool) {
+        self.lock().is_paused = is_paused;
+    }
+
+    pub fn to_model(&self) -> CrawlerWorkerStatusModel {
+        let inner = self.lock();
+        CrawlerWorkerStatusModel {
+            id: self.id,
+            current_directory: inner.current_directory.clone(),
+            uptime_secs: self.started_at.elapsed().as_secs(),
+            is_paused: inner.is_paused,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
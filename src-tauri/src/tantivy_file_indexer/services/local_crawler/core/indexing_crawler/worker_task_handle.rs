@@ -1,19 +1,38 @@
+use std::sync::Arc;
+
 use tokio::task::JoinHandle;
 
-use super::task_manager::CrawlerManagerMessageSender;
+use super::{task_manager::CrawlerManagerMessageSender, worker_status::CrawlerWorkerStatus};
 
 pub struct CrawlerWorkerTaskHandle {
     pub sender: CrawlerManagerMessageSender,
     task: JoinHandle<()>,
+    status: Arc<CrawlerWorkerStatus>,
 }
 
 impl CrawlerWorkerTaskHandle {
-    pub fn new(sender: CrawlerManagerMessageSender, task: JoinHandle<()>) -> Self {
+    pub fn new(
+        sender: CrawlerManagerMessageSender,
+        task: JoinHandle<()>,
+        status: Arc<CrawlerWorkerStatus>,
+    ) -> Self {
         Self {
             sender,
             task,
+            status,
+        }
+    }
+
+    /// Waits for the worker to finish, which it only does after being killed
+    pub async fn join(self) {
+        if let Err(err) = self.task.await {
+            println!("Crawler worker task did not finish cleanly: {}", err);
         }
     }
+
+    pub fn status(&self) -> &CrawlerWorkerStatus {
+        &self.status
+    }
    
     pub fn is_finished(&self)->bool{
         self.task.is_finished()
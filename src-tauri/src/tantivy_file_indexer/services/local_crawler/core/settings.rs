@@ -1,14 +1,159 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::sync::watch;
+
 use crate::tantivy_file_indexer::services::local_db::tables::app_kv_store::api::AppKvStoreTable;
 
 const KV_STORE_NAME: &str = "crawlerSettings";
 
+/// The crawler settings as the task manager last read them. It reads them every few seconds, so that the crawlers and
+/// the index watcher don't each have to read them from the KV store
+pub type SharedCrawlerSettings = watch::Receiver<Arc<CrawlerSettings>>;
+
 /// This is to be stored in the KV table
 #[derive(serde::Serialize, serde::Deserialize, Default, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct CrawlerSettings {
     pub max_num_crawlers: u32,
+    /// Files larger than this many bytes won't have their metadata or text extracted. Zero means that the default limit is used
+    #[serde(default)]
+    pub max_extract_file_size: u64,
+    /// Whether the files inside of zip, tar, tar.gz and 7z archives get indexed as well
+    #[serde(default)]
+    pub index_archive_members: bool,
+    /// Whether directories that haven't changed since their files were last indexed get indexed again anyway
+    #[serde(default)]
+    pub reindex_unchanged_directories: bool,
+    /// Whether only the modified time of a directory is compared to find out if it changed. This is faster, but misses
+    /// files that were changed in place, since that doesn't change the modified time of the directory that they are in
+    #[serde(default)]
+    pub compare_modified_times_only: bool,
+    /// Whether unchanged directories are left out of the crawl entirely instead of still being descended into. This is
+    /// faster, but misses changes deeper down, since a directory only changes when its direct children do
+    #[serde(default)]
+    pub stop_at_unchanged_directories: bool,
+    /// Whether the crawler queue is read from and written to SQLite directly, instead of being kept in memory and
+    /// written to SQLite in the background. This uses less memory for very large queues, but is a lot slower. Takes
+    /// effect when the app is restarted
+    #[serde(default)]
+    pub sqlite_queue: bool,
+    /// How often the index roots get crawled again, in minutes, to pick up the changes that the index watcher missed.
+    /// Roots with their own refresh interval use that instead. Zero means that the default interval is used
+    #[serde(default)]
+    pub full_refresh_interval_minutes: u32,
+    /// Whether the crawlers run at full speed no matter how busy the computer is
+    #[serde(default)]
+    pub disable_adaptive_throttle: bool,
+    /// The CPU usage, as a percentage, above which the crawlers start slowing down. Zero means that the default
+    /// threshold is used
+    #[serde(default)]
+    pub throttle_cpu_threshold_percent: u32,
+    /// The share of time that the CPU spends waiting on the disk, as a percentage, above which the crawlers start
+    /// slowing down. Zero means that the default threshold is used
+    #[serde(default)]
+    pub throttle_io_wait_threshold_percent: u32,
+    /// Whether the crawlers keep running at full speed while the computer is on battery
+    #[serde(default)]
+    pub full_speed_on_battery: bool,
+    /// How many seconds after the user last interacted with the app the crawlers stay slowed down. Zero means that
+    /// the default is used
+    #[serde(default)]
+    pub user_idle_secs: u32,
+    /// How long each crawler rests after each file when the crawlers are slowed down all the way, in milliseconds.
+    /// Zero means that the default is used
+    #[serde(default)]
+    pub max_file_rest_millis: u32,
+    /// Whether the index validator stays off. It goes through the whole index in the background, removing the files
+    /// that no longer exist and crawling again the directories of the files that changed
+    #[serde(default)]
+    pub disable_index_validator: bool,
+    /// How long the index validator waits after going through the whole index before starting over, in minutes. Zero
+    /// means that the default interval is used
+    #[serde(default)]
+    pub index_validator_interval_minutes: u32,
+    /// How many files the index validator checks at a time. Zero means that the default is used
+    #[serde(default)]
+    pub index_validator_chunk_size: u32,
 }
+
+/// 50 MB
+const DEFAULT_MAX_EXTRACT_FILE_SIZE: u64 = 50_000_000;
+/// 1 hour
+const DEFAULT_FULL_REFRESH_INTERVAL_MINUTES: u32 = 60;
+const DEFAULT_THROTTLE_CPU_THRESHOLD_PERCENT: u32 = 50;
+const DEFAULT_THROTTLE_IO_WAIT_THRESHOLD_PERCENT: u32 = 10;
+const DEFAULT_USER_IDLE_SECS: u32 = 30;
+const DEFAULT_MAX_FILE_REST_MILLIS: u32 = 200;
+/// 1 day
+const DEFAULT_INDEX_VALIDATOR_INTERVAL_MINUTES: u32 = 60 * 24;
+const DEFAULT_INDEX_VALIDATOR_CHUNK_SIZE: u32 = 500;
+
 impl CrawlerSettings {
+    pub fn max_extract_file_size(&self) -> u64 {
+        if self.max_extract_file_size == 0 {
+            DEFAULT_MAX_EXTRACT_FILE_SIZE
+        } else {
+            self.max_extract_file_size
+        }
+    }
+
+    pub fn full_refresh_interval_minutes(&self) -> u32 {
+        if self.full_refresh_interval_minutes == 0 {
+            DEFAULT_FULL_REFRESH_INTERVAL_MINUTES
+        } else {
+            self.full_refresh_interval_minutes
+        }
+    }
+
+    pub fn throttle_cpu_threshold_percent(&self) -> u32 {
+        if self.throttle_cpu_threshold_percent == 0 {
+            DEFAULT_THROTTLE_CPU_THRESHOLD_PERCENT
+        } else {
+            self.throttle_cpu_threshold_percent
+        }
+    }
+
+    pub fn throttle_io_wait_threshold_percent(&self) -> u32 {
+        if self.throttle_io_wait_threshold_percent == 0 {
+            DEFAULT_THROTTLE_IO_WAIT_THRESHOLD_PERCENT
+        } else {
+            self.throttle_io_wait_threshold_percent
+        }
+    }
+
+    pub fn user_idle_secs(&self) -> u32 {
+        if self.user_idle_secs == 0 {
+            DEFAULT_USER_IDLE_SECS
+        } else {
+            self.user_idle_secs
+        }
+    }
+
+    pub fn max_file_rest(&self) -> Duration {
+        if self.max_file_rest_millis == 0 {
+            Duration::from_millis(DEFAULT_MAX_FILE_REST_MILLIS.into())
+        } else {
+            Duration::from_millis(self.max_file_rest_millis.into())
+        }
+    }
+
+    pub fn index_validator_interval(&self) -> chrono::Duration {
+        let minutes = if self.index_validator_interval_minutes == 0 {
+            DEFAULT_INDEX_VALIDATOR_INTERVAL_MINUTES
+        } else {
+            self.index_validator_interval_minutes
+        };
+        chrono::Duration::minutes(minutes.into())
+    }
+
+    pub fn index_validator_chunk_size(&self) -> usize {
+        if self.index_validator_chunk_size == 0 {
+            DEFAULT_INDEX_VALIDATOR_CHUNK_SIZE as usize
+        } else {
+            self.index_validator_chunk_size as usize
+        }
+    }
+
     pub async fn get_from_db(kv: &AppKvStoreTable) -> Result<Self, String> {
         kv.get_or_create_default::<Self>(KV_STORE_NAME).await
     }
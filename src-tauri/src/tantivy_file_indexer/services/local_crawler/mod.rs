@@ -1,14 +1,32 @@
+pub mod analyzer {
+    pub mod service;
+}
 pub mod service;
 mod core {
     pub mod crawler_queue {
+        pub mod backend;
         pub mod queue;
         pub mod filter;
+        pub mod memory_queue;
+        pub mod scheduler;
         pub mod trait_impl {
             pub mod crawler_file;
             pub mod crawler_queue_api;
+            pub mod crawler_queue_backend_api;
+            pub mod memory_crawler_queue_api;
         }
     }
+    pub mod extractors {
+        pub mod archive;
+        pub mod audio;
+        pub mod document;
+        pub mod symbols;
+    }
     pub mod indexing_crawler;
     pub mod settings;
 }
 pub mod tauri_exports;
+pub mod watcher {
+    mod coalescer;
+    pub mod service;
+}
@@ -1,57 +1,152 @@
+use crate::shared::app_error::{AppError, AppResult};
+use crate::tantivy_file_indexer::dtos::index_root_dto::IndexRootDTO;
+use crate::tantivy_file_indexer::models::crawler_status_model::CrawlerStatusModel;
 use crate::tantivy_file_indexer::services::local_crawler::core::indexing_crawler::plugins::{
-    FiltererPlugin, GarbageCollectorPlugin,
+    ArchiveIndexerPlugin, ChangeDetectorPlugin, FiltererPlugin, GarbageCollectorPlugin,
+    IndexRootsPlugin, MetadataExtractorPlugin, ThrottlePlugin,
 };
 use crate::tantivy_file_indexer::services::local_db::service::LocalDbService;
+use crate::tantivy_file_indexer::services::local_db::tables::index_roots::entities::index_root;
 use crate::tantivy_file_indexer::services::search_index::service::SearchIndexService;
+use crate::tantivy_file_indexer::services::volumes::service::VolumeService;
 use crate::tantivy_file_indexer::shared::async_retry;
+use crate::tantivy_file_indexer::shared::indexing_crawler::traits::crawler_queue_api::CrawlerQueueApi;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinHandle;
 
-use super::core::crawler_queue::queue::{CrawlerQueue, Priority};
-use super::core::indexing_crawler::factory;
-use super::core::indexing_crawler::task_manager;
+use super::analyzer::service::FileCrawlerAnalyzerService;
+use super::core::crawler_queue::{backend::CrawlerQueueBackend, queue::Priority};
+use super::core::indexing_crawler::task_manager::{
+    self, CrawlerTaskManagerHandle, CrawlerWorkerHandles,
+};
+use super::core::indexing_crawler::volume_monitor::{self, SharedIndexWatcher};
+use super::core::indexing_crawler::{factory, full_refresh, index_validator};
+use super::core::settings::CrawlerSettings;
+use super::watcher::service::IndexWatcherService;
+
+/// What runs while the crawlers are dispatched
+struct DispatchedCrawlers {
+    task_manager: CrawlerTaskManagerHandle,
+    status_events: JoinHandle<()>,
+    full_refresh: JoinHandle<()>,
+    volume_monitor: JoinHandle<()>,
+    index_validator: JoinHandle<()>,
+}
 
 pub struct FileCrawlerService {
-    /// True if the file crawlers are already crawling around
-    has_dispatched_crawlers: RwLock<bool>,
+    /// `Some` while the file crawlers are crawling around, even if they are paused
+    dispatched: RwLock<Option<DispatchedCrawlers>>,
 
-    queue: Arc<CrawlerQueue>,
+    queue: Arc<CrawlerQueueBackend>,
     search_index: Arc<SearchIndexService>,
     local_db_service: Arc<LocalDbService>,
+    /// Where the app saves its data. Changes in here are never indexed
+    app_path: PathBuf,
+    /// Shared with the volume monitor, which restarts it when a drive comes back or moves
+    index_watcher: SharedIndexWatcher,
+    index_roots: Arc<IndexRootsPlugin>,
+    volumes: Arc<VolumeService>,
+    analyzer: Arc<FileCrawlerAnalyzerService>,
+    /// Shared by the crawlers and the task manager, and kept between dispatches so that the user's activity is
+    /// remembered
+    throttle: Arc<ThrottlePlugin>,
+    /// Empty until the crawlers are dispatched
+    workers: CrawlerWorkerHandles,
 }
 
+/// How often the `crawler_status` event is emitted while the crawlers are running
+const STATUS_EVENT_INTERVAL: Duration = Duration::from_secs(2);
+
 impl FileCrawlerService {
     pub async fn new_async(
         local_db_service: Arc<LocalDbService>,
         search_index: Arc<SearchIndexService>,
+        volumes: Arc<VolumeService>,
+        app_path: PathBuf,
     ) -> Self {
-        let queue = Arc::new(CrawlerQueue::new(Arc::clone(&local_db_service)));
-        Self {
-            has_dispatched_crawlers: RwLock::new(false),
+        let index_roots = Arc::new(IndexRootsPlugin::new(Arc::clone(&local_db_service)));
+        let queue = Arc::new(
+            CrawlerQueueBackend::new_async(Arc::clone(&local_db_service), Arc::clone(&index_roots))
+                .await,
+        );
+        let service = Self {
+            dispatched: RwLock::new(None),
             queue,
             search_index: Arc::clone(&search_index),
             local_db_service,
+            app_path,
+            index_watcher: Arc::new(RwLock::new(None)),
+            index_roots,
+            volumes,
+            analyzer: Arc::new(FileCrawlerAnalyzerService::new(
+                STATUS_EVENT_INTERVAL.as_secs(),
+            )),
+            throttle: Arc::new(ThrottlePlugin::new()),
+            workers: Arc::new(RwLock::new(Vec::new())),
+        };
+        if search_index.was_reset() {
+            service.reset_crawl_state().await;
+        }
+        service
+    }
+
+    /// Nothing that the crawlers remember about the index holds once it got wiped. The directory states are forgotten so
+    /// that no directory gets skipped as unchanged, the index validator starts over, and the enabled roots get crawled
+    /// again
+    async fn reset_crawl_state(&self) {
+        if let Err(err) = self
+            .local_db_service
+            .directory_states_table()
+            .delete_all()
+            .await
+        {
+            println!(
+                "FileCrawlerService - Could not clear the directory states: {}",
+                err
+            );
+        }
+        if let Err(err) = index_validator::reset(self.local_db_service.kv_store_table()).await {
+            println!(
+                "FileCrawlerService - Could not reset the index validator: {}",
+                err
+            );
+        }
+        match self.index_roots.get_enabled().await {
+            Ok(roots) => {
+                let paths = roots
+                    .iter()
+                    .map(|root| (PathBuf::from(&root.path), root.priority))
+                    .collect();
+                self.push_dirs(paths).await;
+            }
+            Err(err) => println!(
+                "FileCrawlerService - Could not read the index roots to crawl them again: {}",
+                err
+            ),
         }
     }
 
     /// Once built, the crawlers will get dispatched and start working
     ///
-    /// If this function gets called while the crawlers are already dispatched, nothing will happen
-    pub async fn dispatch_crawlers(&self) -> Result<(), String> {
-        let mut has_dispatched_crawlers_lock = self.has_dispatched_crawlers.write().await;
-        if *has_dispatched_crawlers_lock {
-            return Err("Crawlers have already been dispatched".to_string());
+    /// Returns `AlreadyExists` if the crawlers are already dispatched
+    pub async fn dispatch_crawlers(&self, app_handle: AppHandle) -> AppResult<()> {
+        let mut dispatched_lock = self.dispatched.write().await;
+        if dispatched_lock.is_some() {
+            return Err(AppError::AlreadyExists(
+                "Crawlers have already been dispatched".to_string(),
+            ));
         }
-        *has_dispatched_crawlers_lock = true;
 
         let pipeline = self.search_index.get_pipeline();
         let crawler_queue = Arc::clone(&self.queue);
         crawler_queue
             .set_taken_to_false_all()
             .await
-            .map_err(|err| err.to_string())?;
+            .map_err(AppError::DbError)?;
 
         // Create the garbage collector and inject it
         let collector = Arc::new(GarbageCollectorPlugin::new(
@@ -63,16 +158,297 @@ impl FileCrawlerService {
             self.local_db_service.kv_store_table().clone(),
         ));
 
-        let factory = factory::IndexingCrawlersFactory::new(crawler_queue, pipeline)
-            .set_garbage_collector(collector)
-            .set_filterer(filterer);
+        let metadata_extractor = Arc::new(MetadataExtractorPlugin::new());
+
+        // The task manager reads the crawler settings every few seconds and shares them with the crawlers and the
+        // index watcher
+        let settings = watch::Sender::new(Arc::new(
+            CrawlerSettings::get_from_db(self.local_db_service.kv_store_table())
+                .await
+                .map_err(AppError::DbError)?,
+        ));
+
+        let archive_indexer = Arc::new(ArchiveIndexerPlugin::new(Arc::clone(
+            &self.local_db_service,
+        )));
+
+        let change_detector = Arc::new(ChangeDetectorPlugin::new(Arc::clone(
+            &self.local_db_service,
+        )));
+
+        let index_watcher = Arc::new(
+            IndexWatcherService::new(Arc::clone(&pipeline), Arc::clone(&crawler_queue))
+                .set_filterer(Arc::clone(&filterer))
+                .set_metadata_extractor(Arc::clone(&metadata_extractor))
+                .set_index_roots(Arc::clone(&self.index_roots))
+                .set_volumes(Arc::clone(&self.volumes))
+                .set_settings(settings.subscribe())
+                .set_ignored_dirs(vec![self.app_path.clone()]),
+        );
+
+        let factory = factory::IndexingCrawlersFactory::new(
+            Arc::clone(&crawler_queue),
+            Arc::clone(&pipeline),
+        )
+        .set_garbage_collector(collector)
+        .set_filterer(filterer)
+        .set_metadata_extractor(metadata_extractor)
+        .set_archive_indexer(archive_indexer)
+        .set_index_roots(Arc::clone(&self.index_roots))
+        .set_analyzer(Arc::clone(&self.analyzer))
+        .set_change_detector(change_detector)
+        .set_throttle(Arc::clone(&self.throttle))
+        .set_volumes(Arc::clone(&self.volumes));
 
         // Hand off the rest of the building to the task manager
         // Currently the only way this can fail is if the DB can't be accessed since the crawler settings need to be retrieved
-        task_manager::build_managed(factory, Arc::clone(&self.local_db_service)).await?;
+        let task_manager = task_manager::build_managed(
+            factory,
+            Arc::clone(&self.local_db_service),
+            Arc::clone(&self.workers),
+            Arc::clone(&self.throttle),
+            settings,
+        )
+        .await
+        .map_err(AppError::DbError)?;
+        *dispatched_lock = Some(DispatchedCrawlers {
+            task_manager,
+            status_events: self.emit_status_periodically(app_handle.clone()),
+            full_refresh: full_refresh::spawn(
+                Arc::clone(&self.queue),
+                Arc::clone(&self.index_roots),
+                self.local_db_service.kv_store_table().clone(),
+            ),
+            index_validator: index_validator::spawn(
+                Arc::clone(&pipeline),
+                Arc::clone(&crawler_queue),
+                Arc::clone(&self.index_roots),
+                Arc::clone(&self.volumes),
+                Arc::clone(&self.throttle),
+                Arc::clone(&self.local_db_service),
+            ),
+            volume_monitor: volume_monitor::spawn(
+                Arc::clone(&self.volumes),
+                pipeline,
+                Arc::clone(&self.local_db_service),
+                crawler_queue,
+                Arc::clone(&self.index_roots),
+                Arc::clone(&self.index_watcher),
+                app_handle,
+            ),
+        });
+
+        // The crawlers handle what changed while the app was closed, and the watcher handles what changes from now on
+        if let Err(err) = index_watcher.watch(self.watch_roots().await).await {
+            println!(
+                "FileCrawlerService - Could not start the index watcher: {}",
+                err
+            );
+        }
+        *self.index_watcher.write().await = Some(index_watcher);
         Ok(())
     }
 
+    /// The crawlers commit what they have crawled so far before pausing. The index watcher keeps running
+    pub async fn pause_crawlers(&self) -> AppResult<()> {
+        match self.dispatched.read().await.as_ref() {
+            Some(dispatched) => {
+                dispatched.task_manager.pause().await;
+                Ok(())
+            }
+            None => Err(Self::not_dispatched_error()),
+        }
+    }
+
+    pub async fn resume_crawlers(&self) -> AppResult<()> {
+        match self.dispatched.read().await.as_ref() {
+            Some(dispatched) => {
+                dispatched.task_manager.resume().await;
+                Ok(())
+            }
+            None => Err(Self::not_dispatched_error()),
+        }
+    }
+
+    /// Stops the crawlers, the index watcher, the background jobs and the status events. Returns once the crawlers
+    /// have committed what they crawled, after which they can be dispatched again
+    pub async fn stop_crawlers(&self) -> AppResult<()> {
+        let dispatched = self
+            .dispatched
+            .write()
+            .await
+            .take()
+            .ok_or_else(Self::not_dispatched_error)?;
+        if let Some(index_watcher) = self.index_watcher.write().await.take() {
+            index_watcher.stop().await;
+        }
+        dispatched.status_events.abort();
+        dispatched.full_refresh.abort();
+        dispatched.volume_monitor.abort();
+        dispatched.index_validator.abort();
+        dispatched.task_manager.stop().await;
+        if let Err(err) = self.queue.persist().await {
+            println!(
+                "FileCrawlerService - Could not write the crawler queue to SQLite: {}",
+                err
+            );
+        }
+        Ok(())
+    }
+
+    /// Stops the crawlers if they are running, then dispatches them again
+    pub async fn restart_crawlers(&self, app_handle: AppHandle) -> AppResult<()> {
+        if self.dispatched.read().await.is_some() {
+            self.stop_crawlers().await?;
+        }
+        self.dispatch_crawlers(app_handle).await
+    }
+
+    fn not_dispatched_error() -> AppError {
+        AppError::Internal("The crawlers have not been dispatched".to_string())
+    }
+
+    pub async fn status(&self) -> AppResult<CrawlerStatusModel> {
+        Ok(get_status(&self.analyzer, &self.queue, &self.workers, &self.throttle).await?)
+    }
+
+    /// Keeps the crawlers slowed down while the user is using the app
+    pub fn report_ui_activity(&self) {
+        self.throttle.record_ui_activity();
+    }
+
+    /// Emits the `crawler_status` event until the returned task is aborted
+    fn emit_status_periodically(&self, app_handle: AppHandle) -> JoinHandle<()> {
+        let analyzer = Arc::clone(&self.analyzer);
+        let queue = Arc::clone(&self.queue);
+        let workers = Arc::clone(&self.workers);
+        let throttle = Arc::clone(&self.throttle);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STATUS_EVENT_INTERVAL);
+            loop {
+                interval.tick().await;
+                analyzer.record_timestamp().await;
+                match get_status(&analyzer, &queue, &workers, &throttle).await {
+                    Ok(status) => {
+                        if let Err(err) = app_handle.emit("crawler_status", status) {
+                            println!(
+                                "FileCrawlerService - Could not emit the crawler status: {}",
+                                err
+                            );
+                        }
+                    }
+                    Err(err) => println!(
+                        "FileCrawlerService - Could not read the crawler queue for the crawler status: {}",
+                        err
+                    ),
+                }
+            }
+        })
+    }
+
+    /// The same roots that the full refresh job pushes to the crawler queue
+    async fn watch_roots(&self) -> Vec<PathBuf> {
+        match self.index_roots.get_enabled().await {
+            Ok(roots) => roots.iter().map(|root| PathBuf::from(&root.path)).collect(),
+            Err(err) => {
+                println!(
+                    "FileCrawlerService - Could not read the index roots: {}",
+                    err
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    pub async fn get_index_roots(&self) -> AppResult<Vec<index_root::Model>> {
+        Ok(self.local_db_service.index_roots_table().get_all().await?)
+    }
+
+    /// Adds the root and crawls it right away if it is enabled
+    pub async fn add_index_root(&self, dto: IndexRootDTO) -> AppResult<index_root::Model> {
+        self.validate_index_root(&dto, None).await?;
+        let root = self
+            .local_db_service
+            .index_roots_table()
+            .insert(dto)
+            .await?;
+        self.on_index_roots_changed().await;
+        if root.enabled {
+            self.push_dirs(vec![(PathBuf::from(&root.path), root.priority)])
+                .await;
+        }
+        Ok(root)
+    }
+
+    pub async fn update_index_root(
+        &self,
+        id: i32,
+        dto: IndexRootDTO,
+    ) -> AppResult<index_root::Model> {
+        self.validate_index_root(&dto, Some(id)).await?;
+        let root = self
+            .local_db_service
+            .index_roots_table()
+            .update(id, dto)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("There is no index root with the id {}", id))
+            })?;
+        self.on_index_roots_changed().await;
+        Ok(root)
+    }
+
+    /// What was already indexed from the root stays in the index
+    pub async fn remove_index_root(&self, id: i32) -> AppResult<()> {
+        if !self.local_db_service.index_roots_table().delete(id).await? {
+            return Err(AppError::NotFound(format!(
+                "There is no index root with the id {}",
+                id
+            )));
+        }
+        self.on_index_roots_changed().await;
+        Ok(())
+    }
+
+    /// `id` is the root that is being updated, which is allowed to keep its own path
+    async fn validate_index_root(&self, dto: &IndexRootDTO, id: Option<i32>) -> AppResult<()> {
+        let metadata = tokio::fs::metadata(&dto.path)
+            .await
+            .map_err(|err| AppError::from_io_with_path(err, &dto.path))?;
+        if !metadata.is_dir() {
+            return Err(AppError::InvalidArgument(format!(
+                "{} is not a directory",
+                dto.path
+            )));
+        }
+        let existing = self
+            .local_db_service
+            .index_roots_table()
+            .get_by_path(&dto.path)
+            .await?;
+        if existing.is_some_and(|existing| Some(existing.id) != id) {
+            return Err(AppError::AlreadyExists(format!(
+                "{} is already an index root",
+                dto.path
+            )));
+        }
+        Ok(())
+    }
+
+    /// The crawlers pick up the changes by themselves, but the watcher has to be told to watch the new roots
+    async fn on_index_roots_changed(&self) {
+        self.index_roots.invalidate().await;
+        let index_watcher = self.index_watcher.read().await.clone();
+        if let Some(index_watcher) = index_watcher {
+            if let Err(err) = index_watcher.watch(self.watch_roots().await).await {
+                println!(
+                    "FileCrawlerService - Could not restart the index watcher: {}",
+                    err
+                );
+            }
+        }
+    }
+
     pub async fn push_dirs(&self, paths: Vec<(PathBuf, Priority)>) {
         if let Err(err) = async_retry::retry_with_backoff(
             |_| self.queue.push_many(&paths),
@@ -88,3 +464,21 @@ impl FileCrawlerService {
         }
     }
 }
+
+async fn get_status(
+    analyzer: &FileCrawlerAnalyzerService,
+    queue: &CrawlerQueueBackend,
+    workers: &CrawlerWorkerHandles,
+    throttle: &ThrottlePlugin,
+) -> Result<CrawlerStatusModel, sea_orm::DbErr> {
+    let queue_depth = queue.get_priority_counts().await?;
+    let workers = workers
+        .read()
+        .await
+        .iter()
+        .map(|worker| worker.status().to_model())
+        .collect();
+    Ok(analyzer
+        .get_status(queue_depth, workers, throttle.status())
+        .await)
+}
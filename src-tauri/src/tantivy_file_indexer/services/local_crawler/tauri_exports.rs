@@ -1,14 +1,18 @@
 use super::core::crawler_queue::filter;
 use super::service::FileCrawlerService;
+use crate::shared::app_error::AppResult;
 use crate::tantivy_file_indexer::dtos::add_to_crawler_queue::AddToCrawlerQueueDTO;
+use crate::tantivy_file_indexer::dtos::index_root_dto::IndexRootDTO;
+use crate::tantivy_file_indexer::models::crawler_status_model::CrawlerStatusModel;
+use crate::tantivy_file_indexer::services::local_db::tables::index_roots::entities::index_root;
 use std::{path::Path, sync::Arc};
-use tauri::State;
+use tauri::{AppHandle, State};
 
 #[tauri::command]
 pub async fn add_dirs_to_crawler_queue(
     directories: Vec<AddToCrawlerQueueDTO>,
     service: State<'_, Arc<FileCrawlerService>>,
-) -> Result<(), ()> {
+) -> AppResult<()> {
     let directories = filter::filter_out_directories_to_add(directories);
 
     service
@@ -24,6 +28,82 @@ pub async fn add_dirs_to_crawler_queue(
 
 /// Dispatch the file crawlers if they are not already active
 #[tauri::command]
-pub async fn dispatch_crawlers(service: State<'_, Arc<FileCrawlerService>>) -> Result<(), String> {
-    service.dispatch_crawlers().await
-}
\ No newline at end of file
+pub async fn dispatch_crawlers(
+    app_handle: AppHandle,
+    service: State<'_, Arc<FileCrawlerService>>,
+) -> AppResult<()> {
+    service.dispatch_crawlers(app_handle).await
+}
+
+/// The crawlers commit what they have crawled so far before pausing
+#[tauri::command]
+pub async fn pause_crawlers(service: State<'_, Arc<FileCrawlerService>>) -> AppResult<()> {
+    service.pause_crawlers().await
+}
+
+#[tauri::command]
+pub async fn resume_crawlers(service: State<'_, Arc<FileCrawlerService>>) -> AppResult<()> {
+    service.resume_crawlers().await
+}
+
+/// Returns once the crawlers have committed what they crawled. They can be dispatched again afterwards
+#[tauri::command]
+pub async fn stop_crawlers(service: State<'_, Arc<FileCrawlerService>>) -> AppResult<()> {
+    service.stop_crawlers().await
+}
+
+#[tauri::command]
+pub async fn restart_crawlers(
+    app_handle: AppHandle,
+    service: State<'_, Arc<FileCrawlerService>>,
+) -> AppResult<()> {
+    service.restart_crawlers(app_handle).await
+}
+
+/// The same status is also emitted as the `crawler_status` event every few seconds while the crawlers are running
+#[tauri::command]
+pub async fn crawler_status(
+    service: State<'_, Arc<FileCrawlerService>>,
+) -> AppResult<CrawlerStatusModel> {
+    service.status().await
+}
+
+/// Called by the frontend while the user is interacting with the app, so that the crawlers slow down to stay out of
+/// the way
+#[tauri::command]
+pub fn report_ui_activity(service: State<'_, Arc<FileCrawlerService>>) {
+    service.report_ui_activity();
+}
+
+#[tauri::command]
+pub async fn get_index_roots(
+    service: State<'_, Arc<FileCrawlerService>>,
+) -> AppResult<Vec<index_root::Model>> {
+    service.get_index_roots().await
+}
+
+#[tauri::command]
+pub async fn add_index_root(
+    root: IndexRootDTO,
+    service: State<'_, Arc<FileCrawlerService>>,
+) -> AppResult<index_root::Model> {
+    service.add_index_root(root).await
+}
+
+#[tauri::command]
+pub async fn update_index_root(
+    id: i32,
+    root: IndexRootDTO,
+    service: State<'_, Arc<FileCrawlerService>>,
+) -> AppResult<index_root::Model> {
+    service.update_index_root(id, root).await
+}
+
+/// What was already indexed from the root stays in the index
+#[tauri::command]
+pub async fn remove_index_root(
+    id: i32,
+    service: State<'_, Arc<FileCrawlerService>>,
+) -> AppResult<()> {
+    service.remove_index_root(id).await
+}
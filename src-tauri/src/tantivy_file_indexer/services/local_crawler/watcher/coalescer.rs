@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use notify::event::{EventKind, ModifyKind};
+
+struct PendingPath {
+    first_seen: Instant,
+    last_seen: Instant,
+    /// Whether the path was created or moved into place, as opposed to just modified or removed
+    created: bool,
+}
+
+/// Collects the paths that file system events were reported for, so that a burst of events for the same path,
+/// such as a create followed by several writes, only gets handled once.
+///
+/// Apart from whether a path is new, what happened to it isn't kept, since the file system gets checked when the path is handled anyway
+pub struct EventCoalescer {
+    pending: HashMap<PathBuf, PendingPath>,
+    /// How long a path has to go without new events before it is handled
+    quiet_period: Duration,
+    /// A path that keeps changing, such as a log file, still gets handled after this long
+    max_delay: Duration,
+}
+
+impl EventCoalescer {
+    pub fn new(quiet_period: Duration, max_delay: Duration) -> Self {
+        Self {
+            pending: HashMap::new(),
+            quiet_period,
+            max_delay,
+        }
+    }
+
+    pub fn touch(&mut self, path: PathBuf, created: bool, now: Instant) {
+        self.pending
+            .entry(path)
+            .and_modify(|pending| {
+                pending.last_seen = now;
+                pending.created |= created;
+            })
+            .or_insert(PendingPath {
+                first_seen: now,
+                last_seen: now,
+                created,
+            });
+    }
+
+    /// Adds the paths of an event. Both sides of a rename count as created, since the old path gets removed from the
+    /// index when it turns out to be gone, while the new one might be a directory that has to be crawled
+    pub fn add_event(&mut self, kind: &EventKind, paths: Vec<PathBuf>, now: Instant) {
+        let created = match kind {
+            EventKind::Access(_) => return,
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)) => true,
+            _ => false,
+        };
+        for path in paths {
+            self.touch(path, created, now);
+        }
+    }
+
+    /// Removes and returns every pending path if there are more than `max_pending` of them, so that their directories
+    /// can be crawled instead of handling each path
+    pub fn take_overflowed(&mut self, max_pending: usize) -> Vec<PathBuf> {
+        if self.pending.len() > max_pending {
+            self.take_all()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Removes and returns the paths that are ready to be handled, along with whether each one was created
+    pub fn take_settled(&mut self, now: Instant) -> Vec<(PathBuf, bool)> {
+        let mut settled = Vec::new();
+        self.pending.retain(|path, pending| {
+            let is_settled = now.duration_since(pending.last_seen) >= self.quiet_period
+                || now.duration_since(pending.first_seen) >= self.max_delay;
+            if is_settled {
+                settled.push((path.clone(), pending.created));
+            }
+            !is_settled
+        });
+        settled
+    }
+
+    /// Removes and returns every pending path, whether it has settled or not
+    pub fn take_all(&mut self) -> Vec<PathBuf> {
+        self.pending.drain().map(|(path, _)| path).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{CreateKind, DataChange, RemoveKind, RenameMode};
+
+    use super::*;
+
+    const QUIET_PERIOD: Duration = Duration::from_millis(500);
+    const MAX_DELAY: Duration = Duration::from_secs(5);
+
+    fn sorted(mut settled: Vec<(PathBuf, bool)>) -> Vec<(PathBuf, bool)> {
+        settled.sort();
+        settled
+    }
+
+    #[test]
+    fn created_then_removed_paths_are_handled_once() {
+        let mut coalescer = EventCoalescer::new(QUIET_PERIOD, MAX_DELAY);
+        let start = Instant::now();
+        let path = PathBuf::from("/home/me/notes.txt");
+        coalescer.add_event(
+            &EventKind::Create(CreateKind::File),
+            vec![path.clone()],
+            start,
+        );
+        coalescer.add_event(
+            &EventKind::Modify(ModifyKind::Data(DataChange::Content)),
+            vec![path.clone()],
+            start + Duration::from_millis(100),
+        );
+        coalescer.add_event(
+            &EventKind::Remove(RemoveKind::File),
+            vec![path.clone()],
+            start + Duration::from_millis(200),
+        );
+
+        assert!(coalescer
+            .take_settled(start + Duration::from_millis(600))
+            .is_empty());
+        assert_eq!(
+            coalescer.take_settled(start + Duration::from_millis(700)),
+            vec![(path, true)]
+        );
+        assert!(coalescer.take_all().is_empty());
+    }
+
+    #[test]
+    fn both_sides_of_a_rename_count_as_created() {
+        let mut coalescer = EventCoalescer::new(QUIET_PERIOD, MAX_DELAY);
+        let start = Instant::now();
+        let from = PathBuf::from("/home/me/old");
+        let to = PathBuf::from("/home/me/new");
+        coalescer.add_event(
+            &EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+            vec![from.clone(), to.clone()],
+            start,
+        );
+
+        assert_eq!(
+            sorted(coalescer.take_settled(start + QUIET_PERIOD)),
+            vec![(to, true), (from, true)]
+        );
+    }
+
+    #[test]
+    fn modified_and_accessed_paths_are_not_created() {
+        let mut coalescer = EventCoalescer::new(QUIET_PERIOD, MAX_DELAY);
+        let start = Instant::now();
+        let path = PathBuf::from("/home/me/log.txt");
+        coalescer.add_event(
+            &EventKind::Access(notify::event::AccessKind::Any),
+            vec![PathBuf::from("/home/me/read.txt")],
+            start,
+        );
+        coalescer.add_event(
+            &EventKind::Modify(ModifyKind::Any),
+            vec![path.clone()],
+            start,
+        );
+
+        assert_eq!(
+            coalescer.take_settled(start + QUIET_PERIOD),
+            vec![(path, false)]
+        );
+    }
+
+    #[test]
+    fn paths_that_keep_changing_are_handled_after_the_max_delay() {
+        let mut coalescer = EventCoalescer::new(QUIET_PERIOD, MAX_DELAY);
+        let start = Instant::now();
+        let path = PathBuf::from("/var/log/app.log");
+        let mut now = start;
+        while now < start + MAX_DELAY {
+            coalescer.touch(path.clone(), false, now);
+            assert!(coalescer.take_settled(now).is_empty());
+            now += Duration::from_millis(250);
+        }
+
+        assert_eq!(coalescer.take_settled(now), vec![(path, false)]);
+    }
+
+    #[test]
+    fn overflowing_paths_are_taken_all_at_once() {
+        let mut coalescer = EventCoalescer::new(QUIET_PERIOD, MAX_DELAY);
+        let now = Instant::now();
+        for i in 0..3 {
+            coalescer.touch(PathBuf::from(format!("/home/me/{}.txt", i)), false, now);
+        }
+
+        assert!(coalescer.take_overflowed(3).is_empty());
+
+        let mut overflowed = coalescer.take_overflowed(2);
+        overflowed.sort();
+        assert_eq!(
+            overflowed,
+            vec![
+                PathBuf::from("/home/me/0.txt"),
+                PathBuf::from("/home/me/1.txt"),
+                PathBuf::from("/home/me/2.txt"),
+            ]
+        );
+        assert!(coalescer.take_all().is_empty());
+    }
+}
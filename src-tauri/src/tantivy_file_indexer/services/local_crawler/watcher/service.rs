@@ -0,0 +1,446 @@
+use std::{
+    collections::HashSet,
+    fs::Metadata,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex as StdMutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::{
+    sync::{
+        mpsc::{self, error::TrySendError},
+        Mutex,
+    },
+    task::JoinHandle,
+};
+
+use crate::tantivy_file_indexer::{
+    services::{
+        local_crawler::core::{
+            crawler_queue::{backend::CrawlerQueueBackend, queue::Priority},
+            indexing_crawler::{
+                plugins::{
+                    filterer::ShouldIndexResult, FiltererPlugin, IndexRootsPlugin,
+                    MetadataExtractorPlugin,
+                },
+                symlinks::{ResolvedEntry, SymlinkPolicy},
+            },
+            settings::{CrawlerSettings, SharedCrawlerSettings},
+        },
+        local_db::tables::index_roots::entities::index_root,
+        search_index::pipelines::tantivy_pipeline::TantivyPipeline,
+        volumes::service::VolumeService,
+    },
+    shared::indexing_crawler::traits::commit_pipeline::CrawlerCommitPipeline,
+    util::path::is_hidden,
+};
+
+use super::coalescer::EventCoalescer;
+
+/// The number of events that can be waiting to be handled before the watcher falls back to crawling
+const EVENT_CHANNEL_CAPACITY: usize = 4096;
+/// Past this many changed paths waiting to settle, their parent directories get crawled instead
+const MAX_PENDING_PATHS: usize = 2_000;
+const QUIET_PERIOD: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(5);
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+/// Directories that the watcher couldn't keep up with are crawled soon, but after anything that the user asked for
+const FALLBACK_CRAWL_PRIORITY: Priority = 1;
+
+/// Directories that need to be crawled because the events inside of them were lost
+type OverflowedDirs = Arc<StdMutex<HashSet<PathBuf>>>;
+
+struct ActiveWatch {
+    /// Events stop being reported once this is dropped
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+}
+
+/// Keeps the index up to date between crawls by watching the roots for changes and indexing changed files right away.
+///
+/// If changes come in faster than they can be handled, the directories that they happened in get pushed to the crawler queue instead
+pub struct IndexWatcherService {
+    pipeline: Arc<TantivyPipeline>,
+    queue: Arc<CrawlerQueueBackend>,
+    filterer: Option<Arc<FiltererPlugin>>,
+    metadata_extractor: Option<Arc<MetadataExtractorPlugin>>,
+    /// Changes are only indexed as deep as the root that they happened in gets crawled, and hidden files are left out
+    /// unless the root includes them
+    index_roots: Option<Arc<IndexRootsPlugin>>,
+    /// Unplugging a drive reports everything on it as removed, which shouldn't remove it from the index
+    volumes: Option<Arc<VolumeService>>,
+    /// The defaults are used until the crawler settings are set
+    settings: Option<SharedCrawlerSettings>,
+    /// Nothing inside of these gets indexed. The app's own save directory belongs here, since the index lives inside of it
+    ignored_dirs: Vec<PathBuf>,
+    active: Mutex<Option<ActiveWatch>>,
+}
+
+impl IndexWatcherService {
+    pub fn new(pipeline: Arc<TantivyPipeline>, queue: Arc<CrawlerQueueBackend>) -> Self {
+        Self {
+            pipeline,
+            queue,
+            filterer: None,
+            metadata_extractor: None,
+            index_roots: None,
+            volumes: None,
+            settings: None,
+            ignored_dirs: Vec::new(),
+            active: Mutex::new(None),
+        }
+    }
+
+    pub fn set_filterer(mut self, f: Arc<FiltererPlugin>) -> Self {
+        self.filterer = Some(f);
+        self
+    }
+
+    pub fn set_metadata_extractor(mut self, e: Arc<MetadataExtractorPlugin>) -> Self {
+        self.metadata_extractor = Some(e);
+        self
+    }
+
+    pub fn set_index_roots(mut self, r: Arc<IndexRootsPlugin>) -> Self {
+        self.index_roots = Some(r);
+        self
+    }
+
+    pub fn set_volumes(mut self, v: Arc<VolumeService>) -> Self {
+        self.volumes = Some(v);
+        self
+    }
+
+    pub fn set_settings(mut self, s: SharedCrawlerSettings) -> Self {
+        self.settings = Some(s);
+        self
+    }
+
+    pub fn set_ignored_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.ignored_dirs = dirs;
+        self
+    }
+
+    /// Recursively watches every root, replacing whatever was being watched before.
+    /// Roots that can't be watched, such as ones that don't exist, are skipped
+    pub async fn watch(self: &Arc<Self>, roots: Vec<PathBuf>) -> notify::Result<()> {
+        self.stop().await;
+
+        let (sender, receiver) = mpsc::channel(EVENT_CHANNEL_CAPACITY);
+        let overflowed: OverflowedDirs = Arc::new(StdMutex::new(HashSet::new()));
+        let overflowed_clone = Arc::clone(&overflowed);
+
+        let mut watcher = RecommendedWatcher::new(
+            move |result: notify::Result<Event>| {
+                if let Err(TrySendError::Full(result)) = sender.try_send(result) {
+                    // Too many events are waiting, so crawl where this one happened instead of handling it
+                    let paths = match result {
+                        Ok(event) => event.paths,
+                        Err(err) => err.paths,
+                    };
+                    lock(&overflowed_clone).extend(parents(&paths));
+                }
+            },
+            Config::default(),
+        )?;
+        for root in roots.iter() {
+            match watcher.watch(root, RecursiveMode::Recursive) {
+                Ok(_) => println!(
+                    "IndexWatcherService: Watching {} for changes",
+                    root.display()
+                ),
+                Err(err) => println!(
+                    "IndexWatcherService: Could not watch {}: {}",
+                    root.display(),
+                    err
+                ),
+            }
+        }
+
+        let service = Arc::clone(self);
+        let task =
+            tokio::spawn(async move { service.handle_events(receiver, overflowed, roots).await });
+        *self.active.lock().await = Some(ActiveWatch {
+            _watcher: watcher,
+            task,
+        });
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        if let Some(active) = self.active.lock().await.take() {
+            active.task.abort();
+        }
+    }
+
+    async fn handle_events(
+        &self,
+        mut receiver: mpsc::Receiver<notify::Result<Event>>,
+        overflowed: OverflowedDirs,
+        roots: Vec<PathBuf>,
+    ) {
+        let mut coalescer = EventCoalescer::new(QUIET_PERIOD, MAX_DELAY);
+        let mut flush_interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            tokio::select! {
+                received = receiver.recv() => match received {
+                    Some(Ok(event)) => self.coalesce_event(event, &mut coalescer, &overflowed, &roots),
+                    Some(Err(err)) => {
+                        println!("IndexWatcherService: The file system watcher reported an error: {}", err);
+                        lock(&overflowed).extend(parents(&err.paths));
+                    }
+                    None => break,
+                },
+                _ = flush_interval.tick() => {
+                    let pending = coalescer.take_overflowed(MAX_PENDING_PATHS);
+                    lock(&overflowed).extend(parents(&pending));
+                    for (path, created) in coalescer.take_settled(Instant::now()) {
+                        self.handle_change(path, created).await;
+                    }
+                    let dirs: Vec<PathBuf> = lock(&overflowed).drain().collect();
+                    self.queue_for_crawl(dirs).await;
+                }
+            }
+        }
+    }
+
+    fn coalesce_event(
+        &self,
+        event: Event,
+        coalescer: &mut EventCoalescer,
+        overflowed: &OverflowedDirs,
+        roots: &[PathBuf],
+    ) {
+        if event.need_rescan() {
+            // The OS dropped events, so there is no telling what changed underneath these paths
+            let dirs = if event.paths.is_empty() {
+                roots.to_vec()
+            } else {
+                event.paths
+            };
+            lock(overflowed).extend(dirs);
+            return;
+        }
+        let paths: Vec<PathBuf> = event
+            .paths
+            .into_iter()
+            .filter(|path| !self.is_ignored(path))
+            .collect();
+        coalescer.add_event(&event.kind, paths, Instant::now());
+    }
+
+    /// Brings the index in line with whatever is at `path` right now
+    async fn handle_change(&self, path: PathBuf, created: bool) {
+        // The link itself is read, so that a symlink whose target is gone doesn't get removed from the index
+        match tokio::fs::symlink_metadata(&path).await {
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                if let Some(volumes) = &self.volumes {
+                    if volumes.is_offline_now(&path).await {
+                        return;
+                    }
+                }
+                // Whether it was a file or a directory is unknown, so anything indexed underneath it gets removed too
+                let path_str = path.to_string_lossy().to_string();
+                if let Err(err) = self.pipeline.remove_tree(path_str).await {
+                    println!(
+                        "IndexWatcherService: Could not remove {} from the index: {}",
+                        path.display(),
+                        err
+                    );
+                }
+                return;
+            }
+            Err(err) => {
+                println!(
+                    "IndexWatcherService: Could not read the metadata of {}: {}",
+                    path.display(),
+                    err
+                );
+                return;
+            }
+        }
+
+        let root = match &self.index_roots {
+            Some(index_roots) => index_roots.root_for(&path).await,
+            None => None,
+        };
+        let symlink_policy = match &self.filterer {
+            Some(filterer) => filterer.symlink_policy().await,
+            None => SymlinkPolicy::default(),
+        }
+        .for_root(root.as_ref());
+        let Some(resolved) = ResolvedEntry::read(&path, symlink_policy) else {
+            return;
+        };
+        if root
+            .as_ref()
+            .is_some_and(|root| !is_reached_by_crawl(root, &path, &resolved.metadata))
+        {
+            return;
+        }
+        if !self.should_index(&path, resolved.metadata.is_dir()).await {
+            return;
+        }
+        let visited_links = match (&self.index_roots, &root) {
+            (Some(index_roots), Some(root)) => Some(index_roots.visited_links(root, &path)),
+            _ => None,
+        };
+        // A directory that was moved or extracted into place doesn't report events for what is inside of it
+        let should_crawl = created
+            && root.as_ref().is_none_or(|root| root.reaches(&path))
+            && resolved.can_descend(&path, visited_links.as_deref());
+        let model = match resolved.into_model(path.clone()) {
+            Ok(model) => model,
+            Err(err) => {
+                println!(
+                    "IndexWatcherService: Could not create a model for {}: {}",
+                    path.display(),
+                    err
+                );
+                return;
+            }
+        };
+        let model = match &self.metadata_extractor {
+            Some(extractor) => {
+                let settings = match &self.settings {
+                    Some(settings) => Arc::clone(&settings.borrow()),
+                    None => Arc::new(CrawlerSettings::default()),
+                };
+                extractor.extract(vec![model], &settings).await.pop()
+            }
+            None => Some(model),
+        };
+        if let Some(model) = model {
+            if let Err(err) = self.pipeline.upsert_one(model).await {
+                println!(
+                    "IndexWatcherService: Could not index {}: {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+        if should_crawl {
+            self.queue_for_crawl(vec![path]).await;
+        }
+    }
+
+    async fn should_index(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(filterer) = &self.filterer else {
+            return true;
+        };
+        let dir = if is_dir { Some(path) } else { path.parent() };
+        if let Some(dir) = dir {
+            if !filterer.should_crawl_directory(dir).await {
+                return false;
+            }
+        }
+        if !is_dir && filterer.is_excluded_by_ignore_files(path, false).await {
+            return false;
+        }
+        matches!(
+            filterer.should_index(path).await,
+            ShouldIndexResult::ShouldIndex
+        )
+    }
+
+    async fn queue_for_crawl(&self, dirs: Vec<PathBuf>) {
+        let entries: Vec<(PathBuf, Priority)> = dirs
+            .into_iter()
+            .filter(|dir| !self.is_ignored(dir))
+            .map(|dir| (dir, FALLBACK_CRAWL_PRIORITY))
+            .collect();
+        if entries.is_empty() {
+            return;
+        }
+        if let Err(err) = self.queue.push_many(&entries).await {
+            println!(
+                "IndexWatcherService: Could not push {} directories to the crawler queue: {}",
+                entries.len(),
+                err
+            );
+        }
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        self.ignored_dirs.iter().any(|dir| path.starts_with(dir))
+    }
+}
+
+/// The watcher sees changes anywhere underneath the root, but only what the crawl would have gotten to gets indexed. That
+/// leaves out anything deeper than the root goes, and anything hidden unless the root includes hidden files
+fn is_reached_by_crawl(root: &index_root::Model, path: &Path, metadata: &Metadata) -> bool {
+    if !path.parent().is_some_and(|parent| root.reaches(parent)) {
+        return false;
+    }
+    if root.include_hidden {
+        return true;
+    }
+    // The crawl doesn't go into hidden directories either
+    let root_path = Path::new(&root.path);
+    let in_hidden_dir = path
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| *dir != root_path)
+        .any(|dir| {
+            dir.metadata()
+                .is_ok_and(|metadata| is_hidden(dir, &metadata))
+        });
+    !is_hidden(path, metadata) && !in_hidden_dir
+}
+
+fn parents(paths: &[PathBuf]) -> impl Iterator<Item = PathBuf> + '_ {
+    paths
+        .iter()
+        .filter_map(|path| path.parent().map(Path::to_path_buf))
+}
+
+fn lock(overflowed: &OverflowedDirs) -> MutexGuard<'_, HashSet<PathBuf>> {
+    overflowed
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_what_the_crawl_gets_to_is_reached() {
+        let dir = std::env::temp_dir().join(format!("watcher_test_{}", uuid::Uuid::new_v4()));
+        let hidden_file = dir.join("docs").join(".notes.txt");
+        let in_hidden_dir = dir.join(".cache").join("page.html");
+        let too_deep = dir.join("a").join("b").join("c.txt");
+        let visible = dir.join("a").join("b.txt");
+        let paths = [&hidden_file, &in_hidden_dir, &too_deep, &visible];
+        for path in paths {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let mut root = index_root::Model {
+            id: 1,
+            path: dir.to_string_lossy().to_string(),
+            enabled: true,
+            max_depth: Some(1),
+            priority: 1,
+            refresh_interval_minutes: None,
+            follow_symlinks: false,
+            include_hidden: false,
+            last_crawled_at: None,
+        };
+        let reached = |root: &index_root::Model, path: &Path| {
+            is_reached_by_crawl(root, path, &path.symlink_metadata().unwrap())
+        };
+
+        let without_hidden = paths.map(|path| reached(&root, path));
+        root.include_hidden = true;
+        root.max_depth = None;
+        let with_hidden = paths.map(|path| reached(&root, path));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(without_hidden, [false, false, false, true]);
+        assert_eq!(with_hidden, [true, true, true, true]);
+    }
+}
@@ -9,12 +9,30 @@ pub mod tables {
             pub mod indexed_dir;
         }
     }
+    pub mod directory_states {
+        pub mod api;
+        pub mod entities {
+            pub mod directory_state;
+        }
+    }
+    pub mod index_roots {
+        pub mod api;
+        pub mod entities {
+            pub mod index_root;
+        }
+    }
     pub mod recently_indexed_dirs {
         pub mod api;
         pub mod entities {
             pub mod recently_indexed_dir;
         }
     }
+    pub mod volumes {
+        pub mod api;
+        pub mod entities {
+            pub mod volume;
+        }
+    }
     pub mod app_kv_store{
         pub mod api;
         mod models{
@@ -4,7 +4,8 @@ use crate::tantivy_file_indexer::services::app_save::service::AppSaveService;
 
 use super::tables::{
     app_kv_store::api::AppKvStoreTable, crawler_queue::api::CrawlerQueueTable,
-    recently_indexed_dirs::api::RecentlyIndexedDirectoriesTable,
+    directory_states::api::DirectoryStatesTable, index_roots::api::IndexRootsTable,
+    recently_indexed_dirs::api::RecentlyIndexedDirectoriesTable, volumes::api::VolumesTable,
 };
 use sea_orm::{ConnectionTrait, DatabaseConnection, Statement};
 use sqlx::sqlite::SqlitePool;
@@ -15,6 +16,9 @@ pub struct LocalDbService {
     recently_indexed_dirs_table: RecentlyIndexedDirectoriesTable,
     crawler_queue_table: CrawlerQueueTable,
     kv_store_table: AppKvStoreTable,
+    index_roots_table: IndexRootsTable,
+    directory_states_table: DirectoryStatesTable,
+    volumes_table: VolumesTable,
 }
 
 impl LocalDbService {
@@ -22,10 +26,13 @@ impl LocalDbService {
     pub async fn new_async(save_service: &AppSaveService, app_handle: AppHandle) -> Self {
         let db_path = save_service.create_path("file_index.db");
         let db_url = format!("sqlite://{}", db_path.to_string_lossy());
+        Self::connect(&db_url, Some(app_handle)).await.unwrap()
+    }
 
+    /// Without an app handle, changes to the KV store aren't emitted to the frontend
+    pub async fn connect(db_url: &str, app_handle: Option<AppHandle>) -> Result<Self, sqlx::Error> {
         // Starts out as a SQLX pool, but 'into' is called to turn it into a Sea ORM database connection
-        let db: Arc<DatabaseConnection> =
-            Arc::new(SqlitePool::connect(&db_url).await.unwrap().into());
+        let db: Arc<DatabaseConnection> = Arc::new(SqlitePool::connect(db_url).await?.into());
 
         // initialize the tables
         let recently_indexed_dirs_table =
@@ -35,12 +42,21 @@ impl LocalDbService {
 
         let kv_store_table = AppKvStoreTable::new_async(db.clone(), app_handle).await;
 
-        Self {
+        let index_roots_table = IndexRootsTable::new_async(db.clone()).await;
+
+        let directory_states_table = DirectoryStatesTable::new_async(db.clone()).await;
+
+        let volumes_table = VolumesTable::new_async(db.clone()).await;
+
+        Ok(Self {
             connection: db,
             recently_indexed_dirs_table,
             crawler_queue_table,
             kv_store_table,
-        }
+            index_roots_table,
+            directory_states_table,
+            volumes_table,
+        })
     }
 
     pub fn recently_indexed_dirs_table(&self) -> &RecentlyIndexedDirectoriesTable {
@@ -55,6 +71,18 @@ impl LocalDbService {
         &self.kv_store_table
     }
 
+    pub fn index_roots_table(&self) -> &IndexRootsTable {
+        &self.index_roots_table
+    }
+
+    pub fn directory_states_table(&self) -> &DirectoryStatesTable {
+        &self.directory_states_table
+    }
+
+    pub fn volumes_table(&self) -> &VolumesTable {
+        &self.volumes_table
+    }
+
     /// Since SQLite doesn't automatically free unused memory, you can use this to shrink the size of the database
     pub async fn vacuum_database(&self) -> Result<(), sea_orm::DbErr> {
         // Execute the VACUUM command
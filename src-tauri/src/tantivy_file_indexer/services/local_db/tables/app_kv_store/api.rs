@@ -26,7 +26,7 @@ pub struct AppKvStoreTable {
 }
 
 impl AppKvStoreTable {
-    pub async fn new_async(db: Arc<DatabaseConnection>, app_handle: AppHandle) -> Self {
+    pub async fn new_async(db: Arc<DatabaseConnection>, app_handle: Option<AppHandle>) -> Self {
         generate_table_lenient(&db, kv_pair::Entity).await;
 
         Self {
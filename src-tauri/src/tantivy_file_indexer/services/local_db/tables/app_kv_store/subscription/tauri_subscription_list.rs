@@ -13,11 +13,12 @@ pub struct EmitSubscription {
 /// Freely cloneable as all of the underlying data is cheap
 pub struct TauriSubscriptionList {
     events: Arc<RwLock<Vec<EmitSubscription>>>,
-    app_handle: AppHandle,
+    /// Nothing gets emitted without one
+    app_handle: Option<AppHandle>,
 }
 
 impl TauriSubscriptionList {
-    pub fn new(app_handle: AppHandle) -> Self {
+    pub fn new(app_handle: Option<AppHandle>) -> Self {
         Self {
             events: Arc::new(RwLock::new(Vec::new())),
             app_handle,
@@ -49,9 +50,12 @@ impl TauriSubscriptionList {
     /// "kv_subscription:{uuid}"
     /// ```
     pub async fn emit_to_subscribers(&self, key: &str, value: &serde_json::Value) {
+        let Some(app_handle) = &self.app_handle else {
+            return;
+        };
         for event in self.events.read().await.iter() {
             if event.key == key {
-                if let Err(err) = self.app_handle.emit(&event.event_name, value) {
+                if let Err(err) = app_handle.emit(&event.event_name, value) {
                     println!("AppKVStore - TauriSubscriptionList: Error emitting to Tauri subscribers: {}",err);
                 }
             }
@@ -2,7 +2,10 @@ use std::sync::Arc;
 
 use tauri::State;
 
-use crate::tantivy_file_indexer::services::local_db::service::LocalDbService;
+use crate::{
+    shared::app_error::{AppError, AppResult},
+    tantivy_file_indexer::services::local_db::service::LocalDbService,
+};
 
 use super::models::frontend_subscription::FrontendKvSubscriptionModel;
 
@@ -11,8 +14,12 @@ pub async fn kv_store_set(
     key: String,
     value: serde_json::Value,
     local_db: State<'_,Arc<LocalDbService>>
-) -> Result<(), String> {
-    local_db.kv_store_table().set(key, value).await?;
+) -> AppResult<()> {
+    local_db
+        .kv_store_table()
+        .set(key, value)
+        .await
+        .map_err(AppError::DbError)?;
     Ok(())
 }
 
@@ -20,8 +27,12 @@ pub async fn kv_store_set(
 pub async fn kv_store_get(
     key: String,
     local_db: State<'_,Arc<LocalDbService>>
-) -> Result<Option<serde_json::Value>, String> {
-    let value = local_db.kv_store_table().get(&key).await?;
+) -> AppResult<Option<serde_json::Value>> {
+    let value = local_db
+        .kv_store_table()
+        .get(&key)
+        .await
+        .map_err(AppError::DbError)?;
     Ok(value)
 }
 
@@ -37,8 +48,12 @@ pub async fn kv_store_get(
 pub async fn kv_store_subscribe_to_key(
     key: String,
     local_db: State<'_,Arc<LocalDbService>>
-) -> Result<FrontendKvSubscriptionModel, String> {
-    let last_data: Option<serde_json::Value> = local_db.kv_store_table().get(&key).await?;
+) -> AppResult<FrontendKvSubscriptionModel> {
+    let last_data: Option<serde_json::Value> = local_db
+        .kv_store_table()
+        .get(&key)
+        .await
+        .map_err(AppError::DbError)?;
     let identifier = local_db.kv_store_table().tauri_subscribe_to_key(&key).await;
     Ok(FrontendKvSubscriptionModel {
         identifier,
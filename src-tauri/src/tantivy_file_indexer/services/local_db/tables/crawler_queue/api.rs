@@ -5,7 +5,10 @@ use sea_orm::{
     QuerySelect,
 };
 use sqlx::{Sqlite, Transaction};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 #[derive(Clone)]
 pub struct CrawlerQueueTable {
@@ -25,14 +28,15 @@ impl CrawlerQueueTable {
             self.db.get_sqlite_connection_pool().begin().await?;
 
         // Raw SQL is needed because SQLite is picky about on conflict operations
-        // Prepare raw SQL for upsert
+        // Prepare raw SQL for upsert. A directory that is already queued keeps the more urgent priority and the time it
+        // was first added at, so that pushing it again doesn't reset how long it has been waiting
         let query = r#"
             INSERT INTO crawler_queue (path, priority, taken, added_at)
             VALUES (?, ?, ?, ?)
             ON CONFLICT(path) DO UPDATE SET
-                priority = excluded.priority,
+                priority = MIN(priority, excluded.priority),
                 taken = excluded.taken,
-                added_at = excluded.added_at
+                added_at = MIN(added_at, excluded.added_at)
         "#;
 
         // Execute the query for each model
@@ -51,6 +55,17 @@ impl CrawlerQueueTable {
         Ok(())
     }
 
+    /// Completely removes the directories with the given paths from the database
+    ///
+    /// Returns the number of items that were deleted
+    pub async fn delete_paths(&self, paths: Vec<String>) -> Result<u64, sea_orm::DbErr> {
+        let result = indexed_dir::Entity::delete_many()
+            .filter(indexed_dir::Column::Path.is_in(paths))
+            .exec(&*self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
     /// Completely removes the given models from the database
     ///
     /// Returns the number of items that were deleted
@@ -65,11 +80,26 @@ impl CrawlerQueueTable {
         Ok(result.rows_affected)
     }
 
-    /// Retrieves the next most popular directories in the collection without removing them
-    pub async fn get_many(&self, amount: u64) -> Result<Vec<indexed_dir::Model>, sea_orm::DbErr> {
-        let next_entries = self.get_next_entries(amount).await?;
+    /// Retrieves the most urgent and the longest waiting directories that aren't taken, without removing them. Up to
+    /// `amount` of each are returned, for the scheduler to pick from
+    pub async fn get_candidates(
+        &self,
+        amount: u64,
+    ) -> Result<Vec<indexed_dir::Model>, sea_orm::DbErr> {
+        let mut candidates = self.get_next_entries(amount).await?;
+        let oldest = self.get_oldest_entries(amount).await?;
+
+        let paths: HashSet<String> = candidates.iter().map(|model| model.path.clone()).collect();
+        candidates.extend(
+            oldest
+                .into_iter()
+                .filter(|model| !paths.contains(&model.path)),
+        );
+        Ok(candidates)
+    }
 
-        Ok(next_entries)
+    pub async fn get_all(&self) -> Result<Vec<indexed_dir::Model>, sea_orm::DbErr> {
+        indexed_dir::Entity::find().all(&*self.db).await
     }
 
     /**
@@ -136,6 +166,19 @@ impl CrawlerQueueTable {
             .await
     }
 
+    /// Finds the models that have been waiting the longest and aren't taken
+    async fn get_oldest_entries(
+        &self,
+        amount: u64,
+    ) -> Result<Vec<indexed_dir::Model>, sea_orm::DbErr> {
+        indexed_dir::Entity::find()
+            .filter(indexed_dir::Column::Taken.eq(false))
+            .order_by_asc(indexed_dir::Column::AddedAt)
+            .limit(amount)
+            .all(&*self.db)
+            .await
+    }
+
     /// Example: passing in `is_taken` as true will set the `taken` field in all of the provided models to `true`
     pub async fn mark_taken(
         &self,
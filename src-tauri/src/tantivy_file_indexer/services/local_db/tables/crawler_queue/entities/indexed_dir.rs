@@ -7,7 +7,8 @@ use serde::Serialize;
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub path: String,
-    /// Where a higher number means that the directory is more important
+    /// Where a lower number means that the directory is more important. Entries become more important the longer
+    /// they wait, and `0` is reserved for directories that the user asked for
     pub priority: u32,
     /// If a directory is "taken", then it means that a crawler is already working on scanning its files and the
     /// other crawlers should not try to pick up this one.
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sqlx::{Sqlite, Transaction};
+
+use crate::tantivy_file_indexer::{
+    services::local_db::table_creator::generate_table_lenient, util::path::with_trailing_separator,
+};
+
+use super::entities::directory_state;
+
+pub struct DirectoryStatesTable {
+    db: Arc<DatabaseConnection>,
+}
+
+impl DirectoryStatesTable {
+    pub async fn new_async(db: Arc<DatabaseConnection>) -> Self {
+        generate_table_lenient(&db, directory_state::Entity).await;
+
+        Self { db }
+    }
+
+    pub async fn get(&self, path: &str) -> Result<Option<directory_state::Model>, sea_orm::DbErr> {
+        directory_state::Entity::find_by_id(path.to_string())
+            .one(&*self.db)
+            .await
+    }
+
+    pub async fn upsert_many(&self, models: &[directory_state::Model]) -> Result<(), sqlx::Error> {
+        let mut transaction: Transaction<'_, Sqlite> =
+            self.db.get_sqlite_connection_pool().begin().await?;
+
+        // Raw SQL is needed because SQLite is picky about on conflict operations
+        let query = r#"
+            INSERT INTO directory_states (path, modified_at, entries_hash, indexed_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(path) DO UPDATE SET
+                modified_at = excluded.modified_at,
+                entries_hash = excluded.entries_hash,
+                indexed_at = excluded.indexed_at;
+        "#;
+
+        for model in models {
+            sqlx::query(query)
+                .bind(&model.path)
+                .bind(model.modified_at)
+                .bind(model.entries_hash)
+                .bind(model.indexed_at)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Moves the states of `old_root` and of everything inside of it over to `new_root`, such as when a drive gets
+    /// mounted somewhere else. States that were already recorded at the new paths get replaced
+    pub async fn move_tree(&self, old_root: &str, new_root: &str) -> Result<u64, sqlx::Error> {
+        let prefix = with_trailing_separator(old_root);
+        // SQLite counts characters rather than bytes, and starts counting at 1
+        let result = sqlx::query(
+            r#"
+            UPDATE OR REPLACE directory_states
+            SET path = ? || substr(path, ?)
+            WHERE path = ? OR substr(path, 1, ?) = ?;
+        "#,
+        )
+        .bind(new_root)
+        .bind(old_root.chars().count() as i64 + 1)
+        .bind(old_root)
+        .bind(prefix.chars().count() as i64)
+        .bind(&prefix)
+        .execute(self.db.get_sqlite_connection_pool())
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Forgets the states of the directories, so that they get indexed on their next crawl
+    pub async fn delete_many(&self, paths: &[String]) -> Result<u64, sea_orm::DbErr> {
+        let result = directory_state::Entity::delete_many()
+            .filter(directory_state::Column::Path.is_in(paths.iter().cloned()))
+            .exec(&*self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Forgets every state, so that every directory gets indexed on its next crawl
+    pub async fn delete_all(&self) -> Result<u64, sea_orm::DbErr> {
+        let result = directory_state::Entity::delete_many()
+            .exec(&*self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
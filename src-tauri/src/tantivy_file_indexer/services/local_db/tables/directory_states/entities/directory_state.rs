@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+
+/// What a directory looked like the last time that its files were indexed, so that it can be skipped if it hasn't changed.
+/// Archives whose members have been indexed get one as well, under the path of the archive
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "directory_states")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub path: String,
+    /// The modified time of the directory itself, in UNIX milliseconds
+    pub modified_at: i64,
+    /// A hash of the names, sizes and modified times of the entries that got indexed. `None` if only the modified time
+    /// was compared
+    pub entries_hash: Option<i64>,
+    pub indexed_at: i64, // UNIX time
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No relations")
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::tantivy_file_indexer::{
+    dtos::index_root_dto::IndexRootDTO, services::local_db::table_creator::generate_table,
+    util::path::with_trailing_separator,
+};
+
+use super::entities::index_root;
+
+/// The priority that the default roots get pushed to the crawler queue with, which is after anything that the user asked for
+const DEFAULT_ROOT_PRIORITY: u32 = 8;
+
+#[derive(Clone)]
+pub struct IndexRootsTable {
+    db: Arc<DatabaseConnection>,
+}
+
+impl IndexRootsTable {
+    pub async fn new_async(db: Arc<DatabaseConnection>) -> Self {
+        let table = Self { db };
+        // The table only gets created once, which is when the default roots are added. After that, the user decides what gets indexed
+        if generate_table(&table.db, index_root::Entity).await.is_ok() {
+            table.add_default_roots().await;
+        }
+        table
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<index_root::Model>, sea_orm::DbErr> {
+        index_root::Entity::find()
+            .order_by_asc(index_root::Column::Priority)
+            .all(&*self.db)
+            .await
+    }
+
+    pub async fn get_enabled(&self) -> Result<Vec<index_root::Model>, sea_orm::DbErr> {
+        index_root::Entity::find()
+            .filter(index_root::Column::Enabled.eq(true))
+            .order_by_asc(index_root::Column::Priority)
+            .all(&*self.db)
+            .await
+    }
+
+    pub async fn get_by_path(
+        &self,
+        path: &str,
+    ) -> Result<Option<index_root::Model>, sea_orm::DbErr> {
+        index_root::Entity::find()
+            .filter(index_root::Column::Path.eq(path))
+            .one(&*self.db)
+            .await
+    }
+
+    pub async fn insert(&self, dto: IndexRootDTO) -> Result<index_root::Model, sea_orm::DbErr> {
+        let model = index_root::ActiveModel {
+            path: Set(dto.path),
+            enabled: Set(dto.enabled),
+            max_depth: Set(dto.max_depth),
+            priority: Set(dto.priority),
+            refresh_interval_minutes: Set(dto.refresh_interval_minutes),
+            follow_symlinks: Set(dto.follow_symlinks),
+            include_hidden: Set(dto.include_hidden),
+            last_crawled_at: Set(None),
+            ..Default::default()
+        };
+        model.insert(&*self.db).await
+    }
+
+    /// Returns `None` if there is no root with the id
+    pub async fn update(
+        &self,
+        id: i32,
+        dto: IndexRootDTO,
+    ) -> Result<Option<index_root::Model>, sea_orm::DbErr> {
+        let Some(model) = index_root::Entity::find_by_id(id).one(&*self.db).await? else {
+            return Ok(None);
+        };
+        let mut model: index_root::ActiveModel = model.into();
+        model.path = Set(dto.path);
+        model.enabled = Set(dto.enabled);
+        model.max_depth = Set(dto.max_depth);
+        model.priority = Set(dto.priority);
+        model.refresh_interval_minutes = Set(dto.refresh_interval_minutes);
+        model.follow_symlinks = Set(dto.follow_symlinks);
+        model.include_hidden = Set(dto.include_hidden);
+        model.update(&*self.db).await.map(Some)
+    }
+
+    /// Returns `false` if there was no root with the id
+    pub async fn delete(&self, id: i32) -> Result<bool, sea_orm::DbErr> {
+        let result = index_root::Entity::delete_by_id(id).exec(&*self.db).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn set_last_crawled_at(
+        &self,
+        ids: Vec<i32>,
+        time: DateTime<Utc>,
+    ) -> Result<(), sea_orm::DbErr> {
+        index_root::Entity::update_many()
+            .col_expr(
+                index_root::Column::LastCrawledAt,
+                sea_orm::prelude::Expr::value(time),
+            )
+            .filter(index_root::Column::Id.is_in(ids))
+            .exec(&*self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Moves the roots at or inside of `old_root` over to `new_root`, such as when a drive gets mounted somewhere else
+    pub async fn move_tree(&self, old_root: &str, new_root: &str) -> Result<u64, sqlx::Error> {
+        let prefix = with_trailing_separator(old_root);
+        // SQLite counts characters rather than bytes, and starts counting at 1
+        let result = sqlx::query(
+            r#"
+            UPDATE OR REPLACE index_roots
+            SET path = ? || substr(path, ?)
+            WHERE path = ? OR substr(path, 1, ?) = ?;
+        "#,
+        )
+        .bind(new_root)
+        .bind(old_root.chars().count() as i64 + 1)
+        .bind(old_root)
+        .bind(prefix.chars().count() as i64)
+        .bind(&prefix)
+        .execute(self.db.get_sqlite_connection_pool())
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Before index roots existed, every drive got crawled. The local drives are kept as the defaults, but drives that
+    /// can be unplugged or are on the network are left to the user
+    async fn add_default_roots(&self) {
+        let drives = system_info::drives::get_system_drives()
+            .into_iter()
+            .filter(|drive| !drive.is_removable && !drive.is_network);
+        for drive in drives {
+            let dto = IndexRootDTO {
+                path: drive.name,
+                enabled: true,
+                max_depth: None,
+                priority: DEFAULT_ROOT_PRIORITY,
+                refresh_interval_minutes: None,
+                follow_symlinks: false,
+                include_hidden: true,
+            };
+            if let Err(err) = self.insert(dto).await {
+                println!("IndexRootsTable: Error adding a default root: {}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// A directory that the user wants to have indexed, along with how it should be crawled
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "index_roots")]
+#[serde(rename_all = "PascalCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub path: String,
+    /// Disabled roots are kept around, but aren't crawled or watched
+    pub enabled: bool,
+    /// How many directories below the root get crawled. The root itself is at a depth of zero, and `None` means that
+    /// there is no limit
+    pub max_depth: Option<u32>,
+    /// The priority that the root is pushed to the crawler queue with, where a lower number gets crawled sooner
+    pub priority: u32,
+    /// How long to wait before crawling the root again. `None` means that the full refresh interval from the crawler
+    /// settings is used
+    pub refresh_interval_minutes: Option<u32>,
+    /// Whether the crawlers go into directories that are symbolic links
+    pub follow_symlinks: bool,
+    /// Whether hidden files and directories get indexed
+    pub include_hidden: bool,
+    /// The last time that the root was pushed to the crawler queue
+    pub last_crawled_at: Option<DateTimeUtc>,
+}
+
+impl Model {
+    /// Returns how many directories below the root the path is, or `None` if it isn't inside of the root
+    pub fn depth_of(&self, path: &Path) -> Option<usize> {
+        path.strip_prefix(&self.path)
+            .ok()
+            .map(|relative| relative.components().count())
+    }
+
+    /// Returns `true` if the directories inside of `dir_path` are still shallow enough to be crawled
+    pub fn can_descend(&self, dir_path: &Path) -> bool {
+        match (self.max_depth, self.depth_of(dir_path)) {
+            (Some(max_depth), Some(depth)) => depth < max_depth as usize,
+            _ => true,
+        }
+    }
+
+    /// Returns `true` if the crawl gets as far as `dir_path`, so that what is inside of it gets indexed
+    pub fn reaches(&self, dir_path: &Path) -> bool {
+        match (self.max_depth, self.depth_of(dir_path)) {
+            (Some(max_depth), Some(depth)) => depth <= max_depth as usize,
+            _ => true,
+        }
+    }
+
+    /// Roots without their own refresh interval use `default_interval_minutes`
+    pub fn is_due(&self, now: DateTimeUtc, default_interval_minutes: u32) -> bool {
+        let minutes = self
+            .refresh_interval_minutes
+            .unwrap_or(default_interval_minutes);
+        match self.last_crawled_at {
+            Some(last_crawled_at) => {
+                now - last_crawled_at >= chrono::Duration::minutes(minutes as i64)
+            }
+            None => true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No relations")
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use sea_orm::{DatabaseConnection, EntityTrait};
+use sqlx::{Sqlite, Transaction};
+
+use crate::tantivy_file_indexer::services::local_db::table_creator::generate_table_lenient;
+
+use super::entities::volume;
+
+#[derive(Clone)]
+pub struct VolumesTable {
+    db: Arc<DatabaseConnection>,
+}
+
+impl VolumesTable {
+    pub async fn new_async(db: Arc<DatabaseConnection>) -> Self {
+        generate_table_lenient(&db, volume::Entity).await;
+
+        Self { db }
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<volume::Model>, sea_orm::DbErr> {
+        volume::Entity::find().all(&*self.db).await
+    }
+
+    pub async fn upsert_many(&self, models: &[volume::Model]) -> Result<(), sqlx::Error> {
+        let mut transaction: Transaction<'_, Sqlite> =
+            self.db.get_sqlite_connection_pool().begin().await?;
+
+        // Raw SQL is needed because SQLite is picky about on conflict operations
+        let query = r#"
+            INSERT INTO volumes (id, label, mount_path, is_online, is_removable, is_network, last_seen_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                label = excluded.label,
+                mount_path = excluded.mount_path,
+                is_online = excluded.is_online,
+                is_removable = excluded.is_removable,
+                is_network = excluded.is_network,
+                last_seen_at = excluded.last_seen_at;
+        "#;
+
+        for model in models {
+            sqlx::query(query)
+                .bind(&model.id)
+                .bind(&model.label)
+                .bind(&model.mount_path)
+                .bind(model.is_online)
+                .bind(model.is_removable)
+                .bind(model.is_network)
+                .bind(model.last_seen_at)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// A drive or mounted file system that files got indexed on. It is recognized by its UUID or label, so that it is known
+/// to be the same volume even when it gets mounted somewhere else
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "volumes")]
+#[serde(rename_all = "PascalCase")]
+pub struct Model {
+    /// `uuid:` followed by the UUID, `label:` followed by the label for volumes without a UUID, or `mount:` followed by
+    /// the mount path for network shares that have neither
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub label: Option<String>,
+    /// Where the volume is mounted, or where it was last mounted if it is offline
+    pub mount_path: String,
+    /// Offline volumes are unplugged or unreachable. What was indexed on them is kept until they come back
+    pub is_online: bool,
+    pub is_removable: bool,
+    pub is_network: bool,
+    /// The last time that the volume was seen mounted
+    pub last_seen_at: DateTimeUtc,
+}
+
+impl Model {
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.mount_path)
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No relations")
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,7 +1,10 @@
 pub mod query_builder {
     pub mod constructor;
+    pub mod deadline;
     pub mod executor;
     pub mod organizer;
+    pub mod synonyms;
+    pub mod term_query;
 }
 pub mod ranker;
 pub mod querier;
@@ -1,4 +1,6 @@
-use super::query_builder::{constructor::QueryConstructor, executor, organizer};
+use super::query_builder::{
+    constructor::QueryConstructor, deadline::QueryDeadline, executor, organizer,
+};
 use std::{collections::HashSet, sync::Arc};
 
 use tantivy::{IndexReader, TantivyDocument};
@@ -24,19 +26,23 @@ impl Querier {
     /// Where `min_results` indicated how many documents should initially be fetched, and max_results is the value to work up to
     ///
     /// `step_size`: the number of events that will get emitted in total
+    ///
+    /// Stops emitting once `deadline` passes or gets cancelled
     pub async fn advanced_query_streamed<EmitFn>(
         &self,
         search_params: SearchParamsDTO,
         emit: EmitFn,
         step_size: usize,
         min_results: usize,
+        deadline: &QueryDeadline,
     ) -> tantivy::Result<()> where
         EmitFn: Fn(Vec<TantivyFileModel>),
     {
         let searcher = self.reader.searcher();
         let query = self
             .constructor
-            .construct_query(&search_params)?;
+            .construct_query(&search_params)
+            .await?;
 
         let max_results = search_params.num_results as usize;
 
@@ -45,7 +51,10 @@ impl Querier {
         let mut prev_ids: HashSet<String> = HashSet::new();
 
         for _ in 0..step_size {
-            match executor::execute_query(&searcher, current_out_amt, &query) {
+            if deadline.should_stop() {
+                break;
+            }
+            match executor::execute_query(&searcher, current_out_amt, &query, deadline) {
                 Ok(top_docs) => {
                     let mut output_docs: Vec<TantivyFileModel> = Vec::new();
                     for (_score, address) in top_docs {
@@ -80,20 +89,25 @@ impl Querier {
     /// this function slightly differs from `advanced_query_streamed` in the fact that the emit function will emit all of the organized files
     /// that get accumulated, meaning that the frontend needs to REPLACE its list of files with whatever gets emitted, as opposed to appending the
     /// emitted result.
+    ///
+    /// Returns everything that was accumulated, in the same order as the last emit. If `deadline` passes or gets cancelled
+    /// partway through, that is whatever had been accumulated up until then. The second value is whether every step ran
+    /// without failing, since the accumulated results are incomplete otherwise.
+    ///
+    /// Fails if the query could not be constructed
     pub async fn organized_query_streamed<EmitFn>(
         &self,
         search_params: SearchParamsDTO,
         emit: EmitFn,
         step_size: usize,
         min_results: usize,
-    ) where
+        deadline: &QueryDeadline,
+    ) -> tantivy::Result<(Vec<TantivyFileModel>, bool)>
+    where
         EmitFn: Fn(&[TantivyFileModel]),
     {
         let searcher = self.reader.searcher();
-        let query = self
-            .constructor
-            .construct_query(&search_params)
-            .expect("Query could not be constructed");
+        let query = self.constructor.construct_query(&search_params).await?;
 
         let max_results = search_params.num_results as usize;
 
@@ -110,9 +124,13 @@ impl Querier {
         let mut seen_keys = HashSet::new();
 
         let mut accumulated_docs: Vec<TantivyFileModel> = Vec::new();
+        let mut completed = true;
 
         for _ in 0..step_size {
-            match executor::execute_query(&searcher, current_out_amt, &query) {
+            if deadline.should_stop() {
+                break;
+            }
+            match executor::execute_query(&searcher, current_out_amt, &query, deadline) {
                 Ok(top_docs) => {
                     for (_score, address) in top_docs {
                         if let Ok(doc) = searcher.doc(address) {
@@ -136,10 +154,12 @@ impl Querier {
                 }
                 Err(err) => {
                     eprintln!("Error executing query: {}", err);
+                    completed = false;
                     break; // Exit loop on error
                 }
             }
         }
+        Ok((accumulated_docs, completed))
     }
 
  
@@ -148,28 +168,28 @@ impl Querier {
         organizer::sort_by_score(docs);
     }
 
-    pub fn advanced_query(
+    pub async fn advanced_query(
         &self,
         search_params: &SearchParamsDTO,
+        deadline: &QueryDeadline,
     ) -> tantivy::Result<Vec<TantivyFileModel>> {
         let searcher = self.reader.searcher();
-        let query = self
-            .constructor
-            .construct_query(search_params)
-            .expect("Query could not be constructed");
+        let query = self.constructor.construct_query(search_params).await?;
 
         // Execute the query and collect the results
-        let top_docs =
-            executor::execute_query(&searcher, search_params.num_results as usize, &query)?;
-
-        let results: Vec<TantivyFileModel> = top_docs
+        let top_docs = executor::execute_query(
+            &searcher,
+            search_params.num_results as usize,
+            &query,
+            deadline,
+        )?;
+
+        top_docs
             .into_iter()
             .map(|(_score, doc_address)| {
-                let doc: TantivyDocument = searcher.doc(doc_address).unwrap();
-                TantivyFileModel::from_document(doc, _score as f32)
+                let doc: TantivyDocument = searcher.doc(doc_address)?;
+                Ok(TantivyFileModel::from_document(doc, _score as f32))
             })
-            .collect();
-
-        Ok(results)
+            .collect()
     }
 }
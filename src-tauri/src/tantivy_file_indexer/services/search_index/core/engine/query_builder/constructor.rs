@@ -1,54 +1,114 @@
+use std::sync::Arc;
+
 use tantivy::{
-    query::{BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, RangeQuery},
-    schema::Schema,
+    query::{BooleanQuery, FuzzyTermQuery, Occur, Query, RangeQuery, TermQuery},
+    schema::{IndexRecordOption, Schema},
     DateTime, IndexReader, TantivyError, Term,
 };
 
 use crate::tantivy_file_indexer::{
     dtos::search_params_dto::{DateRange, SearchParamsDTO},
     enums::search_query_type::SearchQueryType,
-    services::search_index::models::file::TantivyFileModel,
+    services::{
+        local_db::tables::app_kv_store::api::AppKvStoreTable,
+        search_index::models::file::TantivyFileModel, volumes::service::VolumeService,
+    },
+};
+
+use super::{
+    synonyms::{self, SynonymGroupModel},
+    term_query,
 };
 
+/// Shorthands that can be used in place of a field name in a query, such as `sym:CrawlerQueue`
+const FIELD_ALIASES: [(&str, &str); 1] = [("sym:", "symbols:")];
+/// Whether the files on volumes that aren't connected are left out of the results. Otherwise they are shown, and the
+/// frontend marks them as offline
+const HIDE_OFFLINE_VOLUMES_KEY: &str = "searchHideOfflineVolumes";
+
 pub struct QueryConstructor {
     schema: Schema,
     reader: IndexReader,
+    /// Where the synonym groups are stored
+    kv_store: AppKvStoreTable,
+    volumes: Arc<VolumeService>,
 }
 
 impl QueryConstructor {
-    pub fn new(schema: Schema, reader: IndexReader) -> Self {
-        Self { schema, reader }
+    pub fn new(
+        schema: Schema,
+        reader: IndexReader,
+        kv_store: AppKvStoreTable,
+        volumes: Arc<VolumeService>,
+    ) -> Self {
+        Self {
+            schema,
+            reader,
+            kv_store,
+            volumes,
+        }
+    }
+
+    /// The volumes whose files get left out of the results, which are the offline ones if the user chose to hide them
+    pub async fn hidden_volume_ids(&self) -> Vec<String> {
+        match self
+            .kv_store
+            .get_or_create_default::<bool>(HIDE_OFFLINE_VOLUMES_KEY)
+            .await
+        {
+            Ok(true) => self.volumes.offline_ids(),
+            Ok(false) => Vec::new(),
+            Err(err) => {
+                println!("QueryConstructor: could not read whether to hide offline volumes: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    /// The synonym groups that the terms of a query get expanded with
+    pub async fn synonym_groups(&self) -> Vec<SynonymGroupModel> {
+        match SynonymGroupModel::get_from_db(&self.kv_store).await {
+            Ok(groups) => groups,
+            Err(err) => {
+                println!("QueryConstructor: could not read the synonym groups: {}", err);
+                Vec::new()
+            }
+        }
     }
 
     /// Construct a query according to the query type specified in the parameters
-    pub fn construct_query(
+    pub async fn construct_query(
         &self,
         search_params: &SearchParamsDTO,
     ) -> tantivy::Result<Box<dyn Query>> {
-        match search_params.query_type {
-            SearchQueryType::Term => self
-                .construct_standard_query(search_params)
-                .map(|x| Box::new(BooleanQuery::new(x)) as Box<dyn Query>),
-            SearchQueryType::Fuzzy => self.construct_fuzzy_query(search_params),
+        let synonym_groups = self.synonym_groups().await;
+        let query: Box<dyn Query> = match search_params.query_type {
+            SearchQueryType::Term => Box::new(BooleanQuery::new(
+                self.construct_standard_query(search_params, &synonym_groups)?,
+            )),
+            SearchQueryType::Fuzzy => self.construct_fuzzy_query(search_params)?,
             SearchQueryType::Hybrid => {
-                let mut terms = self.construct_standard_query(search_params)?;
+                let mut terms = self.construct_standard_query(search_params, &synonym_groups)?;
                 let fuzzy = self.construct_fuzzy_query(search_params)?;
                 terms.push((Occur::Should, fuzzy));
-                Ok(Box::new(BooleanQuery::new(terms)))
+                Box::new(BooleanQuery::new(terms))
             }
-        }
+        };
+        Ok(exclude_volumes(query, &self.hidden_volume_ids().await))
     }
 
     /// Construct a query to retrieve files, based off of the files' schema
     fn construct_standard_query(
         &self,
         search_params: &SearchParamsDTO,
+        synonym_groups: &[SynonymGroupModel],
     ) -> tantivy::Result<Vec<(Occur, Box<dyn Query>)>> {
         let mut queries: Vec<(Occur, Box<dyn Query>)> = Vec::new();
 
         if let Some(file_path) = &search_params.file_path {
             let field_name: String = TantivyFileModel::file_path_field().into();
-            let query = self.create_standard_query(&field_name, file_path, Occur::Should)?;
+            let file_path = synonyms::expand_synonyms(file_path, synonym_groups);
+            let query = self.create_standard_query(&field_name, &file_path, Occur::Should)?;
             queries.push(query);
         }
 
@@ -87,6 +147,7 @@ impl QueryConstructor {
         Ok(Box::new(BooleanQuery::new(queries)))
     }
 
+    /// See `term_query` for the supported syntax
     fn create_standard_query(
         &self,
         field_name: &str,
@@ -94,11 +155,15 @@ impl QueryConstructor {
         occur: Occur,
     ) -> Result<(Occur, Box<dyn Query>), TantivyError> {
         let field = self.schema.get_field(field_name)?;
-        let mut query_parser = QueryParser::for_index(self.reader.searcher().index(), vec![field]);
-        query_parser.set_conjunction_by_default();
-        let query = query_parser.parse_query(query)?;
+        let path_string_field: String = TantivyFileModel::file_path_string_field().into();
+        let query = term_query::parse_term_query(
+            self.reader.searcher().index(),
+            field,
+            self.schema.get_field(&path_string_field)?,
+            &expand_field_aliases(query),
+        )?;
 
-        Ok((occur, Box::new(query)))
+        Ok((occur, query))
     }
 
     fn create_date_query(
@@ -113,3 +178,45 @@ impl QueryConstructor {
         (occur, Box::new(query))
     }
 }
+
+/// Leaves out the files on the volumes, by their ids
+fn exclude_volumes(query: Box<dyn Query>, volume_ids: &[String]) -> Box<dyn Query> {
+    if volume_ids.is_empty() {
+        return query;
+    }
+    let mut clauses = vec![(Occur::Must, query)];
+    for volume_id in volume_ids {
+        let term = TantivyFileModel::volume_id_field().term(volume_id.clone());
+        clauses.push((
+            Occur::MustNot,
+            Box::new(TermQuery::new(term, IndexRecordOption::Basic)) as Box<dyn Query>,
+        ));
+    }
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// Replaces every aliased field name in the query with the real field name, given that it starts a clause
+fn expand_field_aliases(query: &str) -> String {
+    let mut expanded = String::with_capacity(query.len());
+    let mut rest = query;
+    while let Some(c) = rest.chars().next() {
+        let at_clause_start = expanded
+            .chars()
+            .last()
+            .is_none_or(|c| c.is_whitespace() || matches!(c, '(' | '-' | '+'));
+        let alias = FIELD_ALIASES
+            .iter()
+            .find(|(alias, _)| at_clause_start && rest.starts_with(alias));
+        match alias {
+            Some((alias, field)) => {
+                expanded.push_str(field);
+                rest = &rest[alias.len()..];
+            }
+            None => {
+                expanded.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    expanded
+}
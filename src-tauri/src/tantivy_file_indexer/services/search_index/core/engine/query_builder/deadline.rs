@@ -0,0 +1,268 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use tantivy::{
+    collector::{Collector, SegmentCollector},
+    query::Weight,
+    DocSet, SegmentOrdinal, SegmentReader, TERMINATED,
+};
+
+/// How many documents get collected between each check of the clock
+const DOCS_PER_CHECK: u32 = 256;
+
+/// Decides when a query has to stop, either because it ran out of time or because it was cancelled.
+///
+/// Share it with the task running the query and call `cancel` from elsewhere to stop the query early
+pub struct QueryDeadline {
+    deadline: Instant,
+    cancelled: AtomicBool,
+    /// Set once a query actually got cut short, meaning that its results are partial
+    interrupted: AtomicBool,
+}
+
+impl QueryDeadline {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + budget,
+            cancelled: AtomicBool::new(false),
+            interrupted: AtomicBool::new(false),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if the query should stop, and marks the results as partial if so
+    pub fn should_stop(&self) -> bool {
+        let stop = self.is_over();
+        if stop {
+            self.mark_interrupted();
+        }
+        stop
+    }
+
+    /// Like `should_stop`, but leaves it to the caller to mark the results as partial, for when stopping might not
+    /// leave anything out
+    fn is_over(&self) -> bool {
+        self.is_cancelled() || Instant::now() >= self.deadline
+    }
+
+    fn mark_interrupted(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether a query had to stop before it could look at every matching document
+    pub fn was_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+}
+
+/// Wraps another collector, and stops feeding it documents once the deadline passes.
+/// Whatever the inner collector has gathered by then is returned as usual
+pub struct DeadlineCollector<'a, C: Collector> {
+    inner: C,
+    deadline: &'a QueryDeadline,
+}
+
+impl<'a, C: Collector> DeadlineCollector<'a, C> {
+    pub fn new(inner: C, deadline: &'a QueryDeadline) -> Self {
+        Self { inner, deadline }
+    }
+}
+
+impl<C: Collector> Collector for DeadlineCollector<'_, C> {
+    type Fruit = C::Fruit;
+    type Child = C::Child;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        self.inner.for_segment(segment_local_id, segment)
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.inner.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        self.inner.merge_fruits(segment_fruits)
+    }
+
+    /// Walks the scorer by hand instead of using `Weight::for_each`, since that can't be stopped partway through.
+    ///
+    /// Building the scorer can't be interrupted, and takes a while for queries that have to go through the whole term
+    /// dictionary, such as regexes. So the deadline gets checked right before and right after it. The results only get
+    /// marked as partial if there were documents left that didn't get looked at
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+    ) -> tantivy::Result<<Self::Child as SegmentCollector>::Fruit> {
+        let mut segment_collector = self.for_segment(segment_ord, reader)?;
+        if self.deadline.is_over() {
+            // Whether any of them would have matched can't be known without the scorer
+            if reader.num_docs() > 0 {
+                self.deadline.mark_interrupted();
+            }
+            return Ok(segment_collector.harvest());
+        }
+
+        let alive_bitset = reader.alive_bitset();
+        let requires_scoring = self.requires_scoring();
+        let mut scorer = weight.scorer(reader, 1.0)?;
+        let mut doc = scorer.doc();
+        let mut until_check = DOCS_PER_CHECK;
+        let mut stop = self.deadline.is_over();
+
+        while doc != TERMINATED {
+            if stop {
+                self.deadline.mark_interrupted();
+                break;
+            }
+            if alive_bitset.is_none_or(|bitset| bitset.is_alive(doc)) {
+                let score = if requires_scoring {
+                    scorer.score()
+                } else {
+                    0.0
+                };
+                segment_collector.collect(doc, score);
+            }
+            until_check -= 1;
+            if until_check == 0 {
+                stop = self.deadline.is_over();
+                until_check = DOCS_PER_CHECK;
+            }
+            doc = scorer.advance();
+        }
+        Ok(segment_collector.harvest())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tantivy::{doc, query::AllQuery, schema::Schema, DocId, Index, Score};
+
+    use super::*;
+
+    /// Counts the documents, and cancels the query once it has seen `cancel_after` of them
+    struct CancellingCount {
+        deadline: Arc<QueryDeadline>,
+        cancel_after: usize,
+    }
+
+    struct CancellingSegmentCount {
+        deadline: Arc<QueryDeadline>,
+        cancel_after: usize,
+        count: usize,
+    }
+
+    impl Collector for CancellingCount {
+        type Fruit = usize;
+        type Child = CancellingSegmentCount;
+
+        fn for_segment(
+            &self,
+            _segment_local_id: SegmentOrdinal,
+            _segment: &SegmentReader,
+        ) -> tantivy::Result<Self::Child> {
+            Ok(CancellingSegmentCount {
+                deadline: Arc::clone(&self.deadline),
+                cancel_after: self.cancel_after,
+                count: 0,
+            })
+        }
+
+        fn requires_scoring(&self) -> bool {
+            false
+        }
+
+        fn merge_fruits(&self, segment_fruits: Vec<usize>) -> tantivy::Result<usize> {
+            Ok(segment_fruits.into_iter().sum())
+        }
+    }
+
+    impl SegmentCollector for CancellingSegmentCount {
+        type Fruit = usize;
+
+        fn collect(&mut self, _doc: DocId, _score: Score) {
+            self.count += 1;
+            if self.count == self.cancel_after {
+                self.deadline.cancel();
+            }
+        }
+
+        fn harvest(self) -> usize {
+            self.count
+        }
+    }
+
+    /// Counts the documents of a single segment that holds `num_docs` of them, cancelling after `cancel_after`
+    fn count(num_docs: usize, cancel_after: usize) -> (usize, bool) {
+        let index = Index::create_in_ram(Schema::builder().build());
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for _ in 0..num_docs {
+            writer.add_document(doc!()).unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let deadline = Arc::new(QueryDeadline::new(Duration::from_secs(60)));
+        let collector = CancellingCount {
+            deadline: Arc::clone(&deadline),
+            cancel_after,
+        };
+        let count = searcher
+            .search(&AllQuery, &DeadlineCollector::new(collector, &deadline))
+            .unwrap();
+        (count, deadline.was_interrupted())
+    }
+
+    #[test]
+    fn stopping_with_documents_left_is_partial() {
+        let num_docs = DOCS_PER_CHECK as usize + 10;
+        assert_eq!(
+            count(num_docs, DOCS_PER_CHECK as usize),
+            (DOCS_PER_CHECK as usize, true)
+        );
+    }
+
+    #[test]
+    fn stopping_after_the_last_document_is_not_partial() {
+        let num_docs = DOCS_PER_CHECK as usize;
+        assert_eq!(count(num_docs, num_docs), (num_docs, false));
+    }
+
+    #[test]
+    fn running_out_of_time_before_the_segment_is_partial() {
+        let index = Index::create_in_ram(Schema::builder().build());
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        writer.add_document(doc!()).unwrap();
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let deadline = QueryDeadline::new(Duration::ZERO);
+        let count = searcher
+            .search(
+                &AllQuery,
+                &DeadlineCollector::new(tantivy::collector::Count, &deadline),
+            )
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(deadline.was_interrupted());
+    }
+}
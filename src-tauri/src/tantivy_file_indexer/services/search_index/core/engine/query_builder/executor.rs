@@ -1,17 +1,21 @@
 use tantivy::{collector::TopDocs, query::Query, Searcher};
 
-/// Execute a standard query, applying a popularity bias to the results
+use super::deadline::{DeadlineCollector, QueryDeadline};
+
+/// Execute a standard query, applying a popularity bias to the results.
+///
+/// If the deadline passes, the best results found up until then are returned
 pub fn execute_query<Q>(
     searcher: &Searcher,
     num_results: usize,
     query: &Q,
+    deadline: &QueryDeadline,
 ) -> tantivy::Result<Vec<(f64, tantivy::DocAddress)>>
 where
     Q: Query + Sized,
 {
-    searcher.search(
-        query,
-        &TopDocs::with_limit(num_results).tweak_score(|segment_reader: &tantivy::SegmentReader| {
+    let collector =
+        TopDocs::with_limit(num_results).tweak_score(|segment_reader: &tantivy::SegmentReader| {
             let popularity_field = segment_reader
                 .fast_fields()
                 .f64("popularity")
@@ -21,8 +25,8 @@ where
                 let pop_score = popularity_field.first(doc).unwrap_or(1.0);
                 apply_popularity(original_score, pop_score)
             }
-        }),
-    )
+        });
+    searcher.search(query, &DeadlineCollector::new(collector, deadline))
 }
 
 fn apply_popularity(existing_score: f32, popularity_score: f64) -> f64 {
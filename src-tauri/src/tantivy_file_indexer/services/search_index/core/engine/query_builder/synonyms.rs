@@ -0,0 +1,201 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tantivy_file_indexer::services::local_db::tables::app_kv_store::api::AppKvStoreTable;
+
+const KV_STORE_NAME: &str = "searchSynonyms";
+
+/// How much a synonym counts for compared to the term that was actually typed in
+const SYNONYM_BOOST: f32 = 0.5;
+
+/// A set of terms that get searched for together. This is to be stored in the KV table
+#[derive(Serialize, Deserialize, Clone, Debug, Hash)]
+#[serde(rename_all = "PascalCase")]
+pub struct SynonymGroupModel {
+    /// Every term expands into all of the other terms. Example: "invoice" and "bill"
+    pub terms: Vec<String>,
+    /// When set, only the first term expands into the others. This is meant for category aliases such as "pics" expanding into
+    /// jpg, png and heic, where searching for "jpg" shouldn't bring up every other kind of image as well
+    #[serde(default)]
+    pub one_way: bool,
+}
+
+impl SynonymGroupModel {
+    pub async fn get_from_db(kv: &AppKvStoreTable) -> Result<Vec<Self>, String> {
+        kv.get_or_create(KV_STORE_NAME, Self::defaults()).await
+    }
+
+    fn defaults() -> Vec<Self> {
+        let group = |terms: &[&str], one_way: bool| Self {
+            terms: terms.iter().map(|term| term.to_string()).collect(),
+            one_way,
+        };
+        vec![
+            group(&["pics", "jpg", "jpeg", "png", "heic", "gif", "webp"], true),
+            group(&["docs", "pdf", "docx", "doc", "odt", "txt"], true),
+            group(&["invoice", "bill"], false),
+        ]
+    }
+}
+
+/// Changes whenever the groups do, so that results that were cached with other synonyms don't get reused
+pub fn version(groups: &[SynonymGroupModel]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    groups.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Rewrites every plain term of the query that has synonyms into an OR clause, where the synonyms are boosted lower than the
+/// term itself. Example: `invoice 2024` becomes `(invoice OR "bill"^0.5) 2024`.
+///
+/// Anything that isn't a plain term, such as phrases, field queries and operators, is left alone
+pub fn expand_synonyms(query: &str, groups: &[SynonymGroupModel]) -> String {
+    let expansions = build_expansions(groups);
+    if expansions.is_empty() {
+        return query.to_string();
+    }
+
+    let mut in_phrase = false;
+    let mut words: Vec<String> = Vec::new();
+    for word in query.split_whitespace() {
+        let is_plain = !in_phrase && word.chars().all(char::is_alphanumeric);
+        if word.matches('"').count() % 2 == 1 {
+            in_phrase = !in_phrase;
+        }
+        let synonyms = is_plain
+            .then(|| expansions.get(&word.to_lowercase()))
+            .flatten();
+        match synonyms {
+            Some(synonyms) => {
+                let clauses: Vec<String> = synonyms
+                    .iter()
+                    .map(|synonym| format!("\"{}\"^{}", synonym, SYNONYM_BOOST))
+                    .collect();
+                words.push(format!("({} OR {})", word, clauses.join(" OR ")));
+            }
+            None => words.push(word.to_string()),
+        }
+    }
+    words.join(" ")
+}
+
+/// Maps each lowercased term to the terms that it expands into
+fn build_expansions(groups: &[SynonymGroupModel]) -> HashMap<String, Vec<String>> {
+    let mut expansions: HashMap<String, Vec<String>> = HashMap::new();
+    for group in groups {
+        // Quotes would break out of the phrase that each synonym gets wrapped in
+        let terms: Vec<String> = group
+            .terms
+            .iter()
+            .map(|term| term.replace('"', "").trim().to_lowercase())
+            .filter(|term| !term.is_empty())
+            .collect();
+        let sources = if group.one_way {
+            &terms[..terms.len().min(1)]
+        } else {
+            &terms[..]
+        };
+        for source in sources {
+            let entry = expansions.entry(source.clone()).or_default();
+            for term in terms.iter().filter(|term| *term != source) {
+                if !entry.contains(term) {
+                    entry.push(term.clone());
+                }
+            }
+        }
+    }
+    expansions.retain(|_, synonyms| !synonyms.is_empty());
+    expansions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(terms: &[&str], one_way: bool) -> SynonymGroupModel {
+        SynonymGroupModel {
+            terms: terms.iter().map(|term| term.to_string()).collect(),
+            one_way,
+        }
+    }
+
+    fn groups() -> Vec<SynonymGroupModel> {
+        vec![
+            group(&["invoice", "bill"], false),
+            group(&["pics", "jpg", "png"], true),
+        ]
+    }
+
+    #[test]
+    fn two_way_groups_expand_every_term() {
+        let expansions = build_expansions(&groups());
+        assert_eq!(expansions["invoice"], vec!["bill"]);
+        assert_eq!(expansions["bill"], vec!["invoice"]);
+    }
+
+    #[test]
+    fn one_way_groups_only_expand_the_first_term() {
+        let expansions = build_expansions(&groups());
+        assert_eq!(expansions["pics"], vec!["jpg", "png"]);
+        assert!(!expansions.contains_key("jpg"));
+        assert!(!expansions.contains_key("png"));
+    }
+
+    #[test]
+    fn terms_are_cleaned_up() {
+        let expansions = build_expansions(&[
+            group(&["Invoice", "\"bill\"", " ", "BILL"], false),
+            group(&["alone"], false),
+        ]);
+        assert_eq!(expansions["invoice"], vec!["bill"]);
+        assert_eq!(expansions["bill"], vec!["invoice"]);
+        // A term without any synonyms has nothing to expand into
+        assert!(!expansions.contains_key("alone"));
+        assert!(!expansions.contains_key(""));
+    }
+
+    #[test]
+    fn plain_terms_get_expanded() {
+        assert_eq!(
+            expand_synonyms("Invoice 2024", &groups()),
+            "(Invoice OR \"bill\"^0.5) 2024"
+        );
+        assert_eq!(
+            expand_synonyms("pics", &groups()),
+            "(pics OR \"jpg\"^0.5 OR \"png\"^0.5)"
+        );
+        assert_eq!(expand_synonyms("jpg", &groups()), "jpg");
+    }
+
+    #[test]
+    fn phrases_and_field_queries_are_left_alone() {
+        assert_eq!(
+            expand_synonyms("\"invoice draft\" invoice", &groups()),
+            "\"invoice draft\" (invoice OR \"bill\"^0.5)"
+        );
+        assert_eq!(
+            expand_synonyms("title:invoice -bill*", &groups()),
+            "title:invoice -bill*"
+        );
+    }
+
+    #[test]
+    fn queries_are_untouched_without_synonyms() {
+        assert_eq!(expand_synonyms("invoice  2024", &[]), "invoice  2024");
+    }
+
+    #[test]
+    fn the_version_follows_the_groups() {
+        let mut changed = groups();
+        assert_eq!(version(&groups()), version(&changed));
+        changed[0].terms.push("receipt".to_string());
+        assert_ne!(version(&groups()), version(&changed));
+        changed[0].terms.pop();
+        changed[0].one_way = true;
+        assert_ne!(version(&groups()), version(&changed));
+    }
+}
@@ -0,0 +1,275 @@
+//! The syntax of a Term query. Terms are matched against the tokenized file path unless a field is given.
+//!
+//! | Query                   | Matches                                                                  |
+//! |-------------------------|--------------------------------------------------------------------------|
+//! | `budget 2024`           | Paths that contain both terms                                            |
+//! | `budget OR invoice`     | Paths that contain either term                                           |
+//! | `budget -draft`         | Paths that contain `budget` but not `draft`                              |
+//! | `"annual report"`       | Paths that contain the words next to each other, in that order           |
+//! | `(budget OR bill) 2024` | Clauses can be grouped with parentheses                                  |
+//! | `-in:archive`           | Leaves out everything inside of any directory called `archive`           |
+//! | `-in:"C:\Old Stuff"`    | Leaves out everything inside of that exact directory                     |
+//! | `title:budget`          | Matches against another field, such as the title of a document           |
+//!
+//! The frontend queries on every keystroke, so a query that is still being typed, such as `"annual rep` or `budget OR`,
+//! gets completed or trimmed instead of being rejected
+use tantivy::{
+    query::{AllQuery, BooleanQuery, Occur, Query, QueryParser, RegexQuery},
+    schema::Field,
+    Index,
+};
+
+use crate::tantivy_file_indexer::util::string::escape_regex;
+
+/// Starts a clause that excludes a directory
+const EXCLUDED_DIR_PREFIX: &str = "-in:";
+
+/// Operators that need something after them to mean anything
+const DANGLING_OPERATORS: [&str; 4] = ["OR", "AND", "-", "+"];
+
+/// Parses `query` into a tantivy query. `field` is what terms get matched against when no field is given, and
+/// `path_string_field` is the untokenized path that excluded directories get matched against
+pub fn parse_term_query(
+    index: &Index,
+    field: Field,
+    path_string_field: Field,
+    query: &str,
+) -> tantivy::Result<Box<dyn Query>> {
+    let (query, excluded_dirs) = split_excluded_dirs(query);
+    let query = complete_partial_query(&query);
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    if query.is_empty() {
+        // A query that only excludes things should match everything else
+        clauses.push((Occur::Must, Box::new(AllQuery)));
+    } else {
+        let mut parser = QueryParser::for_index(index, vec![field]);
+        parser.set_conjunction_by_default();
+        // Whatever can't be parsed is left out, and the rest of the query is still used
+        let (parsed, _errors) = parser.parse_query_lenient(&query);
+        clauses.push((Occur::Must, parsed));
+    }
+    for dir in excluded_dirs {
+        let pattern = excluded_dir_pattern(&dir);
+        let regex = RegexQuery::from_pattern(&pattern, path_string_field)?;
+        clauses.push((Occur::MustNot, Box::new(regex)));
+    }
+
+    Ok(Box::new(BooleanQuery::new(clauses)))
+}
+
+/// Pulls the `-in:` clauses out of the query. Returns the rest of the query and the directories to exclude
+fn split_excluded_dirs(query: &str) -> (String, Vec<String>) {
+    let mut rest = String::with_capacity(query.len());
+    let mut dirs = Vec::new();
+    for token in tokenize(query) {
+        match token.strip_prefix(EXCLUDED_DIR_PREFIX) {
+            Some(dir) => {
+                let dir = dir.trim_matches('"');
+                if !dir.is_empty() {
+                    dirs.push(dir.to_string());
+                }
+            }
+            None => {
+                if !rest.is_empty() {
+                    rest.push(' ');
+                }
+                rest.push_str(token);
+            }
+        }
+    }
+    (rest, dirs)
+}
+
+/// Splits on whitespace, except for whitespace inside of quotes
+fn tokenize(query: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut in_quotes = false;
+    let mut start: Option<usize> = None;
+    for (i, c) in query.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+        if c.is_whitespace() && !in_quotes {
+            if let Some(token_start) = start.take() {
+                tokens.push(&query[token_start..i]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(token_start) = start {
+        tokens.push(&query[token_start..]);
+    }
+    tokens
+}
+
+/// Closes unterminated quotes and parentheses, and removes operators that are missing their right hand side
+fn complete_partial_query(query: &str) -> String {
+    let mut completed = String::with_capacity(query.len() + 2);
+    let mut in_quotes = false;
+    let mut depth = 0usize;
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes => {
+                // A closing parenthesis without an opening one is dropped
+                if depth == 0 {
+                    continue;
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+        completed.push(c);
+    }
+    if in_quotes {
+        completed.push('"');
+    }
+
+    let mut trimmed = completed.trim_end();
+    while let Some(operator) = DANGLING_OPERATORS
+        .iter()
+        .find(|operator| trimmed == **operator || trimmed.ends_with(&format!(" {}", operator)))
+    {
+        trimmed = trimmed[..trimmed.len() - operator.len()].trim_end();
+    }
+    // An opening parenthesis at the very end doesn't group anything yet
+    while let Some(stripped) = trimmed.strip_suffix('(') {
+        trimmed = stripped.trim_end();
+        depth = depth.saturating_sub(1);
+    }
+
+    let mut completed = trimmed.to_string();
+    completed.push_str(&")".repeat(depth));
+    completed
+}
+
+/// A bare name excludes every directory with that name, while a path only excludes that directory. Paths are compared
+/// case-insensitively, and either kind of slash is accepted
+fn excluded_dir_pattern(dir: &str) -> String {
+    let is_path = dir.contains(['/', '\\']);
+    let dir = dir.trim_end_matches(['/', '\\']);
+    let escaped: String = dir
+        .split(['/', '\\'])
+        .map(escape_regex)
+        .collect::<Vec<_>>()
+        .join(r"[\\/]");
+    if is_path {
+        format!(r"(?i){}[\\/].*", escaped)
+    } else {
+        format!(r"(?i).*[\\/]{}[\\/].*", escaped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::{collector::Count, schema::Schema, Index, TantivyDocument};
+    use tantivy_ext::SearchIndex;
+
+    use crate::tantivy_file_indexer::services::search_index::models::file::TantivyFileModel;
+
+    use super::*;
+
+    const PATHS: [&str; 6] = [
+        r"C:\Docs\Budget 2024.xlsx",
+        r"C:\Docs\Budget draft.docx",
+        r"C:\Docs\Archive\Budget 2019.xlsx",
+        r"C:\Docs\Annual Budget Report.pdf",
+        r"C:\Docs\Invoice 2024.pdf",
+        r"D:\Old Stuff\Budget.pdf",
+    ];
+
+    fn schema() -> Schema {
+        SearchIndex::<TantivyFileModel>::schema().clone()
+    }
+
+    fn index() -> Index {
+        let schema = schema();
+        let path = schema.get_field("file_path").unwrap();
+        let path_string = schema.get_field("file_path_string").unwrap();
+        let index = Index::create_in_ram(schema);
+        let mut writer = index.writer(15_000_000).unwrap();
+        for file_path in PATHS {
+            let mut doc = TantivyDocument::default();
+            doc.add_text(path, file_path);
+            doc.add_text(path_string, file_path);
+            writer.add_document(doc).unwrap();
+        }
+        writer.commit().unwrap();
+        index
+    }
+
+    fn count(query: &str) -> usize {
+        let index = index();
+        let schema = schema();
+        let query = parse_term_query(
+            &index,
+            schema.get_field("file_path").unwrap(),
+            schema.get_field("file_path_string").unwrap(),
+            query,
+        )
+        .unwrap();
+        index
+            .reader()
+            .unwrap()
+            .searcher()
+            .search(&query, &Count)
+            .unwrap()
+    }
+
+    #[test]
+    fn terms_are_combined_with_and() {
+        assert_eq!(count("budget 2024"), 1);
+    }
+
+    #[test]
+    fn or_matches_either_term() {
+        assert_eq!(count("2019 OR invoice"), 2);
+    }
+
+    #[test]
+    fn minus_excludes_terms() {
+        assert_eq!(count("budget -draft -archive"), 3);
+    }
+
+    #[test]
+    fn phrases_must_match_in_order() {
+        assert_eq!(count(r#""annual budget""#), 1);
+        assert_eq!(count(r#""budget annual""#), 0);
+    }
+
+    #[test]
+    fn parentheses_group_clauses() {
+        assert_eq!(count("(invoice OR draft) docs"), 2);
+    }
+
+    #[test]
+    fn excluded_dir_by_name() {
+        assert_eq!(count("budget -in:archive"), 4);
+    }
+
+    #[test]
+    fn excluded_dir_by_path() {
+        assert_eq!(count(r#"budget -in:"d:\old stuff""#), 4);
+        // Only the directory itself is excluded, not directories that start with the same name
+        assert_eq!(count(r"budget -in:C:\Doc"), 5);
+    }
+
+    #[test]
+    fn only_exclusions_match_everything_else() {
+        assert_eq!(count("-draft"), 5);
+        assert_eq!(count("-in:docs"), 1);
+    }
+
+    #[test]
+    fn partial_queries_are_completed() {
+        assert_eq!(count(r#""annual bud"#), 0);
+        assert_eq!(count(r#""annual budget"#), 1);
+        assert_eq!(count("budget OR"), 5);
+        assert_eq!(count("budget -"), 5);
+        assert_eq!(count("(invoice OR draft"), 2);
+        assert_eq!(count("budget ("), 5);
+    }
+}
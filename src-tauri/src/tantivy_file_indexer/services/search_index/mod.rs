@@ -6,6 +6,7 @@ pub mod pipelines {
     mod util;
 }
 mod services {
+    pub mod query_cache;
     pub mod task_manager;
 }
 mod core {
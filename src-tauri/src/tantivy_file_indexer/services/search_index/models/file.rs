@@ -1,6 +1,43 @@
-use crate::{get_parent_directory, shared::models::sys_file_model::SystemFileModel};
+use crate::{
+    get_parent_directory,
+    shared::models::{
+        audio_tags_model::AudioTagsModel, document_metadata_model::DocumentMetadataModel,
+        sys_file_model::SystemFileModel,
+    },
+};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::{path::Path, sync::LazyLock};
+use tantivy::{
+    schema::{Schema, TEXT},
+    TantivyDocument,
+};
+
+/// The text of a document, which allows queries such as `body:invoice`. It can be up to 100k characters and is never
+/// needed once a file is found, so it is indexed without being stored. Every field of `TantivyFileModel` is stored, so
+/// the body lives next to it in `FileDocument`
+const BODY_FIELD: &str = "body";
+
+/// The fields of `TantivyFileModel` followed by the body. Coming last means that every other field keeps the id it has
+/// in the schema of the model
+static INDEX_SCHEMA: LazyLock<Schema> = LazyLock::new(|| {
+    let mut builder = Schema::builder();
+    for (_, entry) in TantivyFileModel::schema().fields() {
+        builder.add_field(entry.clone());
+    }
+    builder.add_text_field(BODY_FIELD, TEXT);
+    builder.build()
+});
+
+/// The schema that the index is created with
+pub fn index_schema() -> &'static Schema {
+    &INDEX_SCHEMA
+}
+
+pub fn body_field() -> tantivy::schema::Field {
+    INDEX_SCHEMA
+        .get_field(BODY_FIELD)
+        .expect("the body is part of the index schema")
+}
 
 #[derive(tantivy_ext::TantivySearchIndex, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
@@ -10,16 +47,48 @@ pub struct TantivyFileModel {
     pub file_path_string: tantivy_ext::FastStr,
     /// This field is tokenized and used for searches
     pub file_path: tantivy_ext::Tokenized,
+    /// For archive members this is the path of the archive, so that the members get treated as its children
     pub parent_directory: tantivy_ext::FastStr,
     pub date_modified: tantivy_ext::Date,
     pub date_created: tantivy_ext::Date,
     pub score: tantivy_ext::Score,
     pub popularity: tantivy_ext::FastF64,
+    /// Audio tags. These are empty strings for files that aren't audio files, which allows queries such as `artist:radiohead`
+    pub artist: tantivy_ext::Tokenized,
+    pub album: tantivy_ext::Tokenized,
+    /// Shared between the title of a song and the title of a document
+    pub title: tantivy_ext::Tokenized,
+    pub genre: tantivy_ext::Tokenized,
+    /// The length of an audio track in seconds. Zero for anything else
+    pub duration: tantivy_ext::FastU64,
+    /// Document metadata. Like the audio tags, these are empty for files that aren't documents
+    pub author: tantivy_ext::Tokenized,
+    /// The number of pages, slides or sheets in a document. Zero for anything else
+    pub page_count: tantivy_ext::FastU64,
+    /// The names of the functions, structs, classes, etc. defined in a source file, separated by spaces.
+    /// Can be searched with `sym:` or `symbols:`
+    pub symbols: tantivy_ext::Tokenized,
+    /// The path of the archive that an archive member lives in. Empty for regular files
+    pub container_path: tantivy_ext::FastStr,
+    /// 1 for files that live inside of an archive, which allows queries such as `is_archive_member:1`
+    pub is_archive_member: tantivy_ext::FastU64,
+    /// 1 for symlinks, which allows queries such as `is_symlink:1`
+    pub is_symlink: tantivy_ext::FastU64,
+    /// Where a symlink points to. Empty for anything that isn't a symlink
+    pub link_target: tantivy_ext::FastStr,
+    /// The id of the volume that the file is on, or empty if the volume isn't tracked. Lets the files on offline volumes
+    /// be hidden from searches, and be moved along with their volume when it gets mounted somewhere else
+    pub volume_id: tantivy_ext::FastStr,
 }
 
 impl From<SystemFileModel> for TantivyFileModel {
     fn from(value: SystemFileModel) -> TantivyFileModel {
-        let parent_directory = get_parent_directory(&value.file_path);
+        let parent_directory = match &value.container_path {
+            Some(container) => container.clone(),
+            None => get_parent_directory(&value.file_path),
+        };
+        let tags = value.audio_tags.unwrap_or_default();
+        let document = value.document.unwrap_or_default();
         TantivyFileModel {
             file_path_string: value.file_path.clone().into(),
             file_path: value.file_path.into(),
@@ -28,6 +97,20 @@ impl From<SystemFileModel> for TantivyFileModel {
             date_created: value.date_created.into(),
             score: 0.0.into(),
             popularity: 0.0.into(),
+            artist: tags.artist.unwrap_or_default().into(),
+            album: tags.album.unwrap_or_default().into(),
+            title: tags.title.or(document.title).unwrap_or_default().into(),
+            genre: tags.genre.unwrap_or_default().into(),
+            duration: tags.duration.into(),
+            author: document.author.unwrap_or_default().into(),
+            page_count: document.page_count.into(),
+            symbols: value.symbols.unwrap_or_default().join(" ").into(),
+            is_archive_member: u64::from(value.container_path.is_some()).into(),
+            container_path: value.container_path.unwrap_or_default().into(),
+            is_symlink: u64::from(value.is_symlink).into(),
+            link_target: value.link_target.unwrap_or_default().into(),
+            // Filled in by the pipeline, which knows about the volumes
+            volume_id: String::new().into(),
         }
     }
 }
@@ -41,6 +124,8 @@ impl From<TantivyFileModel> for SystemFileModel {
             .unwrap_or_default()
             .to_string_lossy()
             .to_string();
+        let audio_tags = value.audio_tags();
+        let document = value.document();
         SystemFileModel {
             name,
             file_path: value.file_path.tantivy_val(),
@@ -49,6 +134,140 @@ impl From<TantivyFileModel> for SystemFileModel {
             score: value.score.tantivy_val(),
             size: 0, //TODO: ensure this is not needed
             is_directory: path.is_dir(),
+            audio_tags,
+            document,
+            symbols: None,
+            container_path: non_empty(value.container_path.tantivy_val()),
+            is_symlink: value.is_symlink.tantivy_val() == 1,
+            link_target: non_empty(value.link_target.tantivy_val()),
+        }
+    }
+}
+
+fn non_empty(val: String) -> Option<String> {
+    if val.is_empty() {
+        None
+    } else {
+        Some(val)
+    }
+}
+
+/// A file along with the body that gets indexed for it. The body can't be read back out of the index, so it has to
+/// come along every time the file gets written
+pub struct FileDocument {
+    pub model: TantivyFileModel,
+    pub body: Option<String>,
+}
+
+impl FileDocument {
+    /// For files that were read back out of the index, which left their bodies behind
+    pub fn without_body(model: TantivyFileModel) -> Self {
+        Self { model, body: None }
+    }
+
+    pub fn as_document(&self) -> TantivyDocument {
+        let mut document = self.model.as_document();
+        if let Some(body) = &self.body {
+            document.add_text(body_field(), body);
+        }
+        document
+    }
+}
+
+impl From<SystemFileModel> for FileDocument {
+    fn from(mut value: SystemFileModel) -> Self {
+        // Taken out first, since the model leaves the body out
+        let body = value
+            .document
+            .as_mut()
+            .and_then(|document| document.body.take());
+        Self {
+            model: value.into(),
+            body,
+        }
+    }
+}
+
+impl TantivyFileModel {
+    /// Returns `None` if none of the audio tag fields were filled in when the file was indexed
+    fn audio_tags(&self) -> Option<AudioTagsModel> {
+        let tags = AudioTagsModel {
+            artist: non_empty(self.artist.tantivy_val()),
+            album: non_empty(self.album.tantivy_val()),
+            title: non_empty(self.title.tantivy_val()),
+            genre: non_empty(self.genre.tantivy_val()),
+            duration: self.duration.tantivy_val(),
+        };
+        // The title is left out since documents fill it in as well
+        let has_tags = tags.artist.is_some()
+            || tags.album.is_some()
+            || tags.genre.is_some()
+            || tags.duration > 0;
+        has_tags.then_some(tags)
+    }
+
+    /// Whether the file was parsed as a document, meaning that it may have had its body indexed
+    pub fn is_document(&self) -> bool {
+        self.document().is_some()
+    }
+
+    /// Returns `None` for files that weren't parsed as documents. The body isn't stored, so it is always left out
+    fn document(&self) -> Option<DocumentMetadataModel> {
+        let document = DocumentMetadataModel {
+            title: non_empty(self.title.tantivy_val()),
+            author: non_empty(self.author.tantivy_val()),
+            page_count: self.page_count.tantivy_val(),
+            body: None,
+        };
+        let is_document = document.author.is_some() || document.page_count > 0;
+        is_document.then_some(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_is_indexed_without_being_stored() {
+        let entry = index_schema().get_field_entry(body_field());
+        assert!(entry.is_indexed());
+        assert!(!entry.is_stored());
+    }
+
+    #[test]
+    fn body_is_written_along_with_the_file() {
+        let file = SystemFileModel {
+            name: "report.pdf".to_string(),
+            file_path: "docs/report.pdf".to_string(),
+            date_modified: chrono::Utc::now(),
+            date_created: chrono::Utc::now(),
+            size: 0,
+            score: 0.0,
+            is_directory: false,
+            audio_tags: None,
+            document: Some(DocumentMetadataModel {
+                page_count: 1,
+                body: Some("quarterly invoice".to_string()),
+                ..Default::default()
+            }),
+            symbols: None,
+            container_path: None,
+            is_symlink: false,
+            link_target: None,
+        };
+        let file = FileDocument::from(file);
+        assert_eq!(file.body.as_deref(), Some("quarterly invoice"));
+        assert!(file.as_document().get_first(body_field()).is_some());
+
+        let moved = FileDocument::without_body(file.model);
+        assert!(moved.as_document().get_first(body_field()).is_none());
+    }
+
+    #[test]
+    fn model_fields_keep_their_ids() {
+        for (field, entry) in TantivyFileModel::schema().fields() {
+            assert_eq!(index_schema().get_field(entry.name()).unwrap(), field);
         }
     }
 }
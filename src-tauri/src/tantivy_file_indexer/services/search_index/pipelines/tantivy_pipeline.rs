@@ -1,21 +1,148 @@
 use super::util;
+use crate::get_parent_directory;
 use crate::shared::models::sys_file_model::SystemFileModel;
 use crate::tantivy_file_indexer::services::search_index::core::engine::ranker;
 use crate::tantivy_file_indexer::{
-    services::search_index::models::file::TantivyFileModel,
+    services::{
+        search_index::models::file::{FileDocument, TantivyFileModel},
+        volumes::service::VolumeService,
+    },
     shared::indexing_crawler::traits::commit_pipeline::CrawlerCommitPipeline,
+    util::path,
 };
-use std::collections::HashMap;
-use tantivy_ext::SearchIndex;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use tantivy_ext::{async_retry, Field, Index, SearchIndex};
 
 /// Pipeline where Tantivy is used as the main database. SQLite is used as a queue
 pub struct TantivyPipeline {
     index: SearchIndex<TantivyFileModel>,
+    /// Every file gets marked with the volume that it is on
+    volumes: Arc<VolumeService>,
+}
+
+/// What got moved along with a volume
+pub struct MovedVolume {
+    pub num_moved: usize,
+    /// The directories of the documents that were moved. Their bodies aren't stored, so they were moved without them
+    /// and have to be indexed again
+    pub document_dirs: Vec<String>,
 }
 
 impl TantivyPipeline {
-    pub fn new(index: SearchIndex<TantivyFileModel>) -> Self {
-        Self { index }
+    pub fn new(index: SearchIndex<TantivyFileModel>, volumes: Arc<VolumeService>) -> Self {
+        Self { index, volumes }
+    }
+
+    /// Removes a directory along with everything that was indexed anywhere underneath it
+    pub async fn remove_tree(&self, dir_path: String) -> Result<(), String> {
+        let descendants = util::map_err(util::search_descendants(&self.index, &dir_path))?;
+        let mut keys: Vec<String> = descendants
+            .into_iter()
+            .map(|model| model.file_path_string.tantivy_val())
+            .collect();
+        keys.push(dir_path);
+        self.remove_many(keys).await
+    }
+
+    /// Moves everything that was indexed on the volume from inside of `old_root` to the same place inside of
+    /// `new_root`, for when the volume got mounted somewhere else
+    pub async fn move_volume(
+        &self,
+        volume_id: &str,
+        old_root: &str,
+        new_root: &str,
+    ) -> Result<MovedVolume, String> {
+        let models = util::map_err(util::search_by_volume(&self.index, volume_id.to_string()))?;
+        let mut old_keys = Vec::new();
+        let mut moved = Vec::new();
+        let mut document_dirs = HashSet::new();
+        for mut model in models {
+            let old_key = model.file_path_string.tantivy_val();
+            let Some(new_key) = path::rebase(&old_key, old_root, new_root) else {
+                continue;
+            };
+            let container_path = model.container_path.tantivy_val();
+            if container_path.is_empty() {
+                model.parent_directory = get_parent_directory(&new_key).into();
+            } else {
+                let container_path =
+                    path::rebase(&container_path, old_root, new_root).unwrap_or(container_path);
+                model.parent_directory = container_path.clone().into();
+                model.container_path = container_path.into();
+            }
+            if model.is_document() {
+                document_dirs.insert(model.parent_directory.tantivy_val());
+            }
+            model.file_path_string = new_key.clone().into();
+            model.file_path = new_key.into();
+            old_keys.push(old_key);
+            moved.push(FileDocument::without_body(model));
+        }
+        // Added before the old paths are removed, so that nothing gets lost if removing them fails
+        util::map_err(self.add(&moved).await)?;
+        self.remove_many(old_keys).await?;
+        Ok(MovedVolume {
+            num_moved: moved.len(),
+            document_dirs: document_dirs.into_iter().collect(),
+        })
+    }
+
+    /// Up to `limit` of the indexed files whose paths come after `cursor`, in the order of their paths. Also returns the
+    /// cursor that the next chunk starts after, which is `None` once the end of the index is reached
+    pub async fn get_chunk_after(
+        &self,
+        cursor: &str,
+        limit: usize,
+    ) -> Result<(Vec<SystemFileModel>, Option<String>), String> {
+        let paths = util::map_err(util::paths_after(&self.index, cursor, limit))?;
+        let next_cursor = if paths.len() < limit {
+            None
+        } else {
+            paths.last().cloned()
+        };
+        let mut models = Vec::new();
+        for path in paths {
+            if let Some(model) = util::map_err(util::search_by_path(&self.index, path))? {
+                models.push(model.into());
+            }
+        }
+        Ok((models, next_cursor))
+    }
+
+    /// Works like `SearchIndex::add`, except that the bodies of the documents get added as well. Every file gets
+    /// written through here, so that none of them can leave its body behind without saying so
+    async fn add(&self, files: &[FileDocument]) -> tantivy::Result<()> {
+        let writer = self.index.get_writer();
+        {
+            let mut writer_lock = writer.write().await;
+            let writer = writer_lock
+                .as_mut()
+                .expect("the writer is only missing while it gets replaced, which holds the lock");
+            for file in files {
+                writer.delete_term(file.model.get_primary_key());
+                writer.add_document(file.as_document())?;
+            }
+            async_retry::retry_with_backoff(|_| writer.commit(), 3, Duration::from_millis(100))
+                .await?;
+        }
+        // The writer lock has to be released first, since the writer might get replaced
+        self.index
+            .get_tantivy_backend()
+            .writer
+            .register_entries_processed(files.len())
+            .await
+    }
+
+    fn mark_volume(&self, model: &mut TantivyFileModel) {
+        model.volume_id = self
+            .volumes
+            .volume_id_for(&model.file_path_string.tantivy_val())
+            .into();
     }
 }
 
@@ -44,8 +171,8 @@ impl CrawlerCommitPipeline for TantivyPipeline {
             .collect();
 
         // Separate models into existing and brand_new
-        let mut existing: Vec<(TantivyFileModel, &SystemFileModel)> = Vec::new();
-        let mut brand_new: Vec<TantivyFileModel> = Vec::new();
+        let mut existing: Vec<(FileDocument, &SystemFileModel)> = Vec::new();
+        let mut brand_new: Vec<FileDocument> = Vec::new();
 
         for model in models {
             if let Some(child) = child_map.get(model.file_path.as_str()) {
@@ -56,21 +183,31 @@ impl CrawlerCommitPipeline for TantivyPipeline {
         }
 
         // Rank the files as a part of preprocessing
-        let tantivy_models: Vec<TantivyFileModel> = rank_files(existing, brand_new);
+        let mut files: Vec<FileDocument> = rank_files(existing, brand_new);
+        for file in files.iter_mut() {
+            self.mark_volume(&mut file.model);
+        }
 
         // Classify and remove stale files
-        let stale_keys = util::classify_stale_models(&children, &tantivy_models);
+        let mut stale_keys = util::classify_stale_models(&children, &files);
+        if !stale_keys.is_empty() {
+            // A drive that was just unplugged looks like a deleted directory, but its files are kept until it comes back
+            self.volumes.refresh().await;
+            stale_keys.retain(|key| !self.volumes.is_offline(Path::new(key)));
+        }
 
         self.remove_many(stale_keys).await?;
 
-        util::map_err(self.index.add(&tantivy_models).await)?;
+        util::map_err(self.add(&files).await)?;
 
         Ok(())
     }
 
     async fn upsert_one(&self, model: Self::InputModel) -> Result<(), Self::Error> {
-        let model: TantivyFileModel = model.into();
-        util::map_err(self.index.add(&[model]).await)?;
+        let mut file = FileDocument::from(model);
+        file.model = ranker::rank_new_file(file.model);
+        self.mark_volume(&mut file.model);
+        util::map_err(self.add(&[file]).await)?;
         Ok(())
     }
 
@@ -80,9 +217,15 @@ impl CrawlerCommitPipeline for TantivyPipeline {
 
     async fn remove_many(&self, keys: Vec<String>) -> Result<(), Self::Error> {
         if !keys.is_empty() {
+            // Removing an archive removes the members that were indexed from it as well
             let terms = keys
                 .into_iter()
-                .map(|key| TantivyFileModel::file_path_string_field().term(key))
+                .flat_map(|key| {
+                    [
+                        TantivyFileModel::container_path_field().term(key.clone()),
+                        TantivyFileModel::file_path_string_field().term(key),
+                    ]
+                })
                 .collect();
             util::map_err(self.index.remove_by_terms(terms).await)?;
         }
@@ -92,16 +235,22 @@ impl CrawlerCommitPipeline for TantivyPipeline {
 
 /// Returns the files you passed in, aggregated and ranked
 fn rank_files(
-    existing: Vec<(TantivyFileModel, &SystemFileModel)>,
-    brand_new: Vec<TantivyFileModel>,
-) -> Vec<TantivyFileModel> {
-    let mut tantivy_models: Vec<TantivyFileModel> = Vec::new();
-    tantivy_models.extend(
+    existing: Vec<(FileDocument, &SystemFileModel)>,
+    brand_new: Vec<FileDocument>,
+) -> Vec<FileDocument> {
+    let mut files: Vec<FileDocument> = Vec::new();
+    files.extend(
         existing
             .into_iter()
-            .map(|(new_file, old_file)| ranker::rank_existing_file(new_file, old_file)),
+            .map(|(new_file, old_file)| FileDocument {
+                model: ranker::rank_existing_file(new_file.model, old_file),
+                body: new_file.body,
+            }),
     );
 
-    tantivy_models.extend(brand_new.into_iter().map(ranker::rank_new_file));
-    tantivy_models
+    files.extend(brand_new.into_iter().map(|file| FileDocument {
+        model: ranker::rank_new_file(file.model),
+        body: file.body,
+    }));
+    files
 }
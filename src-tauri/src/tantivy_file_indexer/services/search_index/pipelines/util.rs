@@ -1,11 +1,17 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
-use tantivy::{query::TermQuery, Term};
+use tantivy::{
+    query::{RegexQuery, TermQuery},
+    Term,
+};
 use tantivy_ext::{Field, SearchIndex};
 
 use crate::{
     shared::models::sys_file_model::SystemFileModel,
-    tantivy_file_indexer::services::search_index::models::file::TantivyFileModel,
+    tantivy_file_indexer::{
+        services::search_index::models::file::{FileDocument, TantivyFileModel},
+        util::string::escape_regex,
+    },
 };
 
 /// Helper function to map errors into strings
@@ -14,14 +20,11 @@ pub fn map_err<T, E: ToString>(result: Result<T, E>) -> Result<T, String> {
 }
 
 /// Helper function to get stale models. The `models` parameter represents the new models and `children` are the old ones
-pub fn classify_stale_models(
-    children: &[SystemFileModel],
-    models: &[TantivyFileModel],
-) -> Vec<String> {
+pub fn classify_stale_models(children: &[SystemFileModel], models: &[FileDocument]) -> Vec<String> {
     // Build a HashSet of file paths from `models`
     let model_paths: HashSet<String> = models
         .iter()
-        .map(|file| file.file_path_string.tantivy_val())
+        .map(|file| file.model.file_path_string.tantivy_val())
         .collect();
 
     // Filter out stale `children` whose file paths are not in `model_paths`
@@ -52,17 +55,68 @@ pub fn search_by_directory(
     search_by_term(index, term)
 }
 
+/// Finds everything whose path is inside of `directory_path`, no matter how deeply nested
+pub fn search_descendants(
+    index: &SearchIndex<TantivyFileModel>,
+    directory_path: &str,
+) -> tantivy::Result<Vec<TantivyFileModel>> {
+    let directory_path = directory_path.trim_end_matches(['/', '\\']);
+    let pattern = format!(r"{}[\\/].*", escape_regex(directory_path));
+    let field: String = TantivyFileModel::file_path_string_field().into();
+    let field = SearchIndex::<TantivyFileModel>::schema().get_field(&field)?;
+    let query = RegexQuery::from_pattern(&pattern, field)?;
+    index.query(&query, 1_000_000).execute()
+}
+
+/// Finds everything that was indexed on the volume
+pub fn search_by_volume(
+    index: &SearchIndex<TantivyFileModel>,
+    volume_id: String,
+) -> tantivy::Result<Vec<TantivyFileModel>> {
+    let term = TantivyFileModel::volume_id_field().term(volume_id);
+    search_by_term(index, term)
+}
+
+/// The paths that come after `cursor`, in order, up to `limit` of them. They are read from the term dictionary, so they
+/// can include paths whose files were removed but haven't been merged away yet
+pub fn paths_after(
+    index: &SearchIndex<TantivyFileModel>,
+    cursor: &str,
+    limit: usize,
+) -> tantivy::Result<Vec<String>> {
+    let backend = index.get_tantivy_backend();
+    let field_name: String = TantivyFileModel::file_path_string_field().into();
+    let field = backend.schema.get_field(&field_name)?;
+    let searcher = backend.reader.searcher();
+
+    // Every segment has its own sorted dictionary, so the first `limit` paths of each are enough
+    let mut paths = BTreeSet::new();
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = segment_reader.inverted_index(field)?;
+        let mut stream = inverted_index.terms().range().gt(cursor).into_stream()?;
+        let mut num_read = 0;
+        while num_read < limit && stream.advance() {
+            paths.insert(String::from_utf8_lossy(stream.key()).to_string());
+            num_read += 1;
+        }
+    }
+    Ok(paths.into_iter().take(limit).collect())
+}
+
+/// The path is the primary key, so there should never be more than one match. If there are duplicates anyway,
+/// the first one is returned
 pub fn search_by_path(
     index: &SearchIndex<TantivyFileModel>,
     file_path: String,
 ) -> tantivy::Result<Option<TantivyFileModel>> {
-    let term = TantivyFileModel::file_path_string_field().term(file_path);
-    let mut results = search_by_term(index, term)?;
-    match results.len() {
-        0 => Ok(None),
-        1 => Ok(Some(results.pop().unwrap())),
-        val => {
-            panic!("Duplicate keys exist in index. Found {} entries", val)
-        }
+    let term = TantivyFileModel::file_path_string_field().term(file_path.clone());
+    let results = search_by_term(index, term)?;
+    if results.len() > 1 {
+        println!(
+            "Duplicate keys exist in index. Found {} entries for {}",
+            results.len(),
+            file_path
+        );
     }
+    Ok(results.into_iter().next())
 }
@@ -4,17 +4,30 @@ use crate::{
         dtos::{
             search_params_dto::SearchParamsDTO, streaming_search_dto::StreamingSearchParamsDTO,
         },
+        services::{
+            local_db::tables::app_kv_store::api::AppKvStoreTable, volumes::service::VolumeService,
+        },
         shared::indexing_crawler::traits::commit_pipeline::CrawlerCommitPipeline,
     },
 };
 
 use super::{
-    core::engine::{querier::Querier, query_builder::constructor::QueryConstructor},
-    models::file::TantivyFileModel,
+    core::engine::{
+        querier::Querier,
+        query_builder::{constructor::QueryConstructor, deadline::QueryDeadline, synonyms},
+    },
+    models::file::{self, TantivyFileModel},
     pipelines::tantivy_pipeline::TantivyPipeline,
-    services::task_manager::TaskManagerService,
+    services::{
+        query_cache::{QueryCache, QueryCacheMetricsModel},
+        task_manager::TaskManagerService,
+    },
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
-use std::{path::PathBuf, sync::Arc};
 
 use tantivy_ext::{index::index_builder::SearchIndexBuilder, SearchIndex};
 use tauri::{AppHandle, Manager};
@@ -24,12 +37,29 @@ pub struct SearchIndexService {
     /// Dictates how crawlers store documents
     pub pipeline: Arc<TantivyPipeline>,
     querier: Arc<Querier>,
+    constructor: Arc<QueryConstructor>,
     search_index: SearchIndex<TantivyFileModel>,
+    query_cache: Arc<QueryCache>,
+    volumes: Arc<VolumeService>,
+    /// Whether the index was wiped on startup because its schema was outdated
+    was_reset: bool,
 }
 
+/// Keeps apart the cached results of the different kinds of queries
+const ORGANIZED_QUERY_SCOPE: &str = "organized";
+const QUERY_SCOPE: &str = "query";
+
 impl SearchIndexService {
-    pub fn new(app_path: PathBuf, handle: &AppHandle) -> Self {
+    /// `kv_store` is where the user's search settings, such as synonyms, are read from
+    pub fn new(
+        app_path: PathBuf,
+        handle: &AppHandle,
+        kv_store: AppKvStoreTable,
+        volumes: Arc<VolumeService>,
+    ) -> Self {
         let index_path = app_path.join("TantivyOut");
+        let was_reset = Self::remove_index_if_schema_changed(&index_path);
+        Self::create_index_if_missing(&index_path);
 
         let index = SearchIndexBuilder::new(index_path)
             .with_memory_budget(50_000_000)
@@ -39,14 +69,16 @@ impl SearchIndexService {
         let backend = index_clone.get_tantivy_backend();
 
         let constructor = Arc::new(QueryConstructor::new(
-            SearchIndex::<TantivyFileModel>::schema().clone(),
+            file::index_schema().clone(),
             backend.reader.clone(),
+            kv_store,
+            Arc::clone(&volumes),
         ));
 
         handle.manage(Arc::new(TaskManagerService::new()));
 
         // Create the commit pipeline
-        let pipeline = TantivyPipeline::new(index.clone());
+        let pipeline = TantivyPipeline::new(index.clone(), Arc::clone(&volumes));
 
         Self {
             search_index: index,
@@ -55,13 +87,66 @@ impl SearchIndexService {
                 backend.reader.clone(),
                 Arc::clone(&constructor),
             )),
+            constructor,
+            query_cache: Arc::new(QueryCache::new(backend.reader.clone())),
+            volumes,
+            was_reset,
+        }
+    }
+
+    /// `true` if the index was wiped on startup, in which case everything has to be crawled again
+    pub fn was_reset(&self) -> bool {
+        self.was_reset
+    }
+
+    /// An index that was created with an older version of `TantivyFileModel` can't be written to with the current schema,
+    /// so it gets wiped. The crawlers will then rebuild it from scratch. Returns `true` if the index was removed
+    fn remove_index_if_schema_changed(index_path: &Path) -> bool {
+        if !index_path.exists() {
+            return false;
+        }
+        let Ok(index) = tantivy::Index::open_in_dir(index_path) else {
+            return false;
+        };
+        if index.schema() == *file::index_schema() {
+            return false;
+        }
+        println!(
+            "SearchIndexService: The schema of the index at {:?} is outdated. Removing it so that it can be rebuilt",
+            index_path
+        );
+        // Dropped first, since Windows doesn't remove files that are still open
+        drop(index);
+        match fs::remove_dir_all(index_path) {
+            Ok(()) => true,
+            Err(err) => {
+                println!("SearchIndexService: Failed to remove outdated index: {}", err);
+                false
+            }
         }
     }
 
-    /// Spawns a tokio task for the query
+    /// The index gets created here rather than by `SearchIndexBuilder`, since its schema has the body on top of the
+    /// fields of `TantivyFileModel`
+    fn create_index_if_missing(index_path: &Path) {
+        if index_path.exists() {
+            return;
+        }
+        let result = fs::create_dir_all(index_path).and_then(|()| {
+            tantivy::Index::create_in_dir(index_path, file::index_schema().clone())
+                .map(drop)
+                .map_err(std::io::Error::other)
+        });
+        if let Err(err) = result {
+            println!("SearchIndexService: Failed to create the index: {}", err);
+        }
+    }
+
+    /// Spawns a tokio task for the query, which stops once `deadline` passes or gets cancelled
     pub fn streaming_query<EmitFn>(
         &self,
         params: StreamingSearchParamsDTO,
+        deadline: Arc<QueryDeadline>,
         emit: EmitFn,
     ) -> JoinHandle<tantivy::Result<()>>
     where
@@ -75,38 +160,93 @@ impl SearchIndexService {
                     emit,
                     params.num_events,
                     params.starting_size,
+                    &deadline,
                 )
                 .await
         })
     }
 
-    /// Spawns a tokio task for the query
+    /// Spawns a tokio task for the query. If the same query was made recently and the index hasn't changed since,
+    /// the cached results are emitted all at once instead.
+    ///
+    /// The query stops once `deadline` passes or gets cancelled. Partial results don't get cached
     pub fn streaming_query_organized<EmitFn>(
         &self,
         params: StreamingSearchParamsDTO,
+        deadline: Arc<QueryDeadline>,
         emit: EmitFn,
-    ) -> JoinHandle<()>
+    ) -> JoinHandle<tantivy::Result<()>>
     where
         EmitFn: Fn(&[TantivyFileModel]) + Send + 'static,
     {
         let querier_clone = Arc::clone(&self.querier);
+        let constructor = Arc::clone(&self.constructor);
+        let cache = Arc::clone(&self.query_cache);
         tokio::spawn(async move {
-            querier_clone
+            // How the results get split into events decides which ones get organized, so it is a part of the key
+            let scope = format!(
+                "{}-{}-{}",
+                ORGANIZED_QUERY_SCOPE, params.num_events, params.starting_size
+            );
+            let key = Self::cache_key(&scope, &params.params, &constructor).await;
+            if let Some(cached) = cache.get(&key) {
+                emit(&cached);
+                return Ok(());
+            }
+            let (results, completed) = querier_clone
                 .organized_query_streamed(
                     params.params,
                     emit,
                     params.num_events,
                     params.starting_size,
+                    &deadline,
                 )
-                .await
+                .await?;
+            if completed && !deadline.was_interrupted() {
+                cache.insert(key, results);
+            }
+            Ok(())
         })
     }
 
-    pub fn query(
+    /// The second value is whether the results are partial, because the query ran out of its time budget
+    pub async fn query(
         &self,
         params: &SearchParamsDTO,
-    ) -> Result<Vec<TantivyFileModel>, tantivy::TantivyError> {
-        self.querier.advanced_query(params)
+    ) -> Result<(Vec<TantivyFileModel>, bool), tantivy::TantivyError> {
+        let key = Self::cache_key(QUERY_SCOPE, params, &self.constructor).await;
+        if let Some(cached) = self.query_cache.get(&key) {
+            return Ok((cached.as_ref().clone(), false));
+        }
+        let deadline = QueryDeadline::new(params.time_budget());
+        let results = self.querier.advanced_query(params, &deadline).await?;
+        let partial = deadline.was_interrupted();
+        if !partial {
+            self.query_cache.insert(key, results.clone());
+        }
+        Ok((results, partial))
+    }
+
+    /// The results change without the index changing whenever the synonyms change, offline volumes get hidden or shown,
+    /// or a volume goes offline or comes back while they are hidden. The hidden volumes are read fresh for every query
+    /// from both the setting and the online state, so they and the synonyms are a part of the key
+    async fn cache_key(
+        scope: &str,
+        params: &SearchParamsDTO,
+        constructor: &QueryConstructor,
+    ) -> String {
+        let hidden = constructor.hidden_volume_ids().await;
+        let scope = if hidden.is_empty() {
+            scope.to_string()
+        } else {
+            format!("{}-{}", scope, hidden.join(","))
+        };
+        let synonyms_version = synonyms::version(&constructor.synonym_groups().await);
+        QueryCache::key(&scope, params, synonyms_version)
+    }
+
+    pub fn query_cache_metrics(&self) -> QueryCacheMetricsModel {
+        self.query_cache.metrics()
     }
 
     pub async fn get_file_from_index(&self, file: SystemFileModel) -> Option<SystemFileModel> {
@@ -121,11 +261,15 @@ impl SearchIndexService {
         Arc::clone(&self.pipeline)
     }
 
-    /// Returns true if the file exists in the file system. If the file does not exist, it is removed from the index.
+    /// Returns true if the file exists in the file system. If the file does not exist, it is removed from the index,
+    /// unless it is on a volume that isn't connected right now.
     pub async fn validate_file_exists(&self, path: &str) -> Result<bool, tantivy::TantivyError> {
         if PathBuf::from(path).exists() {
             return Ok(true);
         }
+        if self.volumes.is_offline_now(Path::new(path)).await {
+            return Ok(false);
+        }
         let path_str = path.to_string();
         let terms = vec![TantivyFileModel::file_path_string_field().term(path_str)];
         self.search_index.remove_by_terms(terms).await?;
@@ -0,0 +1,258 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use lru::LruCache;
+use serde::Serialize;
+use tantivy::IndexReader;
+
+use crate::tantivy_file_indexer::{
+    dtos::search_params_dto::{DateRange, SearchParamsDTO},
+    services::search_index::models::file::TantivyFileModel,
+};
+
+/// The number of distinct queries whose results are kept around
+const CACHE_CAPACITY: usize = 128;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct QueryCacheMetricsModel {
+    pub hits: u64,
+    pub misses: u64,
+    /// The number of times that the cache was cleared because the index changed
+    pub invalidations: u64,
+    pub num_entries: usize,
+}
+
+struct CacheState {
+    entries: LruCache<String, Arc<Vec<TantivyFileModel>>>,
+    /// The searcher generation that the entries were computed with
+    generation: u64,
+}
+
+/// Remembers the results of recent queries. The frontend queries on every keystroke, so the same query often gets repeated.
+///
+/// Every entry gets thrown out as soon as the reader picks up a new commit, since the results could have changed
+pub struct QueryCache {
+    reader: IndexReader,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl QueryCache {
+    pub fn new(reader: IndexReader) -> Self {
+        let generation = Self::current_generation(&reader);
+        Self {
+            reader,
+            state: Mutex::new(CacheState {
+                entries: LruCache::new(NonZeroUsize::new(CACHE_CAPACITY).unwrap()),
+                generation,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<Arc<Vec<TantivyFileModel>>> {
+        let mut state = self.lock_valid_state();
+        match state.entries.get(key) {
+            Some(results) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(Arc::clone(results))
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, key: String, results: Vec<TantivyFileModel>) {
+        self.lock_valid_state().entries.put(key, Arc::new(results));
+    }
+
+    pub fn metrics(&self) -> QueryCacheMetricsModel {
+        let num_entries = self.lock_valid_state().entries.len();
+        QueryCacheMetricsModel {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            num_entries,
+        }
+    }
+
+    /// Clears the entries first if the index has been committed to since they were cached
+    fn lock_valid_state(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        let generation = Self::current_generation(&self.reader);
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if state.generation != generation {
+            state.entries.clear();
+            state.generation = generation;
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+        state
+    }
+
+    fn current_generation(reader: &IndexReader) -> u64 {
+        reader.searcher().generation().generation_id()
+    }
+
+    /// Queries that only differ by extra whitespace share an entry. Casing is kept, since the query grammar only treats
+    /// uppercase `AND`, `OR` and `NOT` as operators.
+    ///
+    /// `scope` keeps apart the results of different kinds of queries, since they can be ordered differently.
+    /// `synonyms_version` is the version of the synonym groups that the query gets expanded with
+    pub fn key(scope: &str, params: &SearchParamsDTO, synonyms_version: u64) -> String {
+        let normalize = |val: &Option<String>| {
+            val.as_deref()
+                .map(|val| val.split_whitespace().collect::<Vec<_>>().join(" "))
+                .unwrap_or_default()
+        };
+        let range = |range: &Option<DateRange>| {
+            range
+                .as_ref()
+                .map(|range| {
+                    format!(
+                        "{}..{}",
+                        range.start.unix_timestamp(),
+                        range.end.unix_timestamp()
+                    )
+                })
+                .unwrap_or_default()
+        };
+        format!(
+            "{}|{:x}|{:?}|{}|{}|{}|{}|{}|{}",
+            scope,
+            synonyms_version,
+            params.query_type,
+            params.num_results,
+            normalize(&params.file_path),
+            normalize(&params.name),
+            normalize(&params.metadata),
+            range(&params.date_modified_range),
+            range(&params.date_created_range),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::{
+        doc,
+        schema::{Schema, TEXT},
+        Index, ReloadPolicy,
+    };
+
+    use crate::tantivy_file_indexer::enums::search_query_type::SearchQueryType;
+
+    use super::*;
+
+    fn params(name: &str) -> SearchParamsDTO {
+        SearchParamsDTO {
+            name: Some(name.to_string()),
+            metadata: None,
+            date_modified_range: None,
+            date_created_range: None,
+            file_path: None,
+            num_results: 50,
+            query_type: SearchQueryType::Hybrid,
+            time_budget_ms: None,
+        }
+    }
+
+    #[test]
+    fn key_ignores_whitespace_but_not_casing() {
+        assert_eq!(
+            QueryCache::key("query", &params("annual  report"), 0),
+            QueryCache::key("query", &params(" annual report "), 0)
+        );
+        assert_ne!(
+            QueryCache::key("query", &params("annual report"), 0),
+            QueryCache::key("query", &params("annual reports"), 0)
+        );
+        // Only uppercase operators are operators
+        assert_ne!(
+            QueryCache::key("query", &params("budget OR invoice"), 0),
+            QueryCache::key("query", &params("budget or invoice"), 0)
+        );
+        assert_ne!(
+            QueryCache::key("query", &params("budget NOT draft"), 0),
+            QueryCache::key("query", &params("budget not draft"), 0)
+        );
+    }
+
+    #[test]
+    fn key_changes_with_anything_that_changes_the_results() {
+        let key = QueryCache::key("query", &params("report"), 0);
+        assert_ne!(key, QueryCache::key("organized", &params("report"), 0));
+        assert_ne!(key, QueryCache::key("query", &params("report"), 1));
+
+        let mut other = params("report");
+        other.num_results = 100;
+        assert_ne!(key, QueryCache::key("query", &other, 0));
+
+        let mut other = params("report");
+        other.query_type = SearchQueryType::Fuzzy;
+        assert_ne!(key, QueryCache::key("query", &other, 0));
+
+        let mut other = params("report");
+        other.file_path = Some("C:/docs".to_string());
+        assert_ne!(key, QueryCache::key("query", &other, 0));
+
+        let mut other = params("report");
+        other.date_modified_range = Some(DateRange {
+            start: tantivy::time::OffsetDateTime::UNIX_EPOCH,
+            end: tantivy::time::OffsetDateTime::UNIX_EPOCH + tantivy::time::Duration::DAY,
+        });
+        let modified_key = QueryCache::key("query", &other, 0);
+        assert_ne!(key, modified_key);
+        other.date_created_range = other.date_modified_range.take();
+        assert_ne!(modified_key, QueryCache::key("query", &other, 0));
+
+        // How long the query may run for doesn't change what it finds
+        let mut other = params("report");
+        other.time_budget_ms = Some(1);
+        assert_eq!(key, QueryCache::key("query", &other, 0));
+    }
+
+    #[test]
+    fn entries_are_cleared_once_the_index_changes() {
+        let mut schema = Schema::builder();
+        let field = schema.add_text_field("name", TEXT);
+        let index = Index::create_in_ram(schema.build());
+        let mut writer = index.writer(15_000_000).unwrap();
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .unwrap();
+        let cache = QueryCache::new(reader.clone());
+
+        cache.insert("key".to_string(), Vec::new());
+        assert!(cache.get("key").is_some());
+
+        // Committing alone doesn't invalidate anything until the reader picks it up
+        writer.add_document(doc!(field => "report")).unwrap();
+        writer.commit().unwrap();
+        assert!(cache.get("key").is_some());
+
+        reader.reload().unwrap();
+        assert!(cache.get("key").is_none());
+
+        let metrics = cache.metrics();
+        assert_eq!(metrics.hits, 2);
+        assert_eq!(metrics.misses, 1);
+        assert_eq!(metrics.invalidations, 1);
+        assert_eq!(metrics.num_entries, 0);
+    }
+}
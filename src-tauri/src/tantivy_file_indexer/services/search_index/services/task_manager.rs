@@ -1,13 +1,43 @@
+use std::collections::HashMap;
+
+use tokio::sync::Mutex;
+
 use crate::tantivy_file_indexer::shared::cancel_task::CancellableTask;
 
+/// Keeps one cancellable task per search stream, so that a new query only cancels the previous query of the same stream.
+/// Other windows and panels using a different stream identifier are left alone
 pub struct TaskManagerService {
-    pub task: CancellableTask,
+    tasks: Mutex<HashMap<String, CancellableTask>>,
 }
 
 impl TaskManagerService {
     pub fn new() -> Self {
         Self {
-            task: CancellableTask::new(),
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the task belonging to the stream, creating it if the stream hasn't been used before
+    pub async fn task_for(&self, stream_identifier: &str) -> CancellableTask {
+        self.tasks
+            .lock()
+            .await
+            .entry(stream_identifier.to_string())
+            .or_insert_with(CancellableTask::new)
+            .clone()
+    }
+
+    /// Cancels the search that is running on the stream, if there is one.
+    ///
+    /// Returns `false` if the stream has never been used
+    pub async fn cancel(&self, stream_identifier: &str) -> bool {
+        let task = self.tasks.lock().await.get(stream_identifier).cloned();
+        match task {
+            Some(task) => {
+                task.cancel().await;
+                true
+            }
+            None => false,
         }
     }
 }
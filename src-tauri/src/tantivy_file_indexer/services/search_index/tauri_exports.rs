@@ -2,16 +2,26 @@ use std::sync::Arc;
 use tauri::{AppHandle, Emitter, State};
 
 use crate::{
-    shared::models::sys_file_model::SystemFileModel,
+    shared::{
+        app_error::{AppError, AppResult},
+        models::sys_file_model::SystemFileModel,
+    },
     tantivy_file_indexer::{
         dtos::{
             search_params_dto::SearchParamsDTO, streaming_search_dto::StreamingSearchParamsDTO,
         },
-        models::emit_metadata_model::EmitMetadataModel,
+        models::{
+            emit_metadata_model::EmitMetadataModel, search_done_model::SearchDoneModel,
+            search_results_model::SearchResultsModel,
+        },
     },
 };
 
-use super::{service::SearchIndexService, services::task_manager::TaskManagerService};
+use super::{
+    core::engine::query_builder::deadline::QueryDeadline,
+    service::SearchIndexService,
+    services::{query_cache::QueryCacheMetricsModel, task_manager::TaskManagerService},
+};
 
 /**
 The frontent expects SystemFileModels, so we will map the Tantivy models to this
@@ -20,55 +30,73 @@ The frontent expects SystemFileModels, so we will map the Tantivy models to this
 pub async fn search_index_query(
     params: SearchParamsDTO,
     service: State<'_, Arc<SearchIndexService>>,
-) -> Result<Vec<SystemFileModel>, String> {
+) -> AppResult<SearchResultsModel> {
     let service = Arc::clone(&service);
 
     let handle = tokio::task::spawn(async move {
-        match service.query(&params) {
-            Ok(result) => Ok(result
-                .into_iter()
-                .map(|x| x.into())
-                .collect::<Vec<SystemFileModel>>()),
-            Err(err) => Err(err.to_string()),
+        match service.query(&params).await {
+            Ok((result, partial)) => Ok(SearchResultsModel {
+                results: result
+                    .into_iter()
+                    .map(|x| x.into())
+                    .collect::<Vec<SystemFileModel>>(),
+                partial,
+            }),
+            Err(err) => Err(AppError::from(err)),
         }
     });
 
     // Await the result from the spawned task
     match handle.await {
         Ok(res) => res,
-        Err(err) => Err(format!("Task failed: {}", err)),
+        Err(err) => Err(AppError::Internal(format!("Task failed: {}", err))),
     }
 }
 
 // TODO: you may be able to remove this if organized querying is superior
-/// Emits an event in the format {STREAM_IDENTIFIER}:search_result to the frontend
+/// Emits an event in the format {STREAM_IDENTIFIER}:search_result to the frontend.
+/// Once the stream ends, {STREAM_IDENTIFIER}:search_done is emitted
 #[tauri::command]
 pub async fn search_index_query_streaming(
     params: StreamingSearchParamsDTO,
     app_handle: AppHandle,
     search_service: State<'_, Arc<SearchIndexService>>,
     task_manager: State<'_, Arc<TaskManagerService>>,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let event_name = format!("{}:search_result", params.stream_identifier);
+    let stream_identifier = params.stream_identifier.clone();
     let search_service_clone = Arc::clone(&search_service);
+    let task = task_manager.task_for(&stream_identifier).await;
+    let deadline = Arc::new(QueryDeadline::new(params.params.time_budget()));
+    let cancel_deadline = Arc::clone(&deadline);
 
     let emit_metadata = params.params.file_path.clone().unwrap_or(String::from(""));
+    let done_metadata = emit_metadata.clone();
+    let app_handle_clone = app_handle.clone();
 
-    task_manager
-        .task
-        .run(search_service_clone.streaming_query(params, move |files| {
-            let sys_models: Vec<SystemFileModel> = files.into_iter().map(|x| x.into()).collect();
-            let model_output = EmitMetadataModel::new(sys_models, &emit_metadata);
+    let handle = search_service_clone.streaming_query(params, Arc::clone(&deadline), move |files| {
+        let sys_models: Vec<SystemFileModel> = files.into_iter().map(|x| x.into()).collect();
+        let model_output = EmitMetadataModel::new(sys_models, &emit_metadata);
 
-            match app_handle.emit(&event_name, model_output) {
-                Ok(_) => {}
-                Err(err) => println!("{}", err),
-            }
-        }))
-        .await? // Because the task could fail due to being cancelled
-        .map_err(|err| err.to_string())?; // Because the actual function running returns a Result
-
-    Ok(())
+        match app_handle_clone.emit(&event_name, model_output) {
+            Ok(_) => {}
+            Err(err) => println!("{}", err),
+        }
+    });
+    let result = task
+        .run_with_on_cancel(handle, move || cancel_deadline.cancel())
+        .await // Because the task could fail due to being cancelled
+        .map_err(AppError::from_task_error)
+        .and_then(|res| res.map_err(AppError::from)); // Because the actual function running returns a Result
+
+    emit_search_done(
+        &app_handle,
+        &stream_identifier,
+        done_metadata,
+        &result,
+        deadline.was_interrupted(),
+    );
+    result
 }
 
 #[tauri::command]
@@ -77,30 +105,87 @@ pub async fn search_index_query_streaming_organized(
     app_handle: AppHandle,
     search_service: State<'_, Arc<SearchIndexService>>,
     task_manager: State<'_, Arc<TaskManagerService>>,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let event_name = format!("{}:search_result", params.stream_identifier);
+    let stream_identifier = params.stream_identifier.clone();
     let search_service_clone = Arc::clone(&search_service);
+    let task = task_manager.task_for(&stream_identifier).await;
+    let deadline = Arc::new(QueryDeadline::new(params.params.time_budget()));
+    let cancel_deadline = Arc::clone(&deadline);
 
     // Emit the file path since it gets used as the search query.
     // The frontend will check and ensure that only events emitted with the correct search query will get shown to the user
     let emit_metadata = params.params.file_path.clone().unwrap_or(String::from(""));
+    let done_metadata = emit_metadata.clone();
+    let app_handle_clone = app_handle.clone();
 
-    task_manager
-        .task
-        .run(
-            search_service_clone.streaming_query_organized(params, move |files| {
-                // The frontend expects the payload to be wrapped in a EmitMetadataModel
-                let model_output = EmitMetadataModel::new(files, &emit_metadata);
-
-                match app_handle.emit(&event_name, model_output) {
-                    Ok(_) => {}
-                    Err(err) => println!("{}", err),
-                }
-            }),
-        )
-        .await?;
+    let handle = search_service_clone.streaming_query_organized(
+        params,
+        Arc::clone(&deadline),
+        move |files| {
+            // The frontend expects the payload to be wrapped in a EmitMetadataModel
+            let model_output = EmitMetadataModel::new(files, &emit_metadata);
+
+            match app_handle_clone.emit(&event_name, model_output) {
+                Ok(_) => {}
+                Err(err) => println!("{}", err),
+            }
+        },
+    );
+    // Cancelling has to reach the collector, otherwise the query would keep running in the background
+    let result = task
+        .run_with_on_cancel(handle, move || cancel_deadline.cancel())
+        .await
+        .map_err(AppError::from_task_error)
+        .and_then(|res| res.map_err(AppError::from));
+
+    emit_search_done(
+        &app_handle,
+        &stream_identifier,
+        done_metadata,
+        &result,
+        deadline.was_interrupted(),
+    );
+    result
+}
 
-    Ok(())
+/// Stops the search that is running on the given stream. Searches on other streams keep going.
+///
+/// Returns `false` if no search has ever been run on the stream
+#[tauri::command]
+pub async fn cancel_search(
+    stream_identifier: String,
+    task_manager: State<'_, Arc<TaskManagerService>>,
+) -> AppResult<bool> {
+    Ok(task_manager.cancel(&stream_identifier).await)
+}
+
+/// Emits {STREAM_IDENTIFIER}:search_done so that the frontend knows that no more results are coming
+fn emit_search_done(
+    app_handle: &AppHandle,
+    stream_identifier: &str,
+    metadata: String,
+    result: &AppResult<()>,
+    partial: bool,
+) {
+    let cancelled = matches!(result, Err(AppError::Cancelled));
+    let model = SearchDoneModel {
+        metadata,
+        cancelled,
+        partial: partial || cancelled,
+        error: result.as_ref().err().filter(|_| !cancelled).cloned(),
+    };
+    if let Err(err) = app_handle.emit(&format!("{}:search_done", stream_identifier), model) {
+        println!("{}", err);
+    }
+}
+
+/// The hit and miss counts of the cache that search results are kept in
+#[tauri::command]
+pub fn get_query_cache_metrics(
+    search_service: State<'_, Arc<SearchIndexService>>,
+) -> QueryCacheMetricsModel {
+    search_service.query_cache_metrics()
 }
 
 /// Can be handy for changing the fields of a file, such as the popularity, to some other value
@@ -108,15 +193,18 @@ pub async fn search_index_query_streaming_organized(
 pub async fn upsert_file_to_index(
     file: SystemFileModel,
     search_service: State<'_, Arc<SearchIndexService>>,
-) -> Result<(), String> {
-    search_service.upsert_file_to_index(file).await
+) -> AppResult<()> {
+    search_service
+        .upsert_file_to_index(file)
+        .await
+        .map_err(AppError::IndexUnavailable)
 }
 
 #[tauri::command]
 pub async fn get_file_from_index(
     file: SystemFileModel,
     search_service: State<'_, Arc<SearchIndexService>>,
-) -> Result<Option<SystemFileModel>, String> {
+) -> AppResult<Option<SystemFileModel>> {
     Ok(search_service.get_file_from_index(file).await)
 }
 
@@ -124,7 +212,7 @@ pub async fn get_file_from_index(
 pub async fn validate_file_exists(
     path: String,
     search_service: State<'_, Arc<SearchIndexService>>,
-) -> Result<bool, String> {
-    search_service.validate_file_exists(&path).await.map_err(|err| err.to_string())
+) -> AppResult<bool> {
+    Ok(search_service.validate_file_exists(&path).await?)
 }
 
@@ -0,0 +1,308 @@
+use std::{
+    collections::HashSet,
+    path::Path,
+    sync::{Mutex as StdMutex, RwLock, RwLockReadGuard},
+};
+
+use chrono::{DateTime, Utc};
+use system_info::models::drive_model::DriveModel;
+use tokio::sync::Mutex;
+
+use crate::tantivy_file_indexer::services::local_db::tables::volumes::{
+    api::VolumesTable, entities::volume,
+};
+
+/// What changed about the volumes since they were last refreshed
+#[derive(Default)]
+pub struct VolumeChanges {
+    /// Volumes that had never been seen before
+    pub added: Vec<volume::Model>,
+    pub came_online: Vec<volume::Model>,
+    pub went_offline: Vec<volume::Model>,
+    /// Volumes that are mounted somewhere else than before, along with where they were mounted before
+    pub moved: Vec<(String, volume::Model)>,
+}
+
+impl VolumeChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.came_online.is_empty()
+            && self.went_offline.is_empty()
+            && self.moved.is_empty()
+    }
+
+    fn append(&mut self, other: VolumeChanges) {
+        self.added.extend(other.added);
+        self.came_online.extend(other.came_online);
+        self.went_offline.extend(other.went_offline);
+        self.moved.extend(other.moved);
+    }
+}
+
+/// Keeps track of the volumes that files get indexed on, and of which of them are online, so that unplugging a drive
+/// doesn't make its files look deleted. Shared by the crawlers, the index watcher and the search index.
+///
+/// The volumes are only checked when they get refreshed, which the volume monitor does every few seconds while the
+/// crawlers are running
+pub struct VolumeService {
+    table: VolumesTable,
+    volumes: RwLock<Vec<volume::Model>>,
+    /// What changed since the changes were last taken. Any refresh can notice a change, but only the volume monitor
+    /// acts on them
+    changes: StdMutex<VolumeChanges>,
+    refresh_lock: Mutex<()>,
+}
+
+impl VolumeService {
+    /// The volumes get refreshed right away, so that volumes that were unplugged while the app was closed are known to
+    /// be offline before anything gets crawled
+    pub async fn new_async(table: VolumesTable) -> Self {
+        let volumes = table.get_all().await.unwrap_or_else(|err| {
+            println!("VolumeService: Could not read the volumes: {}", err);
+            Vec::new()
+        });
+        let service = Self {
+            table,
+            volumes: RwLock::new(volumes),
+            changes: StdMutex::new(VolumeChanges::default()),
+            refresh_lock: Mutex::new(()),
+        };
+        service.refresh().await;
+        service
+    }
+
+    /// Compares the known volumes against the drives that are mounted right now. What changed is kept until it is
+    /// taken with `take_changes`
+    pub async fn refresh(&self) {
+        let _guard = self.refresh_lock.lock().await;
+        let drives = match tokio::task::spawn_blocking(system_info::drives::get_system_drives).await
+        {
+            Ok(drives) => drives,
+            Err(err) => {
+                println!("VolumeService: Could not list the drives: {}", err);
+                return;
+            }
+        };
+
+        let known = self.read().clone();
+        let (volumes, changes) = reconcile(&known, &drives, Utc::now());
+        let changed: Vec<volume::Model> = volumes
+            .iter()
+            .filter(|volume| !known.iter().any(|old| same_state(old, volume)))
+            .cloned()
+            .collect();
+        if !changed.is_empty() {
+            if let Err(err) = self.table.upsert_many(&changed).await {
+                println!("VolumeService: Could not save the volumes: {}", err);
+            }
+        }
+
+        *self
+            .volumes
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = volumes;
+        self.lock_changes().append(changes);
+    }
+
+    pub fn take_changes(&self) -> VolumeChanges {
+        std::mem::take(&mut *self.lock_changes())
+    }
+
+    pub fn get_all(&self) -> Vec<volume::Model> {
+        self.read().clone()
+    }
+
+    /// The id of the volume that the path is on, or an empty string if the volume isn't known
+    pub fn volume_id_for(&self, path: &str) -> String {
+        volume_for(&self.read(), Path::new(path))
+            .map(|volume| volume.id.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether the path is on a volume that was offline when the volumes were last refreshed
+    pub fn is_offline(&self, path: &Path) -> bool {
+        volume_for(&self.read(), path).is_some_and(|volume| !volume.is_online)
+    }
+
+    /// Like `is_offline`, but refreshes the volumes first, for when a volume that was unplugged just now has to be
+    /// noticed
+    pub async fn is_offline_now(&self, path: &Path) -> bool {
+        self.refresh().await;
+        self.is_offline(path)
+    }
+
+    pub fn offline_ids(&self) -> Vec<String> {
+        self.read()
+            .iter()
+            .filter(|volume| !volume.is_online)
+            .map(|volume| volume.id.clone())
+            .collect()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Vec<volume::Model>> {
+        self.volumes
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_changes(&self) -> std::sync::MutexGuard<'_, VolumeChanges> {
+        self.changes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Drives are recognized by their UUID, or by their label if they don't have one. Network shares usually have neither,
+/// so they are recognized by where they are mounted, which means that they can go offline but can't move. Other drives
+/// without either aren't tracked
+fn volume_id(drive: &DriveModel) -> Option<String> {
+    if let Some(uuid) = &drive.uuid {
+        return Some(format!("uuid:{}", uuid));
+    }
+    if let Some(label) = &drive.label {
+        return Some(format!("label:{}", label));
+    }
+    drive.is_network.then(|| format!("mount:{}", drive.name))
+}
+
+/// Returns the volumes as they are now, along with what changed
+fn reconcile(
+    known: &[volume::Model],
+    drives: &[DriveModel],
+    now: DateTime<Utc>,
+) -> (Vec<volume::Model>, VolumeChanges) {
+    let mut volumes = known.to_vec();
+    let mut changes = VolumeChanges::default();
+    let mut mounted = HashSet::new();
+
+    for drive in drives {
+        let Some(id) = volume_id(drive) else {
+            continue;
+        };
+        // The same volume can be mounted more than once, in which case the first mount is the one that counts
+        if !mounted.insert(id.clone()) {
+            continue;
+        }
+        let Some(volume) = volumes.iter_mut().find(|volume| volume.id == id) else {
+            let volume = volume::Model {
+                id,
+                label: drive.label.clone(),
+                mount_path: drive.name.clone(),
+                is_online: true,
+                is_removable: drive.is_removable,
+                is_network: drive.is_network,
+                last_seen_at: now,
+            };
+            changes.added.push(volume.clone());
+            volumes.push(volume);
+            continue;
+        };
+
+        let was_online = volume.is_online;
+        let old_mount_path = std::mem::replace(&mut volume.mount_path, drive.name.clone());
+        volume.label = drive.label.clone();
+        volume.is_online = true;
+        volume.is_removable = drive.is_removable;
+        volume.is_network = drive.is_network;
+        volume.last_seen_at = now;
+        if old_mount_path != volume.mount_path {
+            changes.moved.push((old_mount_path, volume.clone()));
+        }
+        if !was_online {
+            changes.came_online.push(volume.clone());
+        }
+    }
+
+    for volume in volumes
+        .iter_mut()
+        .filter(|volume| volume.is_online && !mounted.contains(&volume.id))
+    {
+        volume.is_online = false;
+        changes.went_offline.push(volume.clone());
+    }
+    (volumes, changes)
+}
+
+/// Whether nothing but the time that the volume was last seen at differs
+fn same_state(a: &volume::Model, b: &volume::Model) -> bool {
+    volume::Model {
+        last_seen_at: b.last_seen_at,
+        ..a.clone()
+    } == *b
+}
+
+/// The volume that the path is on. A path can be inside of several volumes, such as `/` and `/media/usb`, in which case
+/// the deepest one is the one that it is on. If several volumes were mounted at the same path, the online one is the one
+/// that is there now
+fn volume_for<'a>(volumes: &'a [volume::Model], path: &Path) -> Option<&'a volume::Model> {
+    volumes
+        .iter()
+        .filter(|volume| volume.contains(path))
+        .max_by_key(|volume| {
+            (
+                Path::new(&volume.mount_path).components().count(),
+                volume.is_online,
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drive(name: &str, uuid: &str) -> DriveModel {
+        DriveModel {
+            name: name.to_string(),
+            label: None,
+            uuid: Some(uuid.to_string()),
+            total_space: 0,
+            available_space: 0,
+            is_removable: true,
+            is_network: false,
+        }
+    }
+
+    #[test]
+    fn unplugged_volumes_go_offline_and_come_back() {
+        let now = Utc::now();
+        let (volumes, changes) =
+            reconcile(&[], &[drive("/", "root"), drive("/media/usb", "usb")], now);
+        assert_eq!(changes.added.len(), 2);
+
+        let (volumes, changes) = reconcile(&volumes, &[drive("/", "root")], now);
+        assert_eq!(changes.went_offline.len(), 1);
+        assert!(volume_for(&volumes, Path::new("/media/usb/music")).is_some_and(|v| !v.is_online));
+        assert!(volume_for(&volumes, Path::new("/home/me")).is_some_and(|v| v.is_online));
+
+        let (_, changes) = reconcile(
+            &volumes,
+            &[drive("/", "root"), drive("/media/usb", "usb")],
+            now,
+        );
+        assert_eq!(changes.came_online.len(), 1);
+        assert!(changes.moved.is_empty());
+    }
+
+    #[test]
+    fn remounted_volumes_move() {
+        let now = Utc::now();
+        let (volumes, _) = reconcile(&[], &[drive("/media/usb", "usb")], now);
+        let (volumes, _) = reconcile(&volumes, &[], now);
+        let (volumes, changes) = reconcile(&volumes, &[drive("/media/usb1", "usb")], now);
+
+        assert_eq!(changes.moved.len(), 1);
+        assert_eq!(changes.moved[0].0, "/media/usb");
+        assert_eq!(changes.moved[0].1.mount_path, "/media/usb1");
+        assert_eq!(volumes.len(), 1);
+    }
+
+    #[test]
+    fn online_volume_wins_at_a_shared_mount_path() {
+        let now = Utc::now();
+        let (volumes, _) = reconcile(&[], &[drive("/media/usb", "old")], now);
+        let (volumes, _) = reconcile(&volumes, &[drive("/media/usb", "new")], now);
+
+        let volume = volume_for(&volumes, Path::new("/media/usb/file")).unwrap();
+        assert_eq!(volume.id, "uuid:new");
+    }
+}
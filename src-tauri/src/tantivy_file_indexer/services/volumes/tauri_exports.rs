@@ -0,0 +1,13 @@
+use std::sync::Arc;
+use tauri::State;
+
+use crate::tantivy_file_indexer::services::local_db::tables::volumes::entities::volume;
+
+use super::service::VolumeService;
+
+/// Every volume that files got indexed on, including the ones that are offline. The `volumes_changed` event is emitted
+/// with the same list whenever a volume goes offline, comes online or moves
+#[tauri::command]
+pub fn get_volumes(service: State<'_, Arc<VolumeService>>) -> Vec<volume::Model> {
+    service.get_all()
+}
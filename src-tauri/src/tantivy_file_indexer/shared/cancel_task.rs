@@ -2,6 +2,9 @@ use std::sync::Arc;
 use tokio::sync::{oneshot, watch, RwLock};
 use tokio::task::JoinHandle;
 
+/// The error that `CancellableTask::run` returns when the task gets cancelled
+pub const CANCELLED_MESSAGE: &str = "Task was canceled.";
+
 type AtomicOption<T> = Arc<RwLock<Option<T>>>;
 #[derive(Clone)]
 pub struct CancellableTask {
@@ -26,6 +29,16 @@ impl CancellableTask {
         self.run_internal(task, || {}).await
     }
 
+    /// Same as `run`, but `on_cancel` gets called if the task is cancelled. Dropping the join handle doesn't stop the
+    /// task that it belongs to, so this is how the task itself can be told to stop
+    pub async fn run_with_on_cancel<T, F>(&self, task: JoinHandle<T>, on_cancel: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: Fn() + Send + 'static,
+    {
+        self.run_internal(task, on_cancel).await
+    }
+
     async fn run_internal<T, F>(&self, task: JoinHandle<T>, on_cancel: F) -> Result<T, String>
     where
         T: Send + 'static,
@@ -40,7 +53,7 @@ impl CancellableTask {
             let result = tokio::select! {
                 _ = cancel_rx.changed() => {
                     on_cancel();
-                    Err("Task was canceled.")
+                    Err(CANCELLED_MESSAGE)
                 },
                 res = task => Ok(res),
             };
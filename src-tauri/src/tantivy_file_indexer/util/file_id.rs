@@ -0,0 +1,55 @@
+use std::path::Path;
+
+/// Identifies a file or directory no matter which path it is reached through, such as through a symlink. Made out of the
+/// device and the inode on Unix, and the volume serial number and the file index on Windows
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FileId {
+    device: u64,
+    index: u64,
+}
+
+/// Follows symlinks. Returns `None` if the path can't be read
+#[cfg(windows)]
+pub fn file_id(path: &Path) -> Option<FileId> {
+    use std::{
+        fs::OpenOptions,
+        os::windows::{fs::OpenOptionsExt, io::AsRawHandle},
+    };
+    use winapi::um::fileapi::{GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION};
+
+    /// Needed to open directories
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x02000000;
+
+    // No access rights are needed to read the file information
+    let file = OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .ok()?;
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    // The handle stays open for the duration of the call, since `file` is only dropped afterwards
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+        return None;
+    }
+    Some(FileId {
+        device: info.dwVolumeSerialNumber as u64,
+        index: ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64,
+    })
+}
+
+/// Follows symlinks. Returns `None` if the path can't be read
+#[cfg(unix)]
+pub fn file_id(path: &Path) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(path).ok()?;
+    Some(FileId {
+        device: metadata.dev(),
+        index: metadata.ino(),
+    })
+}
+
+#[cfg(not(any(windows, unix)))]
+pub fn file_id(_path: &Path) -> Option<FileId> {
+    None
+}
@@ -0,0 +1,127 @@
+/// Translates a glob into the body of a regex, without anchors. Paths are expected to be separated with `/`.
+///
+/// - `*` matches anything except `/`
+/// - `?` matches any one character except `/`
+/// - `[abc]`, `[a-z]` and `[!abc]` match one character out of a set
+/// - `**/` at the start of a component matches zero or more directories, and `/**` at the end matches everything inside
+/// - `\` makes the next character literal
+pub fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::with_capacity(glob.len() * 2);
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                // `**` only means "any number of directories" when it is a whole path component
+                let starts_component = i == 0 || chars[i - 1] == '/';
+                match chars.get(i + 2) {
+                    Some('/') if starts_component => {
+                        regex.push_str("(?:.*/)?");
+                        i += 3;
+                    }
+                    None if starts_component => {
+                        regex.push_str(".*");
+                        i += 2;
+                    }
+                    _ => {
+                        regex.push_str("[^/]*");
+                        i += 2;
+                    }
+                }
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => match class_end(&chars, i) {
+                Some(end) => {
+                    regex.push('[');
+                    let mut j = i + 1;
+                    if matches!(chars[j], '!' | '^') {
+                        regex.push('^');
+                        j += 1;
+                    }
+                    for &c in &chars[j..end] {
+                        if matches!(c, '\\' | '[' | '&' | '~') {
+                            regex.push('\\');
+                        }
+                        regex.push(c);
+                    }
+                    regex.push(']');
+                    i = end + 1;
+                    continue;
+                }
+                // A `[` that is never closed is just a character
+                None => regex.push_str(r"\["),
+            },
+            '\\' => {
+                if let Some(&next) = chars.get(i + 1) {
+                    regex.push_str(&regex::escape(&next.to_string()));
+                    i += 2;
+                    continue;
+                }
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    regex
+}
+
+/// Returns the index of the `]` that closes the character class starting at `start`. A `]` right after the opening
+/// `[` (or `[!`) is part of the class
+fn class_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    if matches!(chars.get(i), Some('!' | '^')) {
+        i += 1;
+    }
+    if chars.get(i) == Some(&']') {
+        i += 1;
+    }
+    (i..chars.len()).find(|&i| chars[i] == ']')
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::*;
+
+    fn matches(glob: &str, path: &str) -> bool {
+        Regex::new(&format!("^(?:{})$", glob_to_regex(glob)))
+            .unwrap()
+            .is_match(path)
+    }
+
+    #[test]
+    fn wildcards_stay_inside_of_a_component() {
+        assert!(matches("*.log", "debug.log"));
+        assert!(!matches("*.log", "logs/debug.log"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("file?.txt", "file10.txt"));
+    }
+
+    #[test]
+    fn double_star_matches_directories() {
+        assert!(matches("**/build", "build"));
+        assert!(matches("**/build", "a/b/build"));
+        assert!(matches("src/**", "src/a/b.rs"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(!matches("a**b", "a/b"));
+    }
+
+    #[test]
+    fn character_classes() {
+        assert!(matches("[abc].txt", "b.txt"));
+        assert!(!matches("[!abc].txt", "b.txt"));
+        assert!(matches("[0-9]", "7"));
+        assert!(matches("[", "["));
+    }
+
+    #[test]
+    fn escapes_and_regex_characters_are_literal() {
+        assert!(matches(r"\*.txt", "*.txt"));
+        assert!(!matches(r"\*.txt", "a.txt"));
+        assert!(matches("a+b (1).txt", "a+b (1).txt"));
+    }
+}
@@ -1,4 +1,7 @@
-use std::path::{Component, Path};
+use std::{
+    fs::Metadata,
+    path::{Component, Path, MAIN_SEPARATOR},
+};
 
 /// Get the components of a path
 ///
@@ -25,3 +28,74 @@ pub fn get_path_components(path: &Path) -> Vec<String> {
 
     components
 }
+
+/// Moves a path that is inside of `old_root` to the same place inside of `new_root`. Returns `None` if the path isn't
+/// inside of `old_root`
+pub fn rebase(path: &str, old_root: &str, new_root: &str) -> Option<String> {
+    let relative = Path::new(path).strip_prefix(old_root).ok()?;
+    if relative.as_os_str().is_empty() {
+        return Some(new_root.to_string());
+    }
+    Some(
+        Path::new(new_root)
+            .join(relative)
+            .to_string_lossy()
+            .to_string(),
+    )
+}
+
+/// Ends the path with a separator, so that it only matches the paths inside of it when used as a prefix
+pub fn with_trailing_separator(path: &str) -> String {
+    if path.ends_with(MAIN_SEPARATOR) {
+        path.to_string()
+    } else {
+        format!("{}{}", path, MAIN_SEPARATOR)
+    }
+}
+
+/// Dot files are hidden everywhere, and on Windows, so is anything with the hidden attribute
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn is_hidden(path: &Path, metadata: &Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+            return true;
+        }
+    }
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn rebase_moves_paths_between_roots() {
+        assert_eq!(
+            rebase("/media/usb/music/song.mp3", "/media/usb", "/media/usb1").as_deref(),
+            Some("/media/usb1/music/song.mp3")
+        );
+        assert_eq!(
+            rebase("/media/usb", "/media/usb", "/media/usb1").as_deref(),
+            Some("/media/usb1")
+        );
+        // Only whole components count, so a sibling with a longer name isn't inside of the root
+        assert_eq!(
+            rebase("/media/usb2/file", "/media/usb", "/media/usb1"),
+            None
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn rebase_moves_paths_between_drives() {
+        assert_eq!(
+            rebase("E:\\music\\song.mp3", "E:\\", "F:\\").as_deref(),
+            Some("F:\\music\\song.mp3")
+        );
+    }
+}
@@ -14,6 +14,18 @@ pub fn calculate_alphabetic_noise_ratio(string: &str) -> f64 {
     1.0 - (letter_chars / total_chars)
 }
 
+/// Escapes every character that has a special meaning in a regex, so that `text` only matches itself
+pub fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if r"\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 /*
 cargo test test_string -- --show-output
 */
@@ -28,6 +40,16 @@ mod tests {
         print_rank("C:/$B9D075E1EB7D4CED964EF9FC24A7FD75");
     }
 
+    #[test]
+    fn escape_regex_escapes_special_characters() {
+        assert_eq!(escape_regex("notes"), "notes");
+        assert_eq!(
+            escape_regex(r"C:\Users\me\my.project (old)"),
+            r"C:\\Users\\me\\my\.project \(old\)"
+        );
+        assert_eq!(escape_regex("a+b*c?|[d]{e}^$"), r"a\+b\*c\?\|\[d\]\{e\}\^\$");
+    }
+
     fn print_rank(s: &str) {
         println!("Noise ratio for {}: {}", s, calculate_alphabetic_noise_ratio(s));
     }
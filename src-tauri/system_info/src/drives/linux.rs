@@ -0,0 +1,367 @@
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::models::drive_model::DriveModel;
+
+const MOUNT_INFO_PATH: &str = "/proc/self/mountinfo";
+const LABELS_DIR: &str = "/dev/disk/by-label";
+const UUIDS_DIR: &str = "/dev/disk/by-uuid";
+
+/// File systems that only exist in memory or expose the kernel, so there are no user files on them
+const PSEUDO_FS_TYPES: [&str; 26] = [
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "overlay",
+    "proc",
+    "pstore",
+    "ramfs",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "squashfs",
+    "sysfs",
+    "tmpfs",
+    "tracefs",
+    "fuse.portal",
+];
+
+const NETWORK_FS_TYPES: [&str; 12] = [
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "ncpfs",
+    "afs",
+    "ceph",
+    "glusterfs",
+    "9p",
+    "fuse.sshfs",
+    "fuse.rclone",
+];
+
+/// One line of `/proc/self/mountinfo`
+struct MountInfo {
+    /// The `major:minor` of the device, which is how it can be found in `/sys/dev/block`
+    device: String,
+    /// The directory inside of the file system that is mounted, which is `/` unless it is a bind mount
+    root: String,
+    mount_point: String,
+    fs_type: String,
+    source: String,
+}
+
+pub fn get_system_drives() -> Vec<DriveModel> {
+    let mount_info = match fs::read_to_string(MOUNT_INFO_PATH) {
+        Ok(mount_info) => mount_info,
+        Err(err) => {
+            println!("could not read {}: {}", MOUNT_INFO_PATH, err);
+            return vec![];
+        }
+    };
+    let labels = get_device_names(LABELS_DIR);
+    let uuids = get_device_names(UUIDS_DIR);
+
+    // A file system can be mounted on top of another one, in which case only the last mount is visible
+    let mut drives: Vec<DriveModel> = Vec::new();
+    for mount in mount_info.lines().filter_map(parse_line) {
+        if !is_drive(&mount) {
+            continue;
+        }
+        let Some((total_space, available_space)) = get_space(&mount.mount_point) else {
+            continue;
+        };
+        if total_space == 0 {
+            continue;
+        }
+        let device = fs::canonicalize(&mount.source).ok();
+        let label = device
+            .as_ref()
+            .and_then(|device| labels.get(device).cloned());
+        let uuid = device.and_then(|device| uuids.get(&device).cloned());
+
+        drives.retain(|drive| drive.name != mount.mount_point);
+        drives.push(DriveModel {
+            label,
+            uuid,
+            total_space,
+            available_space,
+            is_removable: is_removable(&mount),
+            is_network: NETWORK_FS_TYPES.contains(&mount.fs_type.as_str()),
+            name: mount.mount_point,
+        });
+    }
+    drives
+}
+
+/// Pseudo file systems hold no user files, and bind mounts only show files that are already on another mount
+fn is_drive(mount: &MountInfo) -> bool {
+    !PSEUDO_FS_TYPES.contains(&mount.fs_type.as_str()) && mount.root == "/"
+}
+
+/// The format is described in `man 5 proc`, under `/proc/pid/mountinfo`:
+///
+/// `36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue`
+fn parse_line(line: &str) -> Option<MountInfo> {
+    let (mount_fields, fs_fields) = line.split_once(" - ")?;
+    let mut mount_fields = mount_fields.split(' ');
+    let device = mount_fields.nth(2)?.to_string();
+    let root = unescape_octal(mount_fields.next()?);
+    let mount_point = unescape_octal(mount_fields.next()?);
+
+    let mut fs_fields = fs_fields.split(' ');
+    let fs_type = fs_fields.next()?.to_string();
+    let source = unescape_octal(fs_fields.next()?);
+
+    Some(MountInfo {
+        device,
+        root,
+        mount_point,
+        fs_type,
+        source,
+    })
+}
+
+/// Spaces, tabs, newlines and backslashes in mountinfo paths are written as octal escapes, such as `\040` for a space
+fn unescape_octal(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match escaped {
+            Some(byte) => {
+                unescaped.push(byte);
+                i += 4;
+            }
+            None => {
+                unescaped.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+/// udev escapes characters such as spaces in the names of `/dev/disk/by-label`, for example as `\x20`
+fn unescape_hex(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 2..i + 4)
+            .filter(|_| bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'x'))
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+        match escaped {
+            Some(byte) => {
+                unescaped.push(byte);
+                i += 4;
+            }
+            None => {
+                unescaped.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+/// Maps each device in one of the `/dev/disk` directories, such as `/dev/sdb1`, to its name in there, which is its
+/// label in `by-label` and its UUID in `by-uuid`
+fn get_device_names(dir: &str) -> HashMap<PathBuf, String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let device = fs::canonicalize(entry.path()).ok()?;
+            let name = unescape_hex(&entry.file_name().to_string_lossy());
+            Some((device, name))
+        })
+        .collect()
+}
+
+/// Returns the total and available space in bytes
+fn get_space(mount_point: &str) -> Option<(u64, u64)> {
+    let path = CString::new(mount_point).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let fragment_size = stat.f_frsize as u64;
+    Some((
+        stat.f_blocks as u64 * fragment_size,
+        stat.f_bavail as u64 * fragment_size,
+    ))
+}
+
+/// The kernel marks removable disks in sysfs. For a partition, the flag is on the disk that it belongs to
+fn is_removable(mount: &MountInfo) -> bool {
+    let block_dir = Path::new("/sys/dev/block").join(&mount.device);
+    let Ok(block_dir) = fs::canonicalize(block_dir) else {
+        return false;
+    };
+    let flag = fs::read_to_string(block_dir.join("removable"))
+        .or_else(|_| fs::read_to_string(block_dir.join("../removable")));
+    matches!(flag.as_deref().map(str::trim), Ok("1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mountinfo_lines_are_parsed() {
+        // (line, device, root, mount point, file system type, source)
+        let cases = [
+            (
+                "28 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw,errors=remount-ro",
+                "8:2",
+                "/",
+                "/",
+                "ext4",
+                "/dev/sda2",
+            ),
+            (
+                "36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue",
+                "98:0",
+                "/mnt1",
+                "/mnt2",
+                "ext3",
+                "/dev/root",
+            ),
+            (
+                "120 28 8:17 / /media/me/My\\040Drive rw,nosuid,nodev,relatime shared:64 master:3 - vfat /dev/sdb1 rw,fmask=0022",
+                "8:17",
+                "/",
+                "/media/me/My Drive",
+                "vfat",
+                "/dev/sdb1",
+            ),
+            (
+                "301 28 8:2 /home/me/Tab\\011and\\134slash /srv/shared rw,relatime - ext4 /dev/sda2 rw",
+                "8:2",
+                "/home/me/Tab\tand\\slash",
+                "/srv/shared",
+                "ext4",
+                "/dev/sda2",
+            ),
+            (
+                "50 28 0:45 / /mnt/nas rw,relatime shared:90 - nfs4 nas:/export rw,vers=4.2",
+                "0:45",
+                "/",
+                "/mnt/nas",
+                "nfs4",
+                "nas:/export",
+            ),
+            (
+                "22 28 0:21 / /proc rw,nosuid,nodev,noexec,relatime shared:12 - proc proc rw",
+                "0:21",
+                "/",
+                "/proc",
+                "proc",
+                "proc",
+            ),
+        ];
+        for (line, device, root, mount_point, fs_type, source) in cases {
+            let mount = parse_line(line).unwrap_or_else(|| panic!("could not parse {}", line));
+            assert_eq!(mount.device, device, "{}", line);
+            assert_eq!(mount.root, root, "{}", line);
+            assert_eq!(mount.mount_point, mount_point, "{}", line);
+            assert_eq!(mount.fs_type, fs_type, "{}", line);
+            assert_eq!(mount.source, source, "{}", line);
+        }
+    }
+
+    #[test]
+    fn malformed_mountinfo_lines_are_skipped() {
+        assert!(parse_line("").is_none());
+        assert!(parse_line("28 1 8:2 / / rw,relatime shared:1").is_none());
+        assert!(parse_line("28 1 - ext4 /dev/sda2 rw").is_none());
+    }
+
+    #[test]
+    fn only_real_file_systems_count_as_drives() {
+        let cases = [
+            (
+                "28 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw",
+                true,
+            ),
+            (
+                "120 28 8:17 / /media/me/My\\040Drive rw shared:64 - vfat /dev/sdb1 rw",
+                true,
+            ),
+            (
+                "50 28 0:45 / /mnt/nas rw,relatime shared:90 - nfs4 nas:/export rw",
+                true,
+            ),
+            (
+                "80 28 0:50 / /mnt/remote rw,nosuid shared:95 - fuse.sshfs me@host:/ rw",
+                true,
+            ),
+            // Bind mounts of a directory
+            (
+                "301 28 8:2 /home/me/shared /srv/shared rw,relatime - ext4 /dev/sda2 rw",
+                false,
+            ),
+            (
+                "22 28 0:21 / /proc rw,nosuid,nodev,noexec,relatime shared:12 - proc proc rw",
+                false,
+            ),
+            (
+                "30 22 0:25 / /run rw,nosuid,nodev,noexec shared:5 - tmpfs tmpfs rw,mode=755",
+                false,
+            ),
+            (
+                "23 28 0:22 / /sys rw,nosuid,nodev,noexec,relatime shared:7 - sysfs sysfs rw",
+                false,
+            ),
+            (
+                "26 23 0:27 / /sys/fs/cgroup rw,nosuid shared:9 - cgroup2 cgroup2 rw",
+                false,
+            ),
+            (
+                "70 28 7:0 / /snap/core/17200 ro,nodev,relatime shared:30 - squashfs /dev/loop0 ro",
+                false,
+            ),
+            (
+                "90 28 0:60 / /var/lib/docker/overlay2/x/merged rw - overlay overlay rw",
+                false,
+            ),
+        ];
+        for (line, expected) in cases {
+            let mount = parse_line(line).unwrap_or_else(|| panic!("could not parse {}", line));
+            assert_eq!(is_drive(&mount), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn udev_names_are_unescaped() {
+        assert_eq!(unescape_hex("My\\x20Drive"), "My Drive");
+        assert_eq!(unescape_hex("Backup"), "Backup");
+        assert_eq!(unescape_hex("Bad\\xZZ"), "Bad\\xZZ");
+    }
+}
@@ -0,0 +1,79 @@
+use std::ffi::OsString;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::ptr;
+use winapi::um::fileapi::{GetDriveTypeW, GetLogicalDriveStringsW, GetVolumeInformationW};
+use winapi::um::winbase::{DRIVE_REMOTE, DRIVE_REMOVABLE};
+
+use crate::models::drive_model::DriveModel;
+
+pub fn get_system_drives() -> Vec<DriveModel> {
+    let mut buffer: [u16; 1024] = [0; 1024];
+    let length = unsafe { GetLogicalDriveStringsW(buffer.len() as u32, buffer.as_mut_ptr()) };
+
+    if length == 0 {
+        println!("no drives found");
+        return vec![];
+    }
+
+    let drive_str = OsString::from_wide(&buffer[..length as usize]);
+    drive_str
+        .to_string_lossy()
+        .split('\0')
+        .filter(|s| !s.is_empty())
+        .map(|x| {
+            let drive_type = get_drive_type(x);
+            let (label, uuid) = get_volume_information(x).unzip();
+            DriveModel {
+                name: x.to_string(),
+                label: label.filter(|label| !label.is_empty()),
+                uuid,
+                total_space: 0,
+                available_space: 0,
+                is_removable: drive_type == DRIVE_REMOVABLE,
+                is_network: drive_type == DRIVE_REMOTE,
+            }
+        })
+        .collect()
+}
+
+fn get_drive_type(root: &str) -> u32 {
+    let wide = to_wide(root);
+    unsafe { GetDriveTypeW(wide.as_ptr()) }
+}
+
+/// Returns the label and the serial number of the volume, or `None` if there is no volume, such as for a card reader
+/// without a card in it
+fn get_volume_information(root: &str) -> Option<(String, String)> {
+    let wide = to_wide(root);
+    let mut label: [u16; 261] = [0; 261];
+    let mut serial_number: u32 = 0;
+    let succeeded = unsafe {
+        GetVolumeInformationW(
+            wide.as_ptr(),
+            label.as_mut_ptr(),
+            label.len() as u32,
+            &mut serial_number,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if succeeded == 0 {
+        return None;
+    }
+    let length = label.iter().position(|&c| c == 0).unwrap_or(label.len());
+    let label = OsString::from_wide(&label[..length])
+        .to_string_lossy()
+        .to_string();
+    // Written the same way as `vol` does, such as `1A2B-3C4D`
+    let serial_number = format!("{:04X}-{:04X}", serial_number >> 16, serial_number & 0xFFFF);
+    Some((label, serial_number))
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    OsString::from(s)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect()
+}
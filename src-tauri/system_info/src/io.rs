@@ -0,0 +1,52 @@
+/// How long the I/O wait is measured over
+#[cfg(target_os = "linux")]
+const SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// The percentage of time that the CPUs spent waiting on disk I/O, which is high when the disks are busy. Like
+/// `get_global_cpu_usage`, 12.5 means 12.5%
+///
+/// Returns `None` on platforms where it can't be measured
+pub async fn get_io_wait_percent() -> Option<f32> {
+    #[cfg(target_os = "linux")]
+    {
+        let before = linux::read_cpu_times()?;
+        tokio::time::sleep(SAMPLE_INTERVAL).await;
+        let after = linux::read_cpu_times()?;
+        let total = after.total.saturating_sub(before.total);
+        if total == 0 {
+            return Some(0.0);
+        }
+        Some(after.io_wait.saturating_sub(before.io_wait) as f32 / total as f32 * 100.0)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    None
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs;
+
+    const STAT_PATH: &str = "/proc/stat";
+
+    /// In clock ticks since boot, summed over every CPU
+    pub struct CpuTimes {
+        pub total: u64,
+        pub io_wait: u64,
+    }
+
+    /// Reads the first line of `/proc/stat`, which looks like `cpu user nice system idle iowait irq softirq ...`
+    pub fn read_cpu_times() -> Option<CpuTimes> {
+        let stat = fs::read_to_string(STAT_PATH).ok()?;
+        let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+        let times: Vec<u64> = line
+            .split_whitespace()
+            .skip(1)
+            .filter_map(|time| time.parse().ok())
+            .collect();
+        Some(CpuTimes {
+            total: times.iter().sum(),
+            io_wait: *times.get(4)?,
+        })
+    }
+}
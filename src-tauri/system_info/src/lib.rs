@@ -1,4 +1,6 @@
 pub mod models;
 pub mod sys_info;
 pub mod drives;
-pub mod cpu;
\ No newline at end of file
+pub mod cpu;
+pub mod io;
+pub mod power;
\ No newline at end of file
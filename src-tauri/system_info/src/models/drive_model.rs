@@ -5,6 +5,13 @@ use serde::{Deserialize, Serialize};
 pub struct DriveModel {
     pub name: String,
     pub label: Option<String>,
+    /// The UUID of the file system on Linux, or the serial number of the volume on Windows. Unlike the name, it stays
+    /// the same when the drive gets mounted somewhere else
+    pub uuid: Option<String>,
     pub total_space: u64,
     pub available_space: u64,
+    /// USB sticks, SD cards and the like, which can disappear at any time
+    pub is_removable: bool,
+    /// Network shares, which are slow to crawl and might not always be reachable
+    pub is_network: bool,
 }
@@ -0,0 +1,71 @@
+/// Returns `Some(true)` if the computer is running on battery, and `None` if there is no way to tell, such as on a
+/// desktop without a battery
+pub fn is_on_battery() -> Option<bool> {
+    #[cfg(windows)]
+    return windows::is_on_battery();
+
+    #[cfg(target_os = "linux")]
+    return linux::is_on_battery();
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    None
+}
+
+#[cfg(windows)]
+mod windows {
+    use winapi::um::winbase::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    pub fn is_on_battery() -> Option<bool> {
+        let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+        if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+            return None;
+        }
+        match status.ACLineStatus {
+            0 => Some(true),
+            1 => Some(false),
+            // 255 means that the status is unknown
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{fs, path::Path};
+
+    const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+    pub fn is_on_battery() -> Option<bool> {
+        let entries = fs::read_dir(POWER_SUPPLY_DIR).ok()?;
+        let mut has_battery = false;
+        let mut is_discharging = false;
+        for entry in entries.flatten() {
+            let supply = entry.path();
+            match read_attribute(&supply, "type").as_deref() {
+                // A charger that is plugged in means that the battery isn't being used, whatever it reports
+                Some("Mains") | Some("USB")
+                    if read_attribute(&supply, "online").as_deref() == Some("1") =>
+                {
+                    return Some(false);
+                }
+                Some("Battery") => {
+                    // Peripherals such as mice report their batteries here too
+                    if read_attribute(&supply, "scope").as_deref() == Some("Device") {
+                        continue;
+                    }
+                    has_battery = true;
+                    is_discharging |=
+                        read_attribute(&supply, "status").as_deref() == Some("Discharging");
+                }
+                _ => {}
+            }
+        }
+        has_battery.then_some(is_discharging)
+    }
+
+    fn read_attribute(supply: &Path, name: &str) -> Option<String> {
+        fs::read_to_string(supply.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+    }
+}
@@ -1,4 +1,9 @@
-import { Component, OnInit, ViewEncapsulation } from "@angular/core";
+import {
+  Component,
+  HostListener,
+  OnInit,
+  ViewEncapsulation,
+} from "@angular/core";
 import { CommonModule } from "@angular/common";
 import { RouterOutlet } from "@angular/router";
 import { IconifyIconModule } from "@shared/components/icons/IconifyIcons/icon.module";
@@ -22,6 +27,10 @@ import { TauriLifecycleService } from "@core/services/tauri/lifecycle.service";
   styleUrl: "./app.component.scss",
 })
 export class AppComponent implements OnInit {
+  /** The crawlers only need to hear about activity every so often, not on every key press */
+  private static readonly UI_ACTIVITY_REPORT_INTERVAL_MS = 5000;
+  private lastUiActivityReport = 0;
+
   constructor(
     private commandsService: TauriCommandsService,
     private configService: PersistentConfigService,
@@ -43,6 +52,7 @@ export class AppComponent implements OnInit {
         "Windows",
       ]);
       await this.configService.update("crawlerExcludeDirectoriesStartingWithPeriod",true)
+      await this.configService.update("crawlerRespectIgnoreFiles",true)
       const d: AddToCrawlerQueueDTO = {
         DirPath: "C:\\",
         Priority: 5,
@@ -51,4 +61,19 @@ export class AppComponent implements OnInit {
     }
     await this.commandsService.dispatchCrawlers();
   }
+
+  @HostListener("document:keydown")
+  @HostListener("document:mousedown")
+  @HostListener("document:wheel")
+  onUiActivity() {
+    const now = Date.now();
+    if (
+      now - this.lastUiActivityReport <
+      AppComponent.UI_ACTIVITY_REPORT_INTERVAL_MS
+    ) {
+      return;
+    }
+    this.lastUiActivityReport = now;
+    this.commandsService.reportUiActivity();
+  }
 }
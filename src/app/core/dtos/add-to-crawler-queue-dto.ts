@@ -1,5 +1,6 @@
 /**
- * Where `priority` is an integer
+ * Where `priority` is an integer, and a lower number is more urgent. `0` is for directories that the user asked for,
+ * which get crawled before any background work
  */
 export interface AddToCrawlerQueueDTO{
     DirPath:string,
@@ -0,0 +1,15 @@
+/**
+ * What gets sent to the backend to add or update an index root
+ */
+export interface IndexRootDTO {
+    Path: string,
+    Enabled: boolean,
+    /** How many directories below the root get crawled, where `null` means that there is no limit */
+    MaxDepth: number | null,
+    /** A lower number gets crawled sooner */
+    Priority: number,
+    /** `null` means that the full refresh interval from the crawler settings is used */
+    RefreshIntervalMinutes: number | null,
+    FollowSymlinks: boolean,
+    IncludeHidden: boolean
+}
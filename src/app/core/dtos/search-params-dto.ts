@@ -10,4 +10,6 @@ export interface SearchParamsDTO {
 
   NumResults: number;
   QueryType: SearchQueryType;
+  /** How long the query may run for, in milliseconds, before partial results get returned */
+  TimeBudgetMs?: number;
 }
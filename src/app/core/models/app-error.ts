@@ -0,0 +1,28 @@
+export type AppErrorCode =
+  | "NotFound"
+  | "PermissionDenied"
+  | "InvalidArgument"
+  | "AlreadyExists"
+  | "InvalidQuery"
+  | "IndexUnavailable"
+  | "DbError"
+  | "Cancelled"
+  | "Internal";
+
+/** What every backend command rejects with */
+export interface AppError {
+  Code: AppErrorCode;
+  Details?: string;
+}
+
+export function isAppError(err: unknown): err is AppError {
+  return typeof err === "object" && err !== null && "Code" in err;
+}
+
+/** Thrown by `SafeInvokeService.invokeSafe`, so that callers can check `code` instead of parsing the message */
+export class AppCommandError extends Error {
+  constructor(public readonly code: AppErrorCode, details?: string) {
+    super(details ?? code);
+    this.name = code;
+  }
+}
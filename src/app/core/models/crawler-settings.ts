@@ -1,3 +1,31 @@
 export interface CrawlerSettingsModel{
   MaxNumCrawlers:number,
-}
\ No newline at end of file
+  /** Index directories again even if they haven't changed since their files were last indexed */
+  ReindexUnchangedDirectories?:boolean,
+  /** Only compare the modified times of directories, which misses files that were changed in place */
+  CompareModifiedTimesOnly?:boolean,
+  /** Don't descend into unchanged directories, which misses changes deeper down */
+  StopAtUnchangedDirectories?:boolean,
+  /** Keep the crawler queue in SQLite instead of in memory, which is slower but uses less memory. Takes effect after a restart */
+  SqliteQueue?:boolean,
+  /** How often the index roots get crawled again, in minutes. Roots with their own refresh interval use that instead */
+  FullRefreshIntervalMinutes?:number,
+  /** Run the crawlers at full speed no matter how busy the computer is */
+  DisableAdaptiveThrottle?:boolean,
+  /** The CPU usage percentage above which the crawlers slow down. Defaults to 50 */
+  ThrottleCpuThresholdPercent?:number,
+  /** The percentage of time spent waiting on the disk above which the crawlers slow down. Defaults to 10 */
+  ThrottleIoWaitThresholdPercent?:number,
+  /** Keep the crawlers at full speed while on battery */
+  FullSpeedOnBattery?:boolean,
+  /** How many seconds the crawlers stay slowed down after the user last used the app. Defaults to 30 */
+  UserIdleSecs?:number,
+  /** How long each crawler rests after each file when slowed down all the way, in milliseconds. Defaults to 200 */
+  MaxFileRestMillis?:number,
+  /** Turn off the background job that removes deleted files from the index and crawls the directories of changed ones */
+  DisableIndexValidator?:boolean,
+  /** How long the index validator waits between going through the whole index, in minutes. Defaults to a day */
+  IndexValidatorIntervalMinutes?:number,
+  /** How many files the index validator checks at a time. Defaults to 500 */
+  IndexValidatorChunkSize?:number,
+}
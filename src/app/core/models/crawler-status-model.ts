@@ -0,0 +1,44 @@
+export interface CrawlerErrorModel {
+    Message: string,
+    /** An ISO date string */
+    Time: string
+}
+
+export interface CrawlerWorkerStatusModel {
+    Id: number,
+    /** `null` if the worker is between directories or waiting for work */
+    CurrentDirectory: string | null,
+    UptimeSecs: number,
+    /** Paused workers have committed everything that they crawled */
+    IsPaused: boolean
+}
+
+/** How much the crawlers are slowed down, and what the decision was based on */
+export interface CrawlerThrottleModel {
+    /** Between 0, which is full speed, and 1, which is as slow as the crawlers go */
+    Level: number,
+    Reason: "None" | "CPU usage" | "Disk I/O" | "On battery" | "User activity" | "Disabled",
+    /** How long each crawler rests after each file */
+    FileRestMillis: number,
+    RecommendedWorkers: number,
+    CpuUsage: number,
+    /** `null` if it isn't known on this platform */
+    IoWait: number | null,
+    /** `null` if it isn't known whether there is a battery */
+    OnBattery: boolean | null,
+    UserActive: boolean
+}
+
+/**
+ * Returned by `crawler_status`, and emitted as the `crawler_status` event every few seconds while the crawlers are running
+ */
+export interface CrawlerStatusModel {
+    FilesPerSecond: number,
+    DirectoriesPerSecond: number,
+    /** The number of directories in the crawler queue for each priority */
+    QueueDepth: Record<number, number>,
+    /** The errors from the last minute, oldest first */
+    RecentErrors: CrawlerErrorModel[],
+    Workers: CrawlerWorkerStatusModel[],
+    Throttle: CrawlerThrottleModel
+}
@@ -1,15 +1,22 @@
 export interface DriveModel{
     Name:string,
     Label:string,
+    /** Stays the same when the drive gets mounted somewhere else. `null` if the drive doesn't have one */
+    Uuid:string | null,
     TotalSpace:number,
     AvailableSpace:number,
+    IsRemovable:boolean,
+    IsNetwork:boolean,
 }
 
 export function defaultDriveModel():DriveModel{
     return {
         Name:"C:",
         Label:"C",
+        Uuid:null,
         TotalSpace:100,
-        AvailableSpace:100
+        AvailableSpace:100,
+        IsRemovable:false,
+        IsNetwork:false
     }
-}
\ No newline at end of file
+}
@@ -9,6 +9,9 @@ export interface FileModel {
     Score: number,
     Popularity: number,
     IsDirectory: boolean,
+    IsSymlink?: boolean,
+    /** Where a symlink points to, as it is written in the link */
+    LinkTarget?: string | null,
 }
 
 export function newDefaultFileModel(): FileModel {
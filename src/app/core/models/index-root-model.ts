@@ -0,0 +1,10 @@
+import { IndexRootDTO } from "@core/dtos/index-root-dto";
+
+/**
+ * A directory that the crawlers index, along with how it gets crawled
+ */
+export interface IndexRootModel extends IndexRootDTO {
+    Id: number,
+    /** An ISO date string, or `null` if the root hasn't been crawled yet */
+    LastCrawledAt: string | null
+}
@@ -0,0 +1,26 @@
+/** What an index validator sweep found */
+export interface IndexValidatorFindingsModel {
+    NumChecked: number,
+    /** Files that no longer exist, which got removed from the index */
+    NumRemoved: number,
+    /** Files that changed since they were indexed, whose directories got crawled again */
+    NumChanged: number,
+    /** Files on drives that aren't connected, which were left alone */
+    NumOffline: number,
+    /** The last few paths that got removed */
+    RecentlyRemoved: string[]
+}
+
+/**
+ * Where the background index validator is at and what it found
+ */
+export interface IndexValidatorModel {
+    /** The path that the current sweep got up to */
+    Cursor: string,
+    /** An ISO date string, or `null` while waiting for the next sweep */
+    SweepStartedAt: string | null,
+    /** An ISO date string */
+    LastSweepFinishedAt: string | null,
+    Current: IndexValidatorFindingsModel,
+    LastSweep: IndexValidatorFindingsModel | null
+}
@@ -0,0 +1,11 @@
+export type PathRuleAction = "Include" | "Exclude";
+
+/**
+ * A glob pattern such as `**\/node_modules`, `/home/*\/.cache/**` or `*.tmp`.
+ * Patterns that start with `/` or a drive letter match the full path, anything else can match at any depth.
+ * Later rules take precedence over earlier ones
+ */
+export interface PathRuleModel {
+  Pattern: string;
+  Action: PathRuleAction;
+}
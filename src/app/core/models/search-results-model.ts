@@ -0,0 +1,7 @@
+import { FileModel } from "./file-model";
+
+export interface SearchResultsModel {
+  Results: FileModel[];
+  /** The query ran out of its time budget, so better matches may have been missed */
+  Partial: boolean;
+}
@@ -0,0 +1,16 @@
+/**
+ * A drive or mounted file system that files got indexed on. It keeps its id when it gets mounted somewhere else
+ */
+export interface VolumeModel {
+    /** `uuid:`, `label:` or `mount:` followed by what the volume is recognized by */
+    Id: string,
+    Label: string | null,
+    /** Where the volume is mounted, or where it was last mounted if it is offline */
+    MountPath: string,
+    /** Offline volumes are unplugged or unreachable. Their files stay in the index until they come back */
+    IsOnline: boolean,
+    IsRemovable: boolean,
+    IsNetwork: boolean,
+    /** An ISO date string */
+    LastSeenAt: string
+}
@@ -0,0 +1,40 @@
+import { Injectable } from "@angular/core";
+import { BehaviorSubject } from "rxjs";
+import { VolumeModel } from "../../models/volume-model";
+import { TauriCommandsService } from "../tauri/commands.service";
+import { replaceBacklashesWithForwardSlashes } from "@shared/util/string";
+
+/**
+ * Keeps track of the volumes that files got indexed on, so that results on drives that aren't connected can be told apart
+ */
+@Injectable({ 'providedIn': 'root' })
+export class VolumeService {
+    private volumesSubject = new BehaviorSubject<VolumeModel[]>([]);
+    public volumes$ = this.volumesSubject.asObservable();
+
+    constructor(private commandsService: TauriCommandsService) {
+        this.refreshVolumes();
+        this.commandsService.listenToVolumesChanged((volumes) => this.volumesSubject.next(volumes));
+    }
+
+    async refreshVolumes() {
+        const volumes = await this.commandsService.getVolumes();
+        this.volumesSubject.next(volumes)
+    }
+
+    /** Whether the path is on a volume that isn't connected. The deepest volume that the path is inside of is the one that it is on */
+    isOffline(path: string, volumes: VolumeModel[] = this.volumesSubject.value): boolean {
+        const normalize = (text: string) => replaceBacklashesWithForwardSlashes(text).toLowerCase();
+        const filePath = normalize(path);
+        let deepest: VolumeModel | undefined;
+        for (const volume of volumes) {
+            const mountPath = normalize(volume.MountPath);
+            const prefix = mountPath.endsWith("/") ? mountPath : `${mountPath}/`;
+            if (filePath !== mountPath && !filePath.startsWith(prefix)) continue;
+            const isDeeper = !deepest || mountPath.length > normalize(deepest.MountPath).length;
+            const isSameButOnline = deepest && mountPath.length === normalize(deepest.MountPath).length && volume.IsOnline;
+            if (isDeeper || isSameButOnline) deepest = volume;
+        }
+        return deepest !== undefined && !deepest.IsOnline;
+    }
+}
@@ -1,4 +1,6 @@
 import { CrawlerSettingsModel } from "@core/models/crawler-settings";
+import { PathRuleModel } from "@core/models/path-rule";
+import { IndexValidatorModel } from "@core/models/index-validator-model";
 import { FileModel } from "../../models/file-model";
 
 /**
@@ -17,9 +19,20 @@ export interface ConfigKeys {
 
   crawlerExcludeDirectoriesStartingWithPeriod: boolean;
   crawlerDirectoryNamesExclude: string[];
+  /** Whether `.gitignore`, `.ignore` and `.supersearcherignore` files are respected when crawling */
+  crawlerRespectIgnoreFiles: boolean;
+  /** Glob patterns that include or exclude paths from being crawled, where later rules take precedence */
+  crawlerPathRules: PathRuleModel[];
+  /** What the crawlers do with symlinks. `Follow` skips symlinks that lead back to a directory above them */
+  crawlerSymlinkPolicy: "Ignore" | "IndexLinkOnly" | "Follow";
   /** NOTE: The extensions should not have a leading dot */
   crawlerWhitelistedExtensions: string[];
   /** NOTE: The extensions should not have a leading dot */
   crawlerBlacklistedExtensions: string[];
   crawlerSettings: CrawlerSettingsModel;
+  /** Written by the backend as the index validator goes through the index */
+  indexValidatorStatus: IndexValidatorModel;
+
+  /** Whether files on drives that aren't connected are left out of search results, instead of being marked as offline */
+  searchHideOfflineVolumes: boolean;
 }
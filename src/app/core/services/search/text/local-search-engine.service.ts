@@ -8,7 +8,8 @@ export class LocalSearchEngineService {
   constructor(private commandsService: TauriCommandsService) {}
 
   async query(params: SearchParamsDTO): Promise<FileModel[]> {
-    return await this.commandsService.searchIndexQuery(params);
+    const response = await this.commandsService.searchIndexQuery(params);
+    return response.Results;
   }
 
 }
@@ -4,16 +4,21 @@ import { GetFilesParamsDTO } from "@core/dtos/get-files-params-dto";
 import { FileModel, newDefaultFileModel } from "../../models/file-model";
 import { InvokeArgs, InvokeOptions } from "@tauri-apps/api/core";
 import { DriveModel } from "../../models/drive-model";
+import { VolumeModel } from "../../models/volume-model";
 import { InlineQueryDTO } from "@core/dtos/inline-query-dto";
 import { SearchParamsDTO } from "@core/dtos/search-params-dto";
 import { StreamingSearchParamsDTO } from "@core/dtos/streaming-search-params-dtos";
 import { AddToCrawlerQueueDTO } from "@core/dtos/add-to-crawler-queue-dto";
 import { IndexedDirModel } from "../../models/indexed-dir-model";
+import { IndexRootDTO } from "@core/dtos/index-root-dto";
+import { IndexRootModel } from "@core/models/index-root-model";
+import { CrawlerStatusModel } from "@core/models/crawler-status-model";
 
 import { SafeInvokeService } from "./safe-invoke.service";
 import { EmitMetadataModel } from "@core/models/emit-metadata-model";
 import { SystemInfoModel } from "@core/models/system-info-model";
 import { KvSubscriptionModel } from "@core/models/kv-subscription-model";
+import { SearchResultsModel } from "@core/models/search-results-model";
 import { GetIconDTO } from "@core/dtos/get-icon-dto";
 import {
   removeNonAlphanumericCharacters,
@@ -191,14 +196,28 @@ export class TauriCommandsService {
       });
   }
 
+  /** The volumes that files got indexed on, including the ones that aren't connected right now */
+  async getVolumes(): Promise<VolumeModel[]> {
+    return await this.invokeSafe<VolumeModel[]>("get_volumes");
+  }
+
+  /** Calls `onChange` with every volume whenever a drive gets plugged in, unplugged or mounted somewhere else */
+  async listenToVolumesChanged(
+    onChange: (volumes: VolumeModel[]) => void
+  ): Promise<UnlistenFn> {
+    return await listen<VolumeModel[]>("volumes_changed", (event) =>
+      onChange(event.payload)
+    );
+  }
+
   async searchFilesInline(query: InlineQueryDTO): Promise<FileModel[]> {
     return this.invokeSafe<FileModel[]>("search_files_inline", {
       query,
     }).then((result) => result);
   }
 
-  async searchIndexQuery(params: SearchParamsDTO): Promise<FileModel[]> {
-    return this.invokeSafe<FileModel[]>("search_index_query", {
+  async searchIndexQuery(params: SearchParamsDTO): Promise<SearchResultsModel> {
+    return this.invokeSafe<SearchResultsModel>("search_index_query", {
       params,
     })
       .then((result) => result)
@@ -251,6 +270,13 @@ export class TauriCommandsService {
     }
   }
 
+  /** Stops the search running on the stream. Searches on other streams are unaffected. `{StreamIdentifier}:search_done` still gets emitted */
+  async cancelSearch(streamIdentifier: string): Promise<boolean> {
+    return await this.invokeSafe<boolean>("cancel_search", {
+      streamIdentifier,
+    });
+  }
+
   async addDirsToCrawlerQueue(directories: AddToCrawlerQueueDTO[]) {
     await this.invokeSafe<void>("add_dirs_to_crawler_queue", { directories })
       .then(() => {})
@@ -274,6 +300,27 @@ export class TauriCommandsService {
     });
   }
 
+  async getIndexRoots(): Promise<IndexRootModel[]> {
+    return await this.invokeSafe<IndexRootModel[]>("get_index_roots");
+  }
+
+  /** The root gets crawled right away if it is enabled */
+  async addIndexRoot(root: IndexRootDTO): Promise<IndexRootModel> {
+    return await this.invokeSafe<IndexRootModel>("add_index_root", { root });
+  }
+
+  async updateIndexRoot(id: number, root: IndexRootDTO): Promise<IndexRootModel> {
+    return await this.invokeSafe<IndexRootModel>("update_index_root", {
+      id,
+      root,
+    });
+  }
+
+  /** What was already indexed from the root stays in the index */
+  async removeIndexRoot(id: number) {
+    await this.invokeSafe<void>("remove_index_root", { id });
+  }
+
   async viewCrawlerPriorityCounts(): Promise<
     Array<{ priority: number; count: number }>
   > {
@@ -292,6 +339,24 @@ export class TauriCommandsService {
     return [];
   }
 
+  async getCrawlerStatus(): Promise<CrawlerStatusModel> {
+    return await this.invokeSafe<CrawlerStatusModel>("crawler_status");
+  }
+
+  /** Lets the crawlers know that the user is using the app, so that they slow down for a while */
+  async reportUiActivity(): Promise<void> {
+    await this.invokeSafe("report_ui_activity");
+  }
+
+  /** Calls `onStatus` whenever the crawlers emit their status, until the returned function is called */
+  async listenToCrawlerStatus(
+    onStatus: (status: CrawlerStatusModel) => void
+  ): Promise<UnlistenFn> {
+    return await listen<CrawlerStatusModel>("crawler_status", (event) =>
+      onStatus(event.payload)
+    );
+  }
+
   async getCrawlerAnalyzerData(): Promise<
     Array<{ label: string; data: string }>
   > {
@@ -435,6 +500,24 @@ export class TauriCommandsService {
     await this.invokeSafe<void>("dispatch_crawlers");
   }
 
+  /** The crawlers commit what they have crawled so far before pausing */
+  async pauseCrawlers() {
+    await this.invokeSafe<void>("pause_crawlers");
+  }
+
+  async resumeCrawlers() {
+    await this.invokeSafe<void>("resume_crawlers");
+  }
+
+  /** Resolves once the crawlers have committed what they crawled. They can be dispatched again afterwards */
+  async stopCrawlers() {
+    await this.invokeSafe<void>("stop_crawlers");
+  }
+
+  async restartCrawlers() {
+    await this.invokeSafe<void>("restart_crawlers");
+  }
+
   /** Tell the directory watcher to stop watching whatever directory it is watching */
   async watchDirectory(path: string): Promise<string> {
     return await this.invokeSafe<string>("watch_directory", { path });
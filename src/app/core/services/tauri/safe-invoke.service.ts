@@ -1,6 +1,7 @@
 import { Injectable, OnDestroy } from "@angular/core";
 import { Subscription } from "rxjs";
 import { invoke, InvokeArgs, InvokeOptions } from "@tauri-apps/api/core";
+import { AppCommandError, isAppError } from "@core/models/app-error";
 
 @Injectable({ providedIn: 'root' })
 export class SafeInvokeService {
@@ -49,9 +50,18 @@ export class SafeInvokeService {
 
     /**
      * Invoke a Tauri command after all state has been initialized.
+     *
+     * Errors from the backend are rethrown as an `AppCommandError`
      */
     async invokeSafe<T>(cmd: string, args?: InvokeArgs, options?: InvokeOptions): Promise<T> {
         await this.ensureBackendInitialized();
-        return invoke<T>(cmd, args, options);
+        try {
+            return await invoke<T>(cmd, args, options);
+        } catch (err) {
+            if (isAppError(err)) {
+                throw new AppCommandError(err.Code, err.Details);
+            }
+            throw err;
+        }
     }
 }
\ No newline at end of file
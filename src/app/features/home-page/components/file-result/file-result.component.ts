@@ -22,6 +22,8 @@ import { rangeToLastPeriod } from "@shared/util/string";
 import { FileIconComponent } from "../file-icon/file-icon.component";
 import { IndexingFilesOverlayService } from "../indexing-files-overlay/indexing-files-overlay.service";
 import { IndexedDirModel } from "@core/models/indexed-dir-model";
+import { VolumeModel } from "@core/models/volume-model";
+import { VolumeService } from "@core/services/files/volume.service";
 import { animate, style, transition, trigger } from "@angular/animations";
 // If you are looking for the drag functionality, it gets handled by the parent component
 // 'files-display' for example
@@ -58,6 +60,7 @@ export class FileResultComponent implements OnInit, DoCheck {
   _isRenaming = false;
   _nameBeforeRename?: string;
   _filesGettingIndexed$ = this.indexingFilesOverlayService.itemsBeingIndexed$;
+  _volumes$ = this.volumeService.volumes$;
 
   @ViewChild("renameInputBox") renameBox!: ElementRef<HTMLInputElement>;
 
@@ -82,6 +85,7 @@ export class FileResultComponent implements OnInit, DoCheck {
   constructor(
     private pinService: PinService,
     private indexingFilesOverlayService: IndexingFilesOverlayService,
+    private volumeService: VolumeService,
     private cdr: ChangeDetectorRef
   ) {}
 
@@ -193,6 +197,10 @@ export class FileResultComponent implements OnInit, DoCheck {
     return indexedFiles.some((x) => x.Path === this.file?.FilePath);
   }
 
+  isOnOfflineVolume(volumes: VolumeModel[]): boolean {
+    return !!this.file && this.volumeService.isOffline(this.file.FilePath, volumes);
+  }
+
   get animationState(): string {
     if (!this.file || !this.doAnimate) return "void";
     // Create a unique state based on the file's data